@@ -4,29 +4,171 @@
 //! and creating output ZIP files with proper progress tracking.
 
 use crate::error::{Result, ResultExt, TransJlcError};
+use crate::progress::{BYTE_PROGRESS_TEMPLATE, FILE_PROGRESS_TEMPLATE};
 use anyhow::Context;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::HashSet;
 use std::fs;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use tempfile::TempDir;
-use tracing::info;
+use tracing::{debug, info, warn};
 use zip::ZipArchive;
 
+/// Input path that stands for a ZIP archive piped through stdin
+pub const STDIN_PATH: &str = "-";
+
+/// Signature of a ZIP local file header
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Signature of the end-of-central-directory record of an empty ZIP
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+
+/// File names that are never PCB data (OS metadata and thumbnail caches)
+const JUNK_FILE_NAMES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
+
+/// Check whether a path is OS-generated junk that should never be processed
+///
+/// Covers macOS `__MACOSX/` resource-fork folders, `.DS_Store`/`Thumbs.db`
+/// style metadata files and hidden dotfiles.
+pub fn is_junk_path(path: &Path) -> bool {
+    let in_macosx_folder = path
+        .components()
+        .any(|component| component.as_os_str() == "__MACOSX");
+
+    let junk_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            name.starts_with('.')
+                || JUNK_FILE_NAMES
+                    .iter()
+                    .any(|junk| junk.eq_ignore_ascii_case(name))
+        });
+
+    in_macosx_folder || junk_name
+}
+
+/// Check whether a path is an ODB++ export rather than Gerber files
+///
+/// Matches `.tgz` archives as written by Altium, and folders holding the ODB++
+/// `matrix/matrix` file or `steps/` folder directly or inside a single job folder.
+pub fn is_odb_layout(path: &Path) -> bool {
+    if path.is_file() {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        return name.ends_with(".tgz") || name.ends_with(".tar.gz");
+    }
+
+    let is_job_dir =
+        |dir: &Path| dir.join("matrix").join("matrix").is_file() || dir.join("steps").is_dir();
+    if is_job_dir(path) {
+        return true;
+    }
+
+    fs::read_dir(path).is_ok_and(|entries| {
+        entries
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.path().is_dir() && is_job_dir(&entry.path()))
+    })
+}
+
+/// Strip trailing spaces and dots from every component of an archive entry name
+///
+/// Windows cannot represent such names and some archivers leave them in, which
+/// breaks extension-based matching (`project-F_Cu.gbr ` never matches `.gbr$`).
+pub fn trim_entry_name(name: &str) -> String {
+    name.split('/')
+        .map(|component| {
+            let trimmed = component.trim_end_matches([' ', '.']);
+            if trimmed.is_empty() {
+                component
+            } else {
+                trimmed
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Turn an archive entry name into a relative path that stays inside the target folder
+///
+/// Absolute names and `..` components would let a crafted archive write
+/// anywhere on disk (zip-slip), so they are refused. Backslashes count as
+/// separators, as written by some Windows archivers.
+pub fn sanitize_entry_name(name: &str) -> Result<PathBuf> {
+    let unsafe_entry = |why: &str| TransJlcError::ZipExtractionFailed {
+        reason: format!("Refusing archive entry '{}': {}", name, why),
+    };
+
+    let normalized = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                return Err(unsafe_entry("path leaves the extraction folder").into())
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_entry("absolute path").into())
+            }
+        }
+    }
+
+    Ok(path)
+}
+
 /// Archive extractor for handling ZIP input files
 pub struct ArchiveExtractor {
     temp_dir: Option<TempDir>,
+    /// Free space to keep on the extraction volume beyond the archive contents (bytes)
+    min_free_space: u64,
+    /// Whether to trim trailing spaces/dots from extracted entry names
+    trim_names: bool,
+    /// Whether to drop folders and extract every file into the top directory
+    flatten: bool,
 }
 
 impl ArchiveExtractor {
     /// Create a new archive extractor
     pub fn new() -> Self {
-        Self { temp_dir: None }
+        Self {
+            temp_dir: None,
+            min_free_space: 0,
+            trim_names: false,
+            flatten: false,
+        }
+    }
+
+    /// Configure the free space that must remain after extraction
+    pub fn with_min_free_space(mut self, bytes: u64) -> Self {
+        self.min_free_space = bytes;
+        self
+    }
+
+    /// Configure trimming of trailing spaces/dots from extracted entry names
+    pub fn with_trim_names(mut self, trim: bool) -> Self {
+        self.trim_names = trim;
+        self
+    }
+
+    /// Configure extraction of nested entries into the top directory
+    pub fn with_flatten(mut self, flatten: bool) -> Self {
+        self.flatten = flatten;
+        self
     }
 
     /// Extract ZIP file if the input path is a ZIP file
     /// Returns the path to use for processing (original path or extracted directory)
     pub fn extract_if_needed(&mut self, input_path: &Path, show_progress: bool) -> Result<PathBuf> {
+        if input_path == Path::new(STDIN_PATH) {
+            info!("Reading ZIP archive from stdin");
+            return self.extract_from_reader(io::stdin().lock(), show_progress);
+        }
+
         if !self.is_zip_file(input_path) {
             info!(
                 "Input is not a ZIP file, using as directory: {}",
@@ -54,6 +196,43 @@ impl ArchiveExtractor {
         Ok(extracted_path)
     }
 
+    /// Extract a ZIP archive read from a stream (e.g. stdin)
+    ///
+    /// The stream is buffered in memory since ZIP needs random access to its
+    /// central directory. Returns the extraction directory.
+    pub fn extract_from_reader<R: io::Read>(
+        &mut self,
+        mut reader: R,
+        show_progress: bool,
+    ) -> Result<PathBuf> {
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .context("Failed to read ZIP stream")?;
+
+        if !data.starts_with(ZIP_MAGIC) && !data.starts_with(EMPTY_ZIP_MAGIC) {
+            return Err(TransJlcError::ZipExtractionFailed {
+                reason: "Input stream is not a ZIP archive".to_string(),
+            }
+            .into());
+        }
+
+        let temp_dir =
+            TempDir::new().context("Failed to create temporary directory for ZIP extraction")?;
+        let extracted_path = temp_dir.path().to_path_buf();
+
+        let archive = ZipArchive::new(io::Cursor::new(data)).map_err(|e| {
+            TransJlcError::ZipExtractionFailed {
+                reason: format!("Invalid ZIP stream: {}", e),
+            }
+        })?;
+        self.extract_archive(archive, &extracted_path, show_progress)?;
+        self.temp_dir = Some(temp_dir);
+
+        info!("ZIP stream extracted to: {}", extracted_path.display());
+        Ok(extracted_path)
+    }
+
     /// Check if a file is a ZIP file based on extension
     fn is_zip_file(&self, path: &Path) -> bool {
         path.is_file()
@@ -73,20 +252,31 @@ impl ArchiveExtractor {
     ) -> Result<()> {
         let file = fs::File::open(zip_path).with_path_context("open ZIP file", zip_path)?;
 
-        let mut archive =
-            ZipArchive::new(file).map_err(|e| TransJlcError::ZipExtractionFailed {
-                reason: format!("Invalid ZIP file: {}", e),
-            })?;
+        let archive = ZipArchive::new(file).map_err(|e| TransJlcError::ZipExtractionFailed {
+            reason: format!("Invalid ZIP file: {}", e),
+        })?;
+
+        self.extract_archive(archive, target_dir, show_progress)
+    }
 
+    /// Extract every non-junk entry of an opened archive into the target directory
+    fn extract_archive<R: io::Read + io::Seek>(
+        &self,
+        mut archive: ZipArchive<R>,
+        target_dir: &Path,
+        show_progress: bool,
+    ) -> Result<()> {
         let total_files = archive.len();
         info!("Extracted {} entries from archive", total_files);
 
+        self.check_free_space(&mut archive, target_dir)?;
+
         let progress = if show_progress {
             let pb = ProgressBar::new(total_files as u64);
             pb.set_style(
                 ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")?
-                    .progress_chars("#>-")
+                    .template(FILE_PROGRESS_TEMPLATE)?
+                    .progress_chars("#>-"),
             );
             pb.set_message("Extracting files...");
             Some(pb)
@@ -94,6 +284,7 @@ impl ArchiveExtractor {
             None
         };
 
+        let mut flattened_names = HashSet::new();
         for i in 0..archive.len() {
             let mut file = archive
                 .by_index(i)
@@ -101,9 +292,44 @@ impl ArchiveExtractor {
                     reason: format!("Failed to read file at index {}: {}", i, e),
                 })?;
 
-            let outpath = target_dir.join(file.name());
+            if is_junk_path(Path::new(file.name())) {
+                debug!("Skipping junk archive entry: {}", file.name());
+                if let Some(ref pb) = progress {
+                    pb.inc(1);
+                }
+                continue;
+            }
+
+            let entry_name = if self.trim_names {
+                trim_entry_name(file.name())
+            } else {
+                file.name().to_string()
+            };
+            let mut outpath = target_dir.join(sanitize_entry_name(&entry_name)?);
+
+            if self.flatten && !file.is_dir() {
+                let name = outpath.file_name().unwrap_or_default().to_os_string();
+                if !flattened_names.insert(name.clone()) {
+                    warn!(
+                        "Skipping {}: another archive entry has the same file name",
+                        file.name()
+                    );
+                    if let Some(ref pb) = progress {
+                        pb.inc(1);
+                    }
+                    continue;
+                }
+                outpath = target_dir.join(name);
+            }
 
             if file.is_dir() {
+                // Flattened extraction has no use for the folder structure
+                if self.flatten {
+                    if let Some(ref pb) = progress {
+                        pb.inc(1);
+                    }
+                    continue;
+                }
                 fs::create_dir_all(&outpath).with_path_context("create directory", &outpath)?;
             } else {
                 // Create parent directories if needed
@@ -132,10 +358,51 @@ impl ArchiveExtractor {
         Ok(())
     }
 
+    /// Fail early when the target volume cannot hold the uncompressed archive
+    fn check_free_space<R: io::Read + io::Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        target_dir: &Path,
+    ) -> Result<()> {
+        let mut uncompressed: u64 = 0;
+        for i in 0..archive.len() {
+            if let Ok(file) = archive.by_index_raw(i) {
+                uncompressed = uncompressed.saturating_add(file.size());
+            }
+        }
+
+        let required = uncompressed.saturating_add(self.min_free_space);
+        let available =
+            fs2::available_space(target_dir).with_path_context("query free space", target_dir)?;
+
+        debug!(
+            "Extraction needs {} bytes, {} bytes available",
+            required, available
+        );
+
+        if available < required {
+            return Err(TransJlcError::InsufficientDiskSpace {
+                path: target_dir.display().to_string(),
+                required,
+                available,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
     /// Get the temporary directory path if ZIP was extracted
     pub fn temp_path(&self) -> Option<&Path> {
         self.temp_dir.as_ref().map(|dir| dir.path())
     }
+
+    /// Remove the extraction directory now instead of when the extractor is dropped
+    pub fn cleanup(&mut self) {
+        if self.temp_dir.take().is_some() {
+            info!("Cleaning up temporary extraction directory");
+        }
+    }
 }
 
 impl Drop for ArchiveExtractor {
@@ -146,6 +413,61 @@ impl Drop for ArchiveExtractor {
     }
 }
 
+/// Compression method for output ZIP entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Store entries uncompressed
+    #[default]
+    Stored,
+    /// Compress entries with deflate
+    Deflate,
+}
+
+impl Compression {
+    /// Convert to the matching ZIP compression method
+    fn method(self) -> zip::CompressionMethod {
+        match self {
+            Compression::Stored => zip::CompressionMethod::Stored,
+            Compression::Deflate => zip::CompressionMethod::Deflated,
+        }
+    }
+}
+
+/// Settings controlling how output ZIP files are written
+#[derive(Debug, Clone, Default)]
+pub struct ZipSettings {
+    pub compression: Compression,
+    /// Compression level, only meaningful for deflate
+    pub compression_level: Option<i64>,
+    /// Use a fixed modification time and sorted entry order
+    pub deterministic: bool,
+}
+
+impl ZipSettings {
+    /// Create default settings (stored, no compression)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the compression method
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the compression level
+    pub fn with_compression_level(mut self, level: Option<i64>) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// Produce byte-identical archives for identical inputs
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+}
+
 /// Archive creator for building output ZIP files
 pub struct ArchiveCreator;
 
@@ -155,34 +477,41 @@ impl ArchiveCreator {
         files: I,
         output_path: P,
         show_progress: bool,
+    ) -> Result<()> {
+        Self::create_zip_with_settings(files, output_path, show_progress, &ZipSettings::default())
+    }
+
+    /// Create a ZIP file from a collection of files using explicit settings
+    pub fn create_zip_with_settings<P: AsRef<Path>, I: IntoIterator<Item = P>>(
+        files: I,
+        output_path: P,
+        show_progress: bool,
+        settings: &ZipSettings,
     ) -> Result<()> {
         let output_path = output_path.as_ref();
-        let files: Vec<PathBuf> = files
+        let mut files: Vec<PathBuf> = files
             .into_iter()
             .map(|p| p.as_ref().to_path_buf())
             .collect();
 
-        info!("Creating archive: {}", output_path.display());
-
-        // Create output directory if it doesn't exist
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent).with_path_context("create output directory", parent)?;
+        if settings.deterministic {
+            files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
         }
 
-        let file =
-            fs::File::create(output_path).with_path_context("create ZIP file", output_path)?;
-
-        let mut zip = zip::ZipWriter::new(file);
-        let options = zip::write::SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::Stored)
-            .unix_permissions(0o755);
+        let mut zip = ZipStream::create(output_path, settings)?;
 
         let progress = if show_progress {
-            let pb = ProgressBar::new(files.len() as u64);
+            // Weight by file size so large copper layers are reflected in the ETA
+            let total_bytes: u64 = files
+                .iter()
+                .filter_map(|path| fs::metadata(path).ok())
+                .map(|meta| meta.len())
+                .sum();
+            let pb = ProgressBar::new(total_bytes);
             pb.set_style(
                 ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")?
-                    .progress_chars("#>-")
+                    .template(BYTE_PROGRESS_TEMPLATE)?
+                    .progress_chars("#>-"),
             );
             pb.set_message("Creating ZIP file...");
             Some(pb)
@@ -196,30 +525,180 @@ impl ArchiveCreator {
                 .and_then(|name| name.to_str())
                 .context("Invalid filename")?;
 
-            zip.start_file(file_name, options)
-                .context("Failed to start ZIP file entry")?;
-
             let content =
                 fs::read(&file_path).with_path_context("read file for ZIP", &file_path)?;
-
-            use std::io::Write;
-            zip.write_all(&content)
-                .context("Failed to write file content to ZIP")?;
+            zip.add(file_name, &content)?;
 
             if let Some(ref pb) = progress {
-                pb.inc(1);
+                pb.inc(content.len() as u64);
             }
         }
 
-        zip.finish().context("Failed to finalize ZIP file")?;
+        zip.finish()?;
 
         if let Some(pb) = progress {
             pb.finish_with_message("ZIP file created successfully");
         }
 
-        info!("ZIP file created successfully: {}", output_path.display());
         Ok(())
     }
+
+    /// Create a ZIP file from `(name, content)` pairs, writing each as it is produced
+    ///
+    /// Nothing is staged on disk, so `entries` may generate the contents lazily.
+    /// Returns the name and size of every written entry.
+    pub fn create_zip_from_contents<I: IntoIterator<Item = (String, String)>>(
+        entries: I,
+        output_path: &Path,
+        settings: &ZipSettings,
+    ) -> Result<Vec<(String, u64)>> {
+        let mut zip = ZipStream::create(output_path, settings)?;
+        for (name, content) in entries {
+            zip.add(&name, content.as_bytes())?;
+        }
+        zip.finish()
+    }
+
+    /// Re-open a written archive and check every file made it in intact
+    ///
+    /// Each expected file must have an entry with its file name and size.
+    pub fn verify_zip<P: AsRef<Path>>(zip_path: &Path, expected: &[P]) -> Result<()> {
+        let mut entries = Vec::new();
+        for path in expected {
+            let path = path.as_ref();
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .context("Invalid filename")?;
+            let size = fs::metadata(path)
+                .with_path_context("read metadata of", path)?
+                .len();
+            entries.push((name.to_string(), size));
+        }
+
+        Self::verify_zip_entries(zip_path, &entries)
+    }
+
+    /// Re-open a written archive and check it holds every `(name, size)` entry
+    pub fn verify_zip_entries(zip_path: &Path, expected: &[(String, u64)]) -> Result<()> {
+        let file = fs::File::open(zip_path).with_path_context("open ZIP file", zip_path)?;
+        let mut archive = ZipArchive::new(file)
+            .with_context(|| format!("Failed to read back ZIP file {}", zip_path.display()))?;
+
+        let mut problems = Vec::new();
+        for (name, expected_size) in expected {
+            let expected_size = *expected_size;
+            match archive.by_name(name) {
+                Ok(entry) if entry.size() == expected_size => {}
+                Ok(entry) => problems.push(format!(
+                    "{} ({} bytes, expected {})",
+                    name,
+                    entry.size(),
+                    expected_size
+                )),
+                Err(_) => problems.push(format!("{} (missing)", name)),
+            }
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!(
+                "ZIP file {} is incomplete: {}",
+                zip_path.display(),
+                problems.join(", ")
+            );
+        }
+
+        debug!(
+            "Verified {} entries in {}",
+            expected.len(),
+            zip_path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Output ZIP written entry by entry, so contents never touch the disk on their own
+pub struct ZipStream {
+    path: PathBuf,
+    zip: zip::ZipWriter<fs::File>,
+    options: zip::write::SimpleFileOptions,
+    entries: Vec<(String, u64)>,
+}
+
+impl ZipStream {
+    /// Create the archive at `output_path`, creating its directory as needed
+    pub fn create(output_path: &Path, settings: &ZipSettings) -> Result<Self> {
+        info!("Creating archive: {}", output_path.display());
+
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent).with_path_context("create output directory", parent)?;
+        }
+
+        let file =
+            fs::File::create(output_path).with_path_context("create ZIP file", output_path)?;
+
+        let mut options = zip::write::SimpleFileOptions::default()
+            .compression_method(settings.compression.method())
+            .compression_level(match settings.compression {
+                Compression::Stored => None,
+                Compression::Deflate => settings.compression_level,
+            })
+            .unix_permissions(0o755);
+        if settings.deterministic {
+            // The DOS epoch (1980-01-01 00:00:00)
+            options = options.last_modified_time(zip::DateTime::default());
+        }
+
+        Ok(Self {
+            path: output_path.to_path_buf(),
+            zip: zip::ZipWriter::new(file),
+            options,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Write one entry to the archive
+    pub fn add(&mut self, name: &str, content: &[u8]) -> Result<()> {
+        use std::io::Write;
+
+        self.zip
+            .start_file(name, self.options)
+            .context("Failed to start ZIP file entry")?;
+        self.zip
+            .write_all(content)
+            .context("Failed to write file content to ZIP")?;
+
+        debug!("Added {} to {}", name, self.path.display());
+        self.entries.push((name.to_string(), content.len() as u64));
+        Ok(())
+    }
+
+    /// Whether an entry with this name has already been written
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|(entry, _)| entry == name)
+    }
+
+    /// Path of the archive being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Finalize the archive, returning the name and size of every entry
+    pub fn finish(self) -> Result<Vec<(String, u64)>> {
+        self.zip.finish().context("Failed to finalize ZIP file")?;
+
+        info!("ZIP file created successfully: {}", self.path.display());
+        Ok(self.entries)
+    }
+
+    /// Abandon the archive and remove the partially written file
+    pub fn discard(self) {
+        let Self { path, zip, .. } = self;
+        drop(zip);
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Failed to remove partial ZIP {}: {}", path.display(), e);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -241,6 +720,225 @@ mod tests {
         assert!(txt_path.extension().unwrap() != "zip");
     }
 
+    #[test]
+    fn test_is_junk_path() {
+        assert!(is_junk_path(Path::new(".DS_Store")));
+        assert!(is_junk_path(Path::new("thumbs.db")));
+        assert!(is_junk_path(Path::new("__MACOSX/._Gerber_TopLayer.GTL")));
+        assert!(is_junk_path(Path::new("gerbers/.hidden")));
+
+        assert!(!is_junk_path(Path::new("Gerber_TopLayer.GTL")));
+        assert!(!is_junk_path(Path::new("gerbers/project-F_Cu.gbr")));
+    }
+
+    #[test]
+    fn test_is_odb_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let gerbers = dir.path().join("gerbers");
+        fs::create_dir(&gerbers).unwrap();
+        fs::write(gerbers.join("project-F_Cu.gbr"), "M02*\n").unwrap();
+        assert!(!is_odb_layout(&gerbers));
+
+        // A job folder nested one level down, as unpacked from an archive
+        let job = dir.path().join("odb").join("board");
+        fs::create_dir_all(job.join("matrix")).unwrap();
+        fs::write(job.join("matrix").join("matrix"), "STEP {\n}\n").unwrap();
+        assert!(is_odb_layout(&job));
+        assert!(is_odb_layout(&dir.path().join("odb")));
+
+        let tgz = dir.path().join("board.TGZ");
+        fs::write(&tgz, [0x1f, 0x8b]).unwrap();
+        assert!(is_odb_layout(&tgz));
+    }
+
+    #[test]
+    fn test_trim_entry_name() {
+        assert_eq!(trim_entry_name("project-F_Cu.gbr "), "project-F_Cu.gbr");
+        assert_eq!(
+            trim_entry_name("gerbers. /board.GTL.."),
+            "gerbers/board.GTL"
+        );
+        assert_eq!(trim_entry_name("../board.GTL"), "../board.GTL");
+    }
+
+    #[test]
+    fn test_trimmed_entry_matches_layer_pattern() {
+        use crate::patterns::{LayerType, PatternMatcher};
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("input.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        writer
+            .start_file(
+                "project-F_Cu.gbr ",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(b"G04 test*\nM02*\n").unwrap();
+        writer.finish().unwrap();
+
+        let target = dir.path().join("extracted");
+        fs::create_dir_all(&target).unwrap();
+        ArchiveExtractor::new()
+            .with_trim_names(true)
+            .extract_zip_to_directory(&zip_path, &target, false)
+            .unwrap();
+
+        let names: Vec<String> = fs::read_dir(&target)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["project-F_Cu.gbr".to_string()]);
+        assert_eq!(
+            PatternMatcher::create_kicad_patterns().match_filename(&names[0]),
+            Some(LayerType::TopCopper)
+        );
+    }
+
+    #[test]
+    fn test_flatten_extracts_nested_entries_to_top_level() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("input.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("gerbers/", options).unwrap();
+        writer
+            .start_file("gerbers/project-F_Cu.gbr", options)
+            .unwrap();
+        writer.write_all(b"G04 first*\nM02*\n").unwrap();
+        writer
+            .start_file("backup/project-F_Cu.gbr", options)
+            .unwrap();
+        writer.write_all(b"G04 second*\nM02*\n").unwrap();
+        writer.finish().unwrap();
+
+        let mut extractor = ArchiveExtractor::new().with_flatten(true);
+        let extracted = extractor.extract_if_needed(&zip_path, false).unwrap();
+
+        let names: Vec<String> = fs::read_dir(&extracted)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["project-F_Cu.gbr".to_string()]);
+        // The first entry wins a name collision
+        assert_eq!(
+            fs::read_to_string(extracted.join("project-F_Cu.gbr")).unwrap(),
+            "G04 first*\nM02*\n"
+        );
+    }
+
+    #[test]
+    fn test_free_space_check_fails_before_extraction() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("input.zip");
+        let input = dir.path().join("Gerber_TopLayer.GTL");
+        fs::write(&input, "G04 test*\nM02*\n").unwrap();
+        ArchiveCreator::create_zip([&input], &zip_path, false).unwrap();
+
+        let target = dir.path().join("extracted");
+        fs::create_dir_all(&target).unwrap();
+
+        let extractor = ArchiveExtractor::new().with_min_free_space(u64::MAX / 2);
+        let err = extractor
+            .extract_zip_to_directory(&zip_path, &target, false)
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::InsufficientDiskSpace { .. })
+        ));
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_extract_from_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("input.zip");
+        let input = dir.path().join("Gerber_TopLayer.GTL");
+        fs::write(&input, "G04 test*\nM02*\n").unwrap();
+        ArchiveCreator::create_zip([&input], &zip_path, false).unwrap();
+
+        let mut extractor = ArchiveExtractor::new();
+        let extracted = extractor
+            .extract_from_reader(io::Cursor::new(fs::read(&zip_path).unwrap()), false)
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(extracted.join("Gerber_TopLayer.GTL")).unwrap(),
+            "G04 test*\nM02*\n"
+        );
+        assert_eq!(extractor.temp_path(), Some(extracted.as_path()));
+    }
+
+    #[test]
+    fn test_sanitize_entry_name() {
+        assert_eq!(
+            sanitize_entry_name("gerbers/./project-F_Cu.gbr").unwrap(),
+            PathBuf::from("gerbers/project-F_Cu.gbr")
+        );
+        assert_eq!(
+            sanitize_entry_name("gerbers\\project-F_Cu.gbr").unwrap(),
+            PathBuf::from("gerbers/project-F_Cu.gbr")
+        );
+
+        for name in [
+            "../evil.gbr",
+            "gerbers/../../evil.gbr",
+            "..\\evil.gbr",
+            "/etc/evil",
+        ] {
+            let err = sanitize_entry_name(name).unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<TransJlcError>(),
+                Some(TransJlcError::ZipExtractionFailed { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_extraction_refuses_traversal_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut buffer = io::Cursor::new(Vec::new());
+        {
+            use std::io::Write;
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("project-F_Cu.gbr", options).unwrap();
+            zip.write_all(b"G04 test*\nM02*\n").unwrap();
+            zip.start_file("../../escaped.gbr", options).unwrap();
+            zip.write_all(b"G04 evil*\nM02*\n").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let target = dir.path().join("nested").join("extract");
+        fs::create_dir_all(&target).unwrap();
+        let err = ArchiveExtractor::new()
+            .extract_archive(ZipArchive::new(buffer).unwrap(), &target, false)
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::ZipExtractionFailed { .. })
+        ));
+        assert!(err.to_string().contains("../../escaped.gbr"));
+        assert!(!dir.path().join("escaped.gbr").exists());
+    }
+
+    #[test]
+    fn test_extract_from_reader_rejects_non_zip() {
+        let err = ArchiveExtractor::new()
+            .extract_from_reader(io::Cursor::new(b"G04 not a zip*\n".to_vec()), false)
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::ZipExtractionFailed { .. })
+        ));
+    }
+
     #[test]
     fn test_archive_creator_options() {
         let _options = zip::write::SimpleFileOptions::default()
@@ -251,4 +949,81 @@ mod tests {
         // The actual compression method can be verified in integration tests
         assert!(true); // Placeholder for actual verification
     }
+
+    #[test]
+    fn test_verify_zip_checks_every_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let top = dir.path().join("Gerber_TopLayer.GTL");
+        let bottom = dir.path().join("Gerber_BottomLayer.GBL");
+        fs::write(&top, "G04 top*\nM02*\n").unwrap();
+        fs::write(&bottom, "G04 bottom*\nM02*\n").unwrap();
+        let zip_path = dir.path().join("Gerber.zip");
+
+        ArchiveCreator::create_zip([&top, &bottom], &zip_path, false).unwrap();
+        ArchiveCreator::verify_zip(&zip_path, &[&top, &bottom]).unwrap();
+
+        // A file that never made it in, or changed since, is reported
+        let outline = dir.path().join("Gerber_BoardOutlineLayer.GKO");
+        fs::write(&outline, "M02*\n").unwrap();
+        fs::write(&bottom, "G04 bottom, rewritten*\nM02*\n").unwrap();
+        let err = ArchiveCreator::verify_zip(&zip_path, &[&top, &bottom, &outline]).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Gerber_BoardOutlineLayer.GKO (missing)"));
+        assert!(message.contains("Gerber_BottomLayer.GBL (17 bytes, expected 28)"));
+        assert!(!message.contains("Gerber_TopLayer.GTL"));
+    }
+
+    #[test]
+    fn test_zip_from_contents_is_written_lazily() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("Gerber.zip");
+
+        let produced = std::cell::Cell::new(0);
+        let entries = ["Gerber_TopLayer.GTL", "Gerber_BottomLayer.GBL"]
+            .into_iter()
+            .map(|name| {
+                produced.set(produced.get() + 1);
+                (name.to_string(), format!("G04 {}*\nM02*\n", name))
+            });
+        let written =
+            ArchiveCreator::create_zip_from_contents(entries, &zip_path, &ZipSettings::new())
+                .unwrap();
+
+        assert_eq!(produced.get(), 2);
+        assert_eq!(written[0], ("Gerber_TopLayer.GTL".to_string(), 30));
+        ArchiveCreator::verify_zip_entries(&zip_path, &written).unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_deflate_archive_is_smaller_than_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("Gerber_TopLayer.GTL");
+        fs::write(&input, "X1000000Y1000000D01*\n".repeat(2000)).unwrap();
+
+        let stored = dir.path().join("stored.zip");
+        let deflated = dir.path().join("deflated.zip");
+
+        ArchiveCreator::create_zip_with_settings([&input], &stored, false, &ZipSettings::new())
+            .unwrap();
+        ArchiveCreator::create_zip_with_settings(
+            [&input],
+            &deflated,
+            false,
+            &ZipSettings::new()
+                .with_compression(Compression::Deflate)
+                .with_compression_level(Some(9)),
+        )
+        .unwrap();
+
+        let stored_len = fs::metadata(&stored).unwrap().len();
+        let deflated_len = fs::metadata(&deflated).unwrap().len();
+        assert!(deflated_len < stored_len);
+
+        let mut archive = ZipArchive::new(fs::File::open(&deflated).unwrap()).unwrap();
+        assert_eq!(
+            archive.by_index(0).unwrap().compression(),
+            zip::CompressionMethod::Deflated
+        );
+    }
 }
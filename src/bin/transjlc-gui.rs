@@ -2,12 +2,29 @@ use eframe::egui;
 use std::path::{Path, PathBuf};
 use std::thread;
 use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::fs;
+use rust_embed::RustEmbed;
 
 // 引用 TransJLC 本地库的核心逻辑
-use TransJLC::{Config, Converter};
+use TransJLC::{ConversionEvent, Config, Converter};
+use TransJLC::doctor::DoctorReport;
 
 fn main() -> eframe::Result<()> {
+    // --doctor: 检查运行环境后直接退出，不打开窗口
+    if std::env::args().skip(1).any(|arg| arg == "--doctor") {
+        let mut report = DoctorReport::run();
+        report.check("CJK font found", || {
+            load_system_font()
+            .or_else(load_embedded_font)
+            .map(|_| ())
+            .ok_or_else(|| anyhow::anyhow!("no system CJK font and no font in GuiAssets/fonts/"))
+        });
+        print!("{}", report);
+        std::process::exit(if report.is_healthy() { 0 } else { 1 });
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
         .with_inner_size([680.0, 560.0])     // 默认大小
@@ -43,12 +60,22 @@ fn configure_custom_style(ctx: &egui::Context) {
 }
 
 // --- 🔤 字体配置 ---
+// 找不到系统 CJK 字体时使用的内置字体（放在 GuiAssets/fonts/ 下）
+#[derive(RustEmbed)]
+#[folder = "GuiAssets/fonts/"]
+struct FallbackFont;
+
 fn setup_custom_fonts(ctx: &egui::Context) {
     let mut fonts = egui::FontDefinitions::default();
 
+    // 没有可用字体时保留 egui 默认字体，避免加载空字体数据
+    let Some(font_data) = load_system_font().or_else(load_embedded_font) else {
+        return;
+    };
+
     fonts.font_data.insert(
         "cjk_font".to_owned(),
-                           egui::FontData::from_owned(load_system_font()).tweak(
+                           egui::FontData::from_owned(font_data).tweak(
                                egui::FontTweak { scale: 1.25, ..Default::default() }
                            ),
     );
@@ -59,24 +86,56 @@ fn setup_custom_fonts(ctx: &egui::Context) {
     ctx.set_fonts(fonts);
 }
 
-fn load_system_font() -> Vec<u8> {
-    let font_paths = [
-        "/usr/share/fonts/noto/NotoSansCJK-Regular.ttc",
-        "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
-        "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
-        "/usr/share/fonts/wenquanyi/wqy-zenhei/wqy-zenhei.ttc",
-        "/usr/share/fonts/adobe-source-han-sans/SourceHanSansCN-Regular.otf",
-    ];
+fn system_font_paths() -> &'static [&'static str] {
+    if cfg!(target_os = "windows") {
+        &[
+            r"C:\Windows\Fonts\msyh.ttc",
+            r"C:\Windows\Fonts\msyh.ttf",
+            r"C:\Windows\Fonts\Deng.ttf",
+            r"C:\Windows\Fonts\simhei.ttf",
+            r"C:\Windows\Fonts\simsun.ttc",
+        ]
+    } else if cfg!(target_os = "macos") {
+        &[
+            "/System/Library/Fonts/PingFang.ttc",
+            "/System/Library/Fonts/STHeiti Light.ttc",
+            "/System/Library/Fonts/STHeiti Medium.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/Library/Fonts/Arial Unicode.ttf",
+        ]
+    } else {
+        &[
+            "/usr/share/fonts/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+            "/usr/share/fonts/wenquanyi/wqy-zenhei/wqy-zenhei.ttc",
+            "/usr/share/fonts/adobe-source-han-sans/SourceHanSansCN-Regular.otf",
+        ]
+    }
+}
 
-    for path_str in font_paths {
+fn load_system_font() -> Option<Vec<u8>> {
+    for path_str in system_font_paths() {
         let path = Path::new(path_str);
         if path.exists() {
             if let Ok(data) = fs::read(path) {
-                return data;
+                return Some(data);
             }
         }
     }
-    Vec::new()
+    None
+}
+
+fn load_embedded_font() -> Option<Vec<u8>> {
+    FallbackFont::iter()
+        .filter(|name| {
+            let name = name.to_ascii_lowercase();
+            name.ends_with(".ttf") || name.ends_with(".otf") || name.ends_with(".ttc")
+        })
+        .find_map(|name| FallbackFont::get(&name))
+        .map(|file| file.data.into_owned())
 }
 
 fn load_icon() -> eframe::egui::IconData {
@@ -91,11 +150,14 @@ struct MyApp {
     eda_type: String,
     zip_enabled: bool,
     zip_name: String,
+    top_color_image: Option<PathBuf>,
+    bottom_color_image: Option<PathBuf>,
     status_message: String,
     status_type: StatusType,
     is_processing: bool,
-    rx: Receiver<String>,
-    tx: Sender<String>,
+    rx: Receiver<ConversionEvent>,
+    tx: Sender<ConversionEvent>,
+    cancel_flag: Arc<AtomicBool>,
 }
 
 #[derive(PartialEq)]
@@ -114,28 +176,22 @@ impl Default for MyApp {
             eda_type: "auto".to_string(),
             zip_enabled: true,
             zip_name: "Gerber".to_string(),
+            top_color_image: None,
+            bottom_color_image: None,
             status_message: "等待任务...".to_string(),
             status_type: StatusType::Info,
             is_processing: false,
             rx,
             tx,
+            cancel_flag: Arc::new(AtomicBool::new(false)),
         }
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Ok(msg) = self.rx.try_recv() {
-            self.status_message = msg.clone();
-            if msg.contains("成功") {
-                self.status_type = StatusType::Success;
-                self.is_processing = false;
-            } else if msg.contains("失败") || msg.contains("Error") {
-                self.status_type = StatusType::Error;
-                self.is_processing = false;
-            } else {
-                self.status_type = StatusType::Info;
-            }
+        while let Ok(event) = self.rx.try_recv() {
+            self.handle_event(event);
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -223,6 +279,11 @@ impl eframe::App for MyApp {
                             ui.selectable_value(&mut self.eda_type, "kicad".to_string(), "KiCad");
                             ui.selectable_value(&mut self.eda_type, "protel".to_string(), "Protel / Altium");
                             ui.selectable_value(&mut self.eda_type, "jlc".to_string(), "JLC 标准格式");
+                            ui.selectable_value(&mut self.eda_type, "easyeda".to_string(), "EasyEDA 标准版");
+                            ui.selectable_value(&mut self.eda_type, "pads".to_string(), "PADS");
+                            ui.selectable_value(&mut self.eda_type, "allegro".to_string(), "Allegro");
+                            ui.selectable_value(&mut self.eda_type, "diptrace".to_string(), "DipTrace");
+                            ui.selectable_value(&mut self.eda_type, "proteus".to_string(), "Proteus");
                         });
                         ui.end_row();
 
@@ -235,6 +296,14 @@ impl eframe::App for MyApp {
                             }
                         });
                         ui.end_row();
+
+                        ui.label("彩色丝印 (顶层):");
+                        color_image_picker(ui, &mut self.top_color_image);
+                        ui.end_row();
+
+                        ui.label("彩色丝印 (底层):");
+                        color_image_picker(ui, &mut self.bottom_color_image);
+                        ui.end_row();
                     });
                 });
             });
@@ -246,6 +315,14 @@ impl eframe::App for MyApp {
                 if self.is_processing {
                     ui.add(egui::Spinner::new().size(32.0));
                     ui.label(egui::RichText::new("正在转换...").size(14.0));
+                    ui.add_space(5.0);
+
+                    let cancel_requested = self.cancel_flag.load(Ordering::Relaxed);
+                    let cancel_btn = egui::Button::new(if cancel_requested { "正在取消..." } else { "⏹ 取消" })
+                    .min_size(egui::vec2(120.0, 30.0));
+                    if ui.add_enabled(!cancel_requested, cancel_btn).clicked() {
+                        self.cancel_flag.store(true, Ordering::Relaxed);
+                    }
                 } else {
                     let btn = egui::Button::new(
                         egui::RichText::new("🚀 开始转换")
@@ -285,6 +362,40 @@ impl eframe::App for MyApp {
 }
 
 impl MyApp {
+    fn handle_event(&mut self, event: ConversionEvent) {
+        match event {
+            ConversionEvent::Started => {
+                self.status_message = "正在转换...".to_string();
+                self.status_type = StatusType::Info;
+            }
+            ConversionEvent::FileMatched { source, .. } => {
+                let name = source.file_name().unwrap_or_default().to_string_lossy();
+                self.status_message = format!("已识别: {}", name);
+                self.status_type = StatusType::Info;
+            }
+            ConversionEvent::LayerWritten { path, .. } => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                self.status_message = format!("已写入: {}", name);
+                self.status_type = StatusType::Info;
+            }
+            ConversionEvent::Finished(stats) => {
+                self.status_message = format!("转换成功！共处理 {} 个文件。", stats.total_files_processed);
+                self.status_type = StatusType::Success;
+                self.is_processing = false;
+            }
+            ConversionEvent::Failed(message) => {
+                self.status_message = format!("转换失败: {}", message);
+                self.status_type = StatusType::Error;
+                self.is_processing = false;
+            }
+            ConversionEvent::Cancelled => {
+                self.status_message = "转换已取消".to_string();
+                self.status_type = StatusType::Info;
+                self.is_processing = false;
+            }
+        }
+    }
+
     fn start_conversion(&mut self) {
         self.is_processing = true;
         self.status_message = "初始化...".to_string();
@@ -298,22 +409,52 @@ impl MyApp {
             zip_name: self.zip_name.clone(),
             verbose: true,
             no_progress: true,
-            top_color_image: None,
-            bottom_color_image: None,
+            top_color_image: self.top_color_image.clone(),
+            bottom_color_image: self.bottom_color_image.clone(),
+            ..Default::default()
         };
 
         let tx = self.tx.clone();
+        // 每个任务使用新的取消标志，避免上一次的取消影响本次转换
+        self.cancel_flag = Arc::new(AtomicBool::new(false));
+        let cancel_flag = Arc::clone(&self.cancel_flag);
 
         thread::spawn(move || {
-            let mut converter = Converter::new(config);
-            match converter.run() {
-                Ok(_) => { let _ = tx.send("转换成功！文件已保存。".to_string()); }
-                Err(e) => { let _ = tx.send(format!("转换失败: {:#}", e)); }
-            }
+            // 结果通过 ConversionEvent 通知界面
+            let mut converter = Converter::new(config)
+            .with_event_sender(tx)
+            .with_cancel_flag(cancel_flag);
+            let _ = converter.run();
         });
     }
 }
 
+/// 彩色丝印图片选择：选择 / 清除按钮以及当前文件名
+fn color_image_picker(ui: &mut egui::Ui, image: &mut Option<PathBuf>) {
+    ui.horizontal(|ui| {
+        if ui.button("🖼 选择图片").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+            .add_filter("图片", &["png", "jpg", "jpeg", "gif", "bmp", "svg"])
+            .pick_file()
+            {
+                *image = Some(path);
+            }
+        }
+        match image {
+            Some(path) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                ui.label(egui::RichText::new(name).monospace());
+                if ui.small_button("✖").on_hover_text("不生成该层彩色丝印").clicked() {
+                    *image = None;
+                }
+            }
+            None => {
+                ui.label(egui::RichText::new("未启用").weak());
+            }
+        }
+    });
+}
+
 fn smart_truncate_path(path: &Path, available_width: f32) -> String {
     let text = path.to_string_lossy().to_string();
     let max_chars = (available_width / 9.0) as usize;
@@ -0,0 +1,269 @@
+//! Concurrent conversion of several boards
+//!
+//! Every board gets its own `Converter` (and with it its own extraction and
+//! staging directories) and writes into a subfolder of the output path named
+//! after the board. Boards are handed out to a bounded set of worker threads.
+//! They come either from repeated `--board` options or, with `--batch`, from
+//! the ZIP archives and folders inside the input directory.
+
+use crate::{
+    config::Config,
+    converter::{ConversionStats, Converter},
+    error::{Result, ResultExt},
+};
+use anyhow::{anyhow, bail};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Mutex,
+    thread,
+};
+use tracing::{info, info_span};
+
+/// One board to convert
+#[derive(Debug, Clone)]
+pub struct BoardJob {
+    /// Board name, used for the output subfolder and log prefix
+    pub name: String,
+    /// Configuration for this board alone
+    pub config: Config,
+}
+
+/// Outcome of converting one board
+#[derive(Debug)]
+pub struct BoardResult {
+    pub name: String,
+    pub output_path: PathBuf,
+    pub result: Result<ConversionStats>,
+}
+
+/// Build one job per `--board` input, each writing to `output_path/<board name>`
+pub fn board_jobs(config: &Config) -> Vec<BoardJob> {
+    jobs_for_inputs(config, &config.boards)
+}
+
+/// Build one job per ZIP archive or folder inside the `--batch` input directory
+///
+/// Like `--board` jobs, each writes its own `--zip-path` archive suffixed
+/// with the board name.
+pub fn batch_jobs(config: &Config) -> Result<Vec<BoardJob>> {
+    let inputs = batch_inputs(&config.path, &config.output_path)?;
+    if inputs.is_empty() {
+        bail!(
+            "No ZIP archives or board folders found in {}",
+            config.path.display()
+        );
+    }
+
+    Ok(jobs_for_inputs(config, &inputs))
+}
+
+/// ZIP archives and folders directly inside a batch directory, sorted by name
+///
+/// The output directory is skipped so that converting `.` into `./output`
+/// does not pick up previous results as a board.
+fn batch_inputs(dir: &Path, output_path: &Path) -> Result<Vec<PathBuf>> {
+    let output_path = output_path.canonicalize().ok();
+
+    if !dir.is_dir() {
+        bail!("--batch expects a directory, got {}", dir.display());
+    }
+
+    let mut inputs: Vec<PathBuf> = fs::read_dir(dir)
+        .with_path_context("read batch directory", dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| output_path.is_none() || path.canonicalize().ok() != output_path)
+        .filter(|path| {
+            path.is_dir()
+                || path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        })
+        .collect();
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Build one job per input path, deriving unique board names from the paths
+pub(crate) fn jobs_for_inputs(config: &Config, inputs: &[PathBuf]) -> Vec<BoardJob> {
+    let mut used_names = HashSet::new();
+
+    inputs
+        .iter()
+        .map(|input| {
+            let base_name = board_name(input);
+            let mut name = base_name.clone();
+            let mut suffix = 2;
+            while !used_names.insert(name.clone()) {
+                name = format!("{}-{}", base_name, suffix);
+                suffix += 1;
+            }
+
+            let mut board_config = config.clone();
+            board_config.path = input.clone();
+            board_config.output_path = config.output_path.join(&name);
+            // Boards convert concurrently, so each needs its own archive
+            board_config.zip_path = config.suffixed_zip_path(&name);
+            board_config.boards.clear();
+            board_config.batch = false;
+            board_config.list_output = None;
+            // Interleaved progress bars from several boards are unreadable
+            board_config.no_progress = true;
+
+            BoardJob {
+                name,
+                config: board_config,
+            }
+        })
+        .collect()
+}
+
+/// Name of a board derived from its input folder or archive
+fn board_name(input: &Path) -> String {
+    input
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty() && *stem != ".")
+        .unwrap_or("board")
+        .to_string()
+}
+
+/// Number of worker threads to use when none is configured
+pub fn default_thread_count() -> usize {
+    thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
+
+/// Convert all boards using at most `threads` worker threads
+///
+/// Results are returned in job order regardless of completion order.
+pub fn convert_boards(jobs: Vec<BoardJob>, threads: usize) -> Vec<BoardResult> {
+    let worker_count = threads.clamp(1, jobs.len().max(1));
+    info!(
+        "Converting {} boards with {} worker threads",
+        jobs.len(),
+        worker_count
+    );
+
+    let next_job = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<BoardResult>>> =
+        Mutex::new((0..jobs.len()).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..worker_count {
+            scope.spawn(|| loop {
+                let index = next_job.fetch_add(1, Ordering::SeqCst);
+                let Some(job) = jobs.get(index) else {
+                    break;
+                };
+
+                let result = convert_board(job);
+                if let Ok(mut results) = results.lock() {
+                    results[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_default()
+        .into_iter()
+        .zip(&jobs)
+        .map(|(result, job)| {
+            result.unwrap_or_else(|| BoardResult {
+                name: job.name.clone(),
+                output_path: job.config.output_path.clone(),
+                result: Err(anyhow!("Board conversion did not complete")),
+            })
+        })
+        .collect()
+}
+
+/// Convert a single board, prefixing its log lines with the board name
+fn convert_board(job: &BoardJob) -> BoardResult {
+    let span = info_span!("board", name = %job.name);
+    let _guard = span.enter();
+
+    info!("Converting {}", job.config.path.display());
+    let mut converter = Converter::new(job.config.clone());
+    let result = converter.run().map(|()| converter.get_conversion_stats());
+
+    BoardResult {
+        name: job.name.clone(),
+        output_path: job.config.output_path.clone(),
+        result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_board_jobs_get_unique_output_folders() {
+        let config = Config {
+            output_path: PathBuf::from("out"),
+            boards: vec![
+                PathBuf::from("boards/alpha.zip"),
+                PathBuf::from("other/alpha"),
+                PathBuf::from("boards/beta"),
+            ],
+            ..Default::default()
+        };
+
+        let jobs = board_jobs(&config);
+        let names: Vec<&str> = jobs.iter().map(|job| job.name.as_str()).collect();
+
+        assert_eq!(names, vec!["alpha", "alpha-2", "beta"]);
+        assert_eq!(jobs[1].config.output_path, PathBuf::from("out/alpha-2"));
+        assert_eq!(jobs[1].config.path, PathBuf::from("other/alpha"));
+        assert!(jobs.iter().all(|job| job.config.boards.is_empty()));
+        assert!(jobs.iter().all(|job| job.config.zip_path.is_none()));
+
+        let config = Config {
+            zip_path: Some(PathBuf::from("dist/gerbers.zip")),
+            ..config
+        };
+        let zip_paths: Vec<PathBuf> = board_jobs(&config)
+            .iter()
+            .filter_map(|job| job.config.zip_archive_path())
+            .collect();
+        assert_eq!(
+            zip_paths,
+            vec![
+                PathBuf::from("dist/gerbers-alpha.zip"),
+                PathBuf::from("dist/gerbers-alpha-2.zip"),
+                PathBuf::from("dist/gerbers-beta.zip"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_batch_inputs_are_zips_and_folders() {
+        let dir = tempfile::tempdir().expect("Failed to create batch dir");
+        fs::write(dir.path().join("b.zip"), b"").expect("Failed to write zip");
+        fs::write(dir.path().join("a.ZIP"), b"").expect("Failed to write zip");
+        fs::write(dir.path().join("notes.txt"), b"").expect("Failed to write notes");
+        fs::create_dir(dir.path().join("c")).expect("Failed to create folder");
+        fs::create_dir(dir.path().join("output")).expect("Failed to create output");
+
+        let names: Vec<String> = batch_inputs(dir.path(), &dir.path().join("output"))
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.ZIP", "b.zip", "c"]);
+
+        let config = Config {
+            path: dir.path().join("notes.txt"),
+            ..Default::default()
+        };
+        assert!(batch_jobs(&config).is_err());
+    }
+}
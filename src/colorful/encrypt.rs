@@ -4,7 +4,7 @@ use aes_gcm::{
     aes::Aes128,
     AesGcm,
 };
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use rand::{rngs::OsRng, RngCore};
 use rsa::pkcs8::DecodePublicKey;
 use rsa::{Oaep, RsaPublicKey};
@@ -13,6 +13,10 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+/// Length of the RSA-wrapped AES key and IV at the start of every encrypted
+/// file: two 256-byte blocks from the 2048-bit key JLC decrypts with
+pub(crate) const ENCRYPTED_HEADER_LEN: usize = 512;
+
 pub(crate) struct KeyMaterial {
     aes_key: [u8; 16],
     aes_iv: [u8; 16],
@@ -36,6 +40,17 @@ impl KeyMaterial {
             .encrypt(&mut OsRng, Oaep::new::<Sha256>(), &aes_iv)
             .context("Encrypt AES IV")?;
 
+        // The reader splits the header at fixed offsets, so any other key size
+        // would produce files that cannot be decrypted
+        let header_len = enc_key.len() + enc_iv.len();
+        if header_len != ENCRYPTED_HEADER_LEN {
+            bail!(
+                "RSA key produces a {}-byte encrypted header, expected {} bytes (a 2048-bit key)",
+                header_len,
+                ENCRYPTED_HEADER_LEN
+            );
+        }
+
         Ok(Self {
             aes_key,
             aes_iv,
@@ -45,6 +60,12 @@ impl KeyMaterial {
     }
 }
 
+/// Size in bytes of the encrypted file `encrypt_and_write` produces for `svg`
+pub(crate) fn encrypted_len(svg: &str, key_material: &KeyMaterial) -> u64 {
+    // RSA-wrapped key and IV, then ciphertext followed by the 16-byte GCM tag
+    (key_material.enc_key.len() + key_material.enc_iv.len() + svg.len() + 16) as u64
+}
+
 pub(crate) fn encrypt_and_write(
     svg: &str,
     key_material: &KeyMaterial,
@@ -68,3 +89,35 @@ pub(crate) fn encrypt_and_write(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1024-bit key, whose wrapped key and IV are only 128 bytes each
+    const RSA_1024_PUB_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDiWYfLDxTfDIkroAglpcveqabI
+atPgRlluQ23X5tTD5xjVFy4OoWoZN66GqKkG78taQ96o2UcVOS4A34gt32YNuZ8P
+EQcIW6mPNuEvDzKmde9M2plA413NbAA6fMeSXMHadEQAAhSoVnsC7eCIyAFcjc+6
+u23Iqomi6J/5HLB2/QIDAQAB
+-----END PUBLIC KEY-----";
+
+    #[test]
+    fn test_header_size_matches_embedded_key() {
+        let key_material = KeyMaterial::generate(crate::colorful::RSA_PUB_KEY).unwrap();
+
+        assert_eq!(
+            key_material.enc_key.len() + key_material.enc_iv.len(),
+            ENCRYPTED_HEADER_LEN
+        );
+    }
+
+    #[test]
+    fn test_smaller_key_is_rejected() {
+        let err = KeyMaterial::generate(RSA_1024_PUB_KEY)
+            .err()
+            .expect("A 1024-bit key must not be accepted");
+
+        assert!(err.to_string().contains("256-byte encrypted header"));
+    }
+}
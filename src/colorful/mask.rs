@@ -1,7 +1,12 @@
 use super::types::{mm_to_mil_10, MaskPaths};
 use anyhow::{anyhow, Result};
 use gerber_parser::{gerber_types::*, parse};
+use std::collections::HashMap;
 use std::io::{BufReader, Cursor};
+use tracing::warn;
+
+/// Line segments used to approximate a full circle when tracing region arcs
+pub const DEFAULT_ARC_SEGMENTS: usize = 64;
 
 fn coords_to_mm(coords: &Option<Coordinates>, last: (f64, f64), units: Unit) -> (f64, f64) {
     let mut x = last.0;
@@ -25,6 +30,91 @@ fn coords_to_mm(coords: &Option<Coordinates>, last: (f64, f64), units: Unit) ->
     (x, y)
 }
 
+fn offset_to_mm(offset: &Option<CoordinateOffset>, units: Unit) -> (f64, f64) {
+    let scale = if matches!(units, Unit::Inches) {
+        25.4
+    } else {
+        1.0
+    };
+    let Some(offset) = offset else {
+        return (0.0, 0.0);
+    };
+    let i: f64 = offset.x.map(Into::into).unwrap_or(0.0);
+    let j: f64 = offset.y.map(Into::into).unwrap_or(0.0);
+    (i * scale, j * scale)
+}
+
+/// Points (in mm, excluding the start) approximating a circular arc
+///
+/// `segments_per_turn` sets the resolution; every arc gets at least two
+/// segments. In single-quadrant mode the offset signs are implicit, so the
+/// center that gives a consistent radius and a sweep of at most 90° is used.
+fn linearize_arc(
+    start: (f64, f64),
+    end: (f64, f64),
+    offset: (f64, f64),
+    clockwise: bool,
+    single_quadrant: bool,
+    segments_per_turn: usize,
+) -> Vec<(f64, f64)> {
+    use std::f64::consts::{FRAC_PI_2, TAU};
+
+    let sweep_for = |center: (f64, f64)| {
+        let a0 = (start.1 - center.1).atan2(start.0 - center.0);
+        let a1 = (end.1 - center.1).atan2(end.0 - center.0);
+        let mut sweep = a1 - a0;
+        if clockwise {
+            if sweep >= 0.0 {
+                sweep -= TAU;
+            }
+        } else if sweep <= 0.0 {
+            sweep += TAU;
+        }
+        (a0, sweep)
+    };
+    let radius_error = |center: (f64, f64)| {
+        let r0 = (start.0 - center.0).hypot(start.1 - center.1);
+        let r1 = (end.0 - center.0).hypot(end.1 - center.1);
+        (r0 - r1).abs()
+    };
+
+    let center = if single_quadrant {
+        let (i, j) = (offset.0.abs(), offset.1.abs());
+        [(i, j), (-i, j), (i, -j), (-i, -j)]
+            .into_iter()
+            .map(|(di, dj)| (start.0 + di, start.1 + dj))
+            .filter(|center| sweep_for(*center).1.abs() <= FRAC_PI_2 + 1e-6)
+            .min_by(|a, b| radius_error(*a).total_cmp(&radius_error(*b)))
+            .unwrap_or((start.0 + offset.0, start.1 + offset.1))
+    } else {
+        (start.0 + offset.0, start.1 + offset.1)
+    };
+
+    let (a0, mut sweep) = sweep_for(center);
+    // In single-quadrant mode coincident endpoints mean a zero-length arc
+    if single_quadrant && sweep.abs() > FRAC_PI_2 + 1e-6 {
+        sweep = 0.0;
+    }
+
+    let r0 = (start.0 - center.0).hypot(start.1 - center.1);
+    let r1 = (end.0 - center.0).hypot(end.1 - center.1);
+    let steps = ((sweep.abs() / TAU * segments_per_turn as f64).ceil() as usize).max(2);
+
+    let mut points: Vec<(f64, f64)> = (1..steps)
+        .map(|step| {
+            let t = step as f64 / steps as f64;
+            let angle = a0 + sweep * t;
+            let radius = r0 + (r1 - r0) * t;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect();
+    points.push(end);
+    points
+}
+
 fn to_svg_space(x_mm: f64, y_mm: f64) -> (f64, f64) {
     (mm_to_mil_10(x_mm), -mm_to_mil_10(y_mm))
 }
@@ -56,15 +146,303 @@ fn path_from_region(points: &[(f64, f64)]) -> Option<String> {
     Some(d)
 }
 
-fn aperture_bbox(ap: &Aperture) -> Option<(f64, f64)> {
+/// Path of a flashed aperture in its real shape
+///
+/// Circles and obrounds are drawn with arcs so round pads are not over-carved;
+/// other macros are drawn as the bounding rectangle of their dark primitives.
+fn flash_path(ap: &Aperture, macro_bounds: Option<Bounds>, center: (f64, f64)) -> Option<String> {
     match ap {
-        Aperture::Circle(c) => Some((c.diameter, c.diameter)),
-        Aperture::Rectangle(r) | Aperture::Obround(r) => Some((r.x, r.y)),
+        Aperture::Circle(c) => Some(circle_path(center, c.diameter)),
+        Aperture::Rectangle(r) => Some(rect_path(center, r.x, r.y)),
+        Aperture::Obround(r) => Some(obround_path(center, r.x, r.y)),
+        Aperture::Polygon(p) => {
+            let (cx, cy) = center;
+            let points: Vec<(f64, f64)> = polygon_vertices(p)
+                .into_iter()
+                .map(|(x, y)| (cx + mm_to_mil_10(x), cy - mm_to_mil_10(y)))
+                .collect();
+            path_from_region(&points)
+        }
         Aperture::Macro(name, args) if name == "RoundRect" => {
-            parse_round_rect_macro(args.as_deref())
+            parse_round_rect_macro(args.as_deref()).map(|(w, h)| rect_path(center, w, h))
+        }
+        Aperture::Macro(..) => macro_bounds.map(|(min_x, min_y, max_x, max_y)| {
+            let (cx, cy) = center;
+            let offset = (
+                cx + mm_to_mil_10((min_x + max_x) / 2.0),
+                cy - mm_to_mil_10((min_y + max_y) / 2.0),
+            );
+            rect_path(offset, max_x - min_x, max_y - min_y)
+        }),
+    }
+}
+
+fn circle_path(center: (f64, f64), d_mm: f64) -> String {
+    let (cx, cy) = center;
+    let r = mm_to_mil_10(d_mm) / 2.0;
+    let x0 = cx - r;
+    let x1 = cx + r;
+    format!("M {x0} {cy} A {r} {r} 0 1 1 {x1} {cy} A {r} {r} 0 1 1 {x0} {cy} Z ")
+}
+
+/// Stadium shape: a rectangle with semicircular caps on its short sides
+fn obround_path(center: (f64, f64), w_mm: f64, h_mm: f64) -> String {
+    if (w_mm - h_mm).abs() < f64::EPSILON {
+        return circle_path(center, w_mm);
+    }
+
+    let (cx, cy) = center;
+    let hw = mm_to_mil_10(w_mm) / 2.0;
+    let hh = mm_to_mil_10(h_mm) / 2.0;
+
+    if w_mm > h_mm {
+        let r = hh;
+        let (x0, x1) = (cx - hw + r, cx + hw - r);
+        let (y0, y1) = (cy - r, cy + r);
+        format!(
+            "M {x0} {y0} L {x1} {y0} A {r} {r} 0 0 1 {x1} {y1} L {x0} {y1} A {r} {r} 0 0 1 {x0} {y0} Z "
+        )
+    } else {
+        let r = hw;
+        let (x0, x1) = (cx - r, cx + r);
+        let (y0, y1) = (cy - hh + r, cy + hh - r);
+        format!(
+            "M {x1} {y0} L {x1} {y1} A {r} {r} 0 0 1 {x0} {y1} L {x0} {y0} A {r} {r} 0 0 1 {x1} {y0} Z "
+        )
+    }
+}
+
+/// Vertices of a regular polygon aperture in mm, relative to its center
+fn polygon_vertices(p: &Polygon) -> Vec<(f64, f64)> {
+    let radius = p.diameter / 2.0;
+    let vertices = p.vertices.max(3) as usize;
+    let rotation = p.rotation.unwrap_or(0.0).to_radians();
+
+    (0..vertices)
+        .map(|i| {
+            let angle = rotation + std::f64::consts::TAU * i as f64 / vertices as f64;
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Extent of a shape as `(min_x, min_y, max_x, max_y)`
+type Bounds = (f64, f64, f64, f64);
+
+/// Bounding box of the dark primitives of an aperture macro
+///
+/// Only the parameters that size a primitive are used, and every primitive is
+/// rotated about the macro origin by its own angle. Returns `None` when a
+/// parameter cannot be evaluated, so callers skip the flash instead of
+/// guessing its size.
+fn macro_bounds(def: &ApertureMacro, args: &[MacroDecimal]) -> Option<Bounds> {
+    let mut vars: HashMap<u32, f64> = HashMap::new();
+    for (idx, arg) in args.iter().enumerate() {
+        vars.insert(idx as u32 + 1, eval_macro_decimal(arg, &vars)?);
+    }
+
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    for content in &def.content {
+        let (exposure, corners, radius, angle) = match content {
+            MacroContent::VariableDefinition(var) => {
+                let value = eval_macro_expression(&var.expression, &vars)?;
+                vars.insert(var.number, value);
+                continue;
+            }
+            MacroContent::Comment(_) => continue,
+            MacroContent::Circle(c) => {
+                let center = eval_macro_point(&c.center, &vars)?;
+                let angle = match &c.angle {
+                    Some(angle) => eval_macro_decimal(angle, &vars)?,
+                    None => 0.0,
+                };
+                let radius = eval_macro_decimal(&c.diameter, &vars)? / 2.0;
+                (Some(&c.exposure), vec![center], radius, angle)
+            }
+            MacroContent::VectorLine(l) => {
+                let half_width = eval_macro_decimal(&l.width, &vars)? / 2.0;
+                let start = eval_macro_point(&l.start, &vars)?;
+                let end = eval_macro_point(&l.end, &vars)?;
+                let length = (end.0 - start.0).hypot(end.1 - start.1);
+                if length == 0.0 {
+                    continue;
+                }
+                let normal = (
+                    -(end.1 - start.1) / length * half_width,
+                    (end.0 - start.0) / length * half_width,
+                );
+                let corners = vec![
+                    (start.0 + normal.0, start.1 + normal.1),
+                    (start.0 - normal.0, start.1 - normal.1),
+                    (end.0 + normal.0, end.1 + normal.1),
+                    (end.0 - normal.0, end.1 - normal.1),
+                ];
+                let angle = eval_macro_decimal(&l.angle, &vars)?;
+                (Some(&l.exposure), corners, 0.0, angle)
+            }
+            MacroContent::CenterLine(l) => {
+                let (w, h) = eval_macro_point(&l.dimensions, &vars)?;
+                let (cx, cy) = eval_macro_point(&l.center, &vars)?;
+                let (hw, hh) = (w / 2.0, h / 2.0);
+                let corners = vec![
+                    (cx - hw, cy - hh),
+                    (cx + hw, cy - hh),
+                    (cx + hw, cy + hh),
+                    (cx - hw, cy + hh),
+                ];
+                let angle = eval_macro_decimal(&l.angle, &vars)?;
+                (Some(&l.exposure), corners, 0.0, angle)
+            }
+            MacroContent::Outline(o) => {
+                let corners = o
+                    .points
+                    .iter()
+                    .map(|point| eval_macro_point(point, &vars))
+                    .collect::<Option<Vec<_>>>()?;
+                let angle = eval_macro_decimal(&o.angle, &vars)?;
+                (Some(&o.exposure), corners, 0.0, angle)
+            }
+            MacroContent::Polygon(p) => {
+                let center = eval_macro_point(&p.center, &vars)?;
+                let radius = eval_macro_decimal(&p.diameter, &vars)? / 2.0;
+                let angle = eval_macro_decimal(&p.angle, &vars)?;
+                (Some(&p.exposure), vec![center], radius, angle)
+            }
+            MacroContent::Moire(m) => {
+                let center = eval_macro_point(&m.center, &vars)?;
+                let radius = eval_macro_decimal(&m.diameter, &vars)?
+                    .max(eval_macro_decimal(&m.cross_hair_length, &vars)?)
+                    / 2.0;
+                let angle = eval_macro_decimal(&m.angle, &vars)?;
+                (None, vec![center], radius, angle)
+            }
+            MacroContent::Thermal(t) => {
+                let center = eval_macro_point(&t.center, &vars)?;
+                let radius = eval_macro_decimal(&t.outer_diameter, &vars)? / 2.0;
+                let angle = eval_macro_decimal(&t.angle, &vars)?;
+                (None, vec![center], radius, angle)
+            }
+        };
+
+        let dark = match exposure {
+            Some(MacroBoolean::Value(dark)) => *dark,
+            Some(MacroBoolean::Variable(n)) => *vars.get(n)? != 0.0,
+            Some(MacroBoolean::Expression(expr)) => eval_macro_expression(expr, &vars)? != 0.0,
+            None => true,
+        };
+        if !dark {
+            continue;
+        }
+
+        let (sin, cos) = angle.to_radians().sin_cos();
+        for (x, y) in corners {
+            let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+            points.extend([(x - radius, y - radius), (x + radius, y + radius)]);
+        }
+    }
+
+    if points.is_empty() {
+        return None;
+    }
+    Some(points.iter().fold(
+        (
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(min_x, min_y, max_x, max_y), &(x, y)| {
+            (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+        },
+    ))
+}
+
+fn eval_macro_point(
+    point: &(MacroDecimal, MacroDecimal),
+    vars: &HashMap<u32, f64>,
+) -> Option<(f64, f64)> {
+    Some((
+        eval_macro_decimal(&point.0, vars)?,
+        eval_macro_decimal(&point.1, vars)?,
+    ))
+}
+
+fn eval_macro_decimal(value: &MacroDecimal, vars: &HashMap<u32, f64>) -> Option<f64> {
+    match value {
+        MacroDecimal::Value(v) => Some(*v),
+        MacroDecimal::Variable(n) => vars.get(n).copied(),
+        MacroDecimal::Expression(expr) => eval_macro_expression(expr, vars),
+    }
+}
+
+/// Evaluate a macro arithmetic expression
+///
+/// Supports constants, `$n` variables, unary signs, brackets and the `+`, `-`,
+/// `x` and `/` operators with the usual precedence.
+fn eval_macro_expression(expr: &str, vars: &HashMap<u32, f64>) -> Option<f64> {
+    fn sum(input: &mut &[u8], vars: &HashMap<u32, f64>) -> Option<f64> {
+        let mut value = product(input, vars)?;
+        while let Some(&op) = input.first() {
+            if op != b'+' && op != b'-' {
+                break;
+            }
+            *input = &input[1..];
+            let rhs = product(input, vars)?;
+            value = if op == b'+' { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    fn product(input: &mut &[u8], vars: &HashMap<u32, f64>) -> Option<f64> {
+        let mut value = factor(input, vars)?;
+        while let Some(&op) = input.first() {
+            if !matches!(op, b'x' | b'X' | b'/') {
+                break;
+            }
+            *input = &input[1..];
+            let rhs = factor(input, vars)?;
+            value = if op == b'/' { value / rhs } else { value * rhs };
+        }
+        Some(value)
+    }
+
+    fn factor(input: &mut &[u8], vars: &HashMap<u32, f64>) -> Option<f64> {
+        let (&first, rest) = input.split_first()?;
+        match first {
+            b'+' | b'-' => {
+                *input = rest;
+                let value = factor(input, vars)?;
+                Some(if first == b'-' { -value } else { value })
+            }
+            b'(' => {
+                *input = rest;
+                let value = sum(input, vars)?;
+                *input = input.strip_prefix(b")")?;
+                Some(value)
+            }
+            b'$' => {
+                *input = rest;
+                let digits = input.iter().take_while(|c| c.is_ascii_digit()).count();
+                let number = std::str::from_utf8(&input[..digits]).ok()?.parse().ok()?;
+                *input = &input[digits..];
+                vars.get(&number).copied()
+            }
+            _ => {
+                let len = input
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == b'.')
+                    .count();
+                let value = std::str::from_utf8(&input[..len]).ok()?.parse().ok()?;
+                *input = &input[len..];
+                Some(value)
+            }
         }
-        _ => None,
     }
+
+    let compact: Vec<u8> = expr.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    let mut input = compact.as_slice();
+    let value = sum(&mut input, vars)?;
+    input.is_empty().then_some(value)
 }
 
 fn parse_round_rect_macro(args: Option<&[MacroDecimal]>) -> Option<(f64, f64)> {
@@ -98,6 +476,15 @@ fn parse_round_rect_macro(args: Option<&[MacroDecimal]>) -> Option<(f64, f64)> {
 }
 
 pub fn parse_solder_mask(content: &str) -> Result<MaskPaths> {
+    parse_solder_mask_with_arc_segments(content, DEFAULT_ARC_SEGMENTS)
+}
+
+/// Parse solder mask openings, approximating region arcs with
+/// `arc_segments` line segments per full circle
+pub fn parse_solder_mask_with_arc_segments(
+    content: &str,
+    arc_segments: usize,
+) -> Result<MaskPaths> {
     let reader = BufReader::new(Cursor::new(content));
     let doc = match parse(reader) {
         Ok(doc) => doc,
@@ -110,24 +497,60 @@ pub fn parse_solder_mask(content: &str) -> Result<MaskPaths> {
     };
 
     let mut units = doc.units.unwrap_or(Unit::Millimeters);
-    let mut current_aperture: Option<&Aperture> = None;
+    let mut current_aperture: Option<(&i32, &Aperture)> = None;
     let mut current_pos: (f64, f64) = (0.0, 0.0);
     let mut region_active = false;
     let mut region_points: Vec<(f64, f64)> = Vec::new();
     let mut shapes: MaskPaths = Vec::new();
     let mut interp_mode = InterpolationMode::Linear;
+    let mut quadrant_mode = QuadrantMode::Multi;
+    let mut polarity = Polarity::Dark;
+    let mut warned_clear = false;
 
     let apertures = &doc.apertures;
+    let macros: HashMap<&str, &ApertureMacro> = doc
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::ExtendedCode(ExtendedCode::ApertureMacro(def)) => {
+                Some((def.name.as_str(), def))
+            }
+            _ => None,
+        })
+        .collect();
+    let macro_sizes: HashMap<i32, Bounds> = apertures
+        .iter()
+        .filter_map(|(code, ap)| match ap {
+            Aperture::Macro(name, args) if name != "RoundRect" => {
+                let bounds = macros
+                    .get(name.as_str())
+                    .and_then(|def| macro_bounds(def, args.as_deref().unwrap_or_default()));
+                if bounds.is_none() {
+                    warn!("Skipping flashes of macro aperture D{code} ({name}): its size cannot be determined");
+                }
+                bounds.map(|bounds| (*code, bounds))
+            }
+            _ => None,
+        })
+        .collect();
 
     for command in doc.commands() {
         match command {
             Command::ExtendedCode(ExtendedCode::Unit(u)) => units = *u,
+            Command::ExtendedCode(ExtendedCode::LoadPolarity(p)) => {
+                polarity = *p;
+                if matches!(polarity, Polarity::Clear) && !warned_clear {
+                    warn!("Solder mask uses clear polarity (%LPC); clear shapes are ignored");
+                    warned_clear = true;
+                }
+            }
             Command::FunctionCode(FunctionCode::GCode(g)) => match g {
                 GCode::InterpolationMode(m) => interp_mode = *m,
+                GCode::QuadrantMode(m) => quadrant_mode = *m,
                 GCode::RegionMode(on) => {
                     if !on && region_active && region_points.len() > 1 {
-                        if let Some(d) = path_from_region(&region_points) {
-                            shapes.push(d);
+                        if matches!(polarity, Polarity::Dark) {
+                            shapes.extend(path_from_region(&region_points));
                         }
                         region_points.clear();
                     }
@@ -136,37 +559,44 @@ pub fn parse_solder_mask(content: &str) -> Result<MaskPaths> {
                 _ => {}
             },
             Command::FunctionCode(FunctionCode::DCode(d)) => match d {
-                DCode::SelectAperture(code) => current_aperture = apertures.get(code),
+                DCode::SelectAperture(code) => current_aperture = apertures.get_key_value(code),
                 DCode::Operation(op) => match op {
                     Operation::Move(coords) => {
                         current_pos = coords_to_mm(coords, current_pos, units);
                     }
-                    Operation::Interpolate(coords, _) => {
+                    Operation::Interpolate(coords, offset) => {
                         let next = coords_to_mm(coords, current_pos, units);
                         if region_active {
                             if region_points.is_empty() {
                                 let (sx, sy) = to_svg_space(current_pos.0, current_pos.1);
                                 region_points.push((sx, sy));
                             }
-                            let (nx, ny) = to_svg_space(next.0, next.1);
-                            region_points.push((nx, ny));
+                            let traced = match interp_mode {
+                                InterpolationMode::Linear => vec![next],
+                                InterpolationMode::ClockwiseCircular
+                                | InterpolationMode::CounterclockwiseCircular => linearize_arc(
+                                    current_pos,
+                                    next,
+                                    offset_to_mm(offset, units),
+                                    matches!(interp_mode, InterpolationMode::ClockwiseCircular),
+                                    matches!(quadrant_mode, QuadrantMode::Single),
+                                    arc_segments,
+                                ),
+                            };
+                            region_points
+                                .extend(traced.into_iter().map(|(x, y)| to_svg_space(x, y)));
                         }
                         current_pos = next;
-                        let _ = interp_mode; // currently unused (linearized)
                     }
                     Operation::Flash(coords) => {
                         let pos_mm = coords_to_mm(coords, current_pos, units);
                         let (cx, cy) = to_svg_space(pos_mm.0, pos_mm.1);
-                        let ap_ref = current_aperture.or_else(|| {
-                            apertures
-                                .iter()
-                                .max_by_key(|(code, _)| *code)
-                                .map(|(_, ap)| ap)
-                        });
-                        if let Some(ap) = ap_ref {
-                            if let Some((w, h)) = aperture_bbox(ap) {
-                                shapes.push(rect_path((cx, cy), w, h));
-                            }
+                        let ap_ref = current_aperture
+                            .or_else(|| apertures.iter().max_by_key(|(code, _)| *code));
+                        if matches!(polarity, Polarity::Dark) {
+                            shapes.extend(ap_ref.and_then(|(code, ap)| {
+                                flash_path(ap, macro_sizes.get(code).copied(), (cx, cy))
+                            }));
                         }
                         current_pos = pos_mm;
                     }
@@ -177,11 +607,59 @@ pub fn parse_solder_mask(content: &str) -> Result<MaskPaths> {
     }
 
     // Close any pending region
-    if region_active && region_points.len() > 1 {
-        if let Some(d) = path_from_region(&region_points) {
-            shapes.push(d);
-        }
+    if region_active && region_points.len() > 1 && matches!(polarity, Polarity::Dark) {
+        shapes.extend(path_from_region(&region_points));
     }
 
     Ok(shapes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_linearize_quarter_arcs() {
+        // CCW quarter turn from (1, 0) around the origin to (0, 1)
+        let points = linearize_arc((1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), false, false, 8);
+        assert_eq!(points.len(), 2);
+        let diagonal = std::f64::consts::FRAC_1_SQRT_2;
+        assert_close(points[0], (diagonal, diagonal));
+        assert_close(points[1], (0.0, 1.0));
+
+        // The same endpoints clockwise go the long way round
+        let points = linearize_arc((1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), true, false, 8);
+        assert_eq!(points.len(), 6);
+        assert_close(points[1], (0.0, -1.0));
+        assert_close(points[2], (-diagonal, -diagonal));
+
+        // Single-quadrant offsets are unsigned; the center is inferred
+        let points = linearize_arc((1.0, 0.0), (0.0, 1.0), (1.0, 0.0), false, true, 8);
+        assert_eq!(points.len(), 2);
+        assert_close(points[0], (diagonal, diagonal));
+    }
+
+    #[test]
+    fn test_eval_macro_expression() {
+        let vars = HashMap::from([(1, 0.5), (2, 3.0)]);
+        assert_eq!(eval_macro_expression("$1+$1", &vars), Some(1.0));
+        assert_eq!(eval_macro_expression("$2-$1x2", &vars), Some(2.0));
+        assert_eq!(eval_macro_expression("-($2+1)/2", &vars), Some(-2.0));
+        assert_eq!(eval_macro_expression("$3x2", &vars), None);
+        assert_eq!(eval_macro_expression("$1)", &vars), None);
+    }
+
+    #[test]
+    fn test_full_circle_in_multi_quadrant_mode() {
+        let points = linearize_arc((1.0, 0.0), (1.0, 0.0), (-1.0, 0.0), false, false, 16);
+        assert_eq!(points.len(), 16);
+        assert_close(points[7], (-1.0, 0.0));
+    }
+}
@@ -4,9 +4,10 @@
 //! based on board outline, user-specified images, and solder mask openings.
 
 use crate::patterns::LayerType;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::thread;
 
 mod encrypt;
 pub mod mask;
@@ -14,7 +15,18 @@ mod svg;
 mod types;
 
 pub use mask::parse_solder_mask;
-use types::{compute_mark_points, load_image, MaskPaths};
+use tracing::info;
+use types::{
+    compute_mark_points, load_image, load_image_scaled, BoardBounds, MarkPoints, MaskPaths,
+    PlacedImage,
+};
+pub(crate) use types::{parse_layer_bounds, parse_outline_bounds};
+pub use types::{ImagePlacement, DEFAULT_MARK_INSET_MM, DEFAULT_MARK_PAD_DIAMETER_MM};
+
+/// Make sure the embedded RSA key can wrap the colorful silkscreen header
+pub(crate) fn check_embedded_key() -> Result<()> {
+    encrypt::KeyMaterial::generate(RSA_PUB_KEY).map(|_| ())
+}
 
 const RSA_PUB_KEY: &str = r#"-----BEGIN PUBLIC KEY-----
 MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAzPtuUqJecaR/wWtctGT8
@@ -29,10 +41,74 @@ lwIDAQAB
 /// Inputs for colorful silkscreen generation
 #[derive(Debug, Clone)]
 pub struct ColorfulOptions {
+    /// Image stretched over the whole top side
     pub top_image: Option<PathBuf>,
+    /// Further top images, each covering only its placement; drawn above
+    /// `top_image` in order
+    pub top_images: Vec<(PathBuf, ImagePlacement)>,
     pub bottom_image: Option<PathBuf>,
     pub top_solder_mask: Option<PathBuf>,
     pub bottom_solder_mask: Option<PathBuf>,
+    /// Maximum size in bytes of each encrypted silkscreen file; images are
+    /// downscaled until the output fits
+    pub max_output_size: Option<u64>,
+    /// Fail instead of using the recovered commands when the outline only
+    /// parses partially
+    pub strict_outline: bool,
+    /// Distance in millimeters the alignment marks are moved in from the
+    /// board edge
+    pub mark_inset_mm: f64,
+    /// Diameter in millimeters of the flashed alignment mark pads
+    pub mark_pad_diameter_mm: f64,
+    /// Line terminator for the plain Gerber mark layer; `None` keeps `\n`.
+    /// The encrypted layers are binary and are written unchanged
+    pub line_ending: Option<&'static str>,
+}
+
+impl Default for ColorfulOptions {
+    fn default() -> Self {
+        Self {
+            top_image: None,
+            top_images: Vec::new(),
+            bottom_image: None,
+            top_solder_mask: None,
+            bottom_solder_mask: None,
+            max_output_size: None,
+            strict_outline: false,
+            mark_inset_mm: DEFAULT_MARK_INSET_MM,
+            mark_pad_diameter_mm: DEFAULT_MARK_PAD_DIAMETER_MM,
+            line_ending: None,
+        }
+    }
+}
+
+/// Builds the silkscreen SVG for one side of the board
+type SvgBuilder = fn(&BoardBounds, &MarkPoints, &[PlacedImage], &MaskPaths) -> String;
+
+/// An image file and its placement; `None` covers the whole board
+type ImageSource<'a> = (&'a Path, Option<ImagePlacement>);
+
+/// Board side carrying a colorful silkscreen
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Top,
+    Bottom,
+}
+
+impl Side {
+    fn layer(self) -> LayerType {
+        match self {
+            Side::Top => LayerType::ColorfulTopSilkscreen,
+            Side::Bottom => LayerType::ColorfulBottomSilkscreen,
+        }
+    }
+
+    fn svg_builder(self) -> SvgBuilder {
+        match self {
+            Side::Top => svg::build_top_svg,
+            Side::Bottom => svg::build_bottom_svg,
+        }
+    }
 }
 
 /// Generate colorful silkscreen encrypted outputs
@@ -52,38 +128,25 @@ impl ColorfulSilkscreenGenerator {
         outline_path: &Path,
         output_dir: &Path,
     ) -> Result<Vec<(LayerType, PathBuf)>> {
-        if self.options.top_image.is_none() && self.options.bottom_image.is_none() {
+        if self.options.top_image.is_none()
+            && self.options.top_images.is_empty()
+            && self.options.bottom_image.is_none()
+        {
             return Ok(Vec::new());
         }
 
         let outline_content = fs::read_to_string(outline_path)
             .with_context(|| format!("Read outline {}", outline_path.display()))?;
-        let bounds = types::parse_outline_bounds(&outline_content)?;
-        let mark_points = compute_mark_points(&bounds);
+        let bounds = types::parse_outline_bounds(&outline_content, self.options.strict_outline)?;
+        bounds.validate()?;
+        let mark_points = compute_mark_points(&bounds, self.options.mark_inset_mm);
 
         fs::create_dir_all(output_dir)
             .with_context(|| format!("Create output dir {}", output_dir.display()))?;
 
         let key_material = encrypt::KeyMaterial::generate(RSA_PUB_KEY)?;
-        let mut written: Vec<(LayerType, PathBuf)> = Vec::new();
-
-        if let Some(top_path) = &self.options.top_image {
-            let image = load_image(top_path)?;
-            let mask = load_mask_paths(self.options.top_solder_mask.as_deref())?;
-            let svg = svg::build_top_svg(&bounds, &image, &mask);
-            let target = output_dir.join("Fabrication_ColorfulTopSilkscreen.FCTS");
-            encrypt::encrypt_and_write(&svg, &key_material, &target)?;
-            written.push((LayerType::ColorfulTopSilkscreen, target));
-        }
 
-        if let Some(bottom_path) = &self.options.bottom_image {
-            let image = load_image(bottom_path)?;
-            let mask = load_mask_paths(self.options.bottom_solder_mask.as_deref())?;
-            let svg = svg::build_bottom_svg(&bounds, &image, &mask);
-            let target = output_dir.join("Fabrication_ColorfulBottomSilkscreen.FCBS");
-            encrypt::encrypt_and_write(&svg, &key_material, &target)?;
-            written.push((LayerType::ColorfulBottomSilkscreen, target));
-        }
+        let mut written = self.generate_sides(&bounds, &mark_points, &key_material, output_dir)?;
 
         // Colorful board outline layer (encrypted SVG)
         let outline_svg = svg::build_board_outline_svg(&bounds);
@@ -92,7 +155,15 @@ impl ColorfulSilkscreenGenerator {
         written.push((LayerType::ColorfulBoardOutline, outline_target));
 
         // Colorful board outline mark layer (plain Gerber)
-        let mark_gerber = svg::build_outline_mark_gerber(&bounds, &mark_points);
+        let mark_gerber = svg::build_outline_mark_gerber(
+            &bounds,
+            &mark_points,
+            self.options.mark_pad_diameter_mm,
+        );
+        let mark_gerber = match self.options.line_ending {
+            Some(ending) => mark_gerber.replace('\n', ending),
+            None => mark_gerber,
+        };
         let mark_target = output_dir.join("Fabrication_ColorfulBoardOutlineMark.FCBM");
         fs::write(&mark_target, mark_gerber)
             .with_context(|| format!("Write {}", mark_target.display()))?;
@@ -100,6 +171,156 @@ impl ColorfulSilkscreenGenerator {
 
         Ok(written)
     }
+
+    /// Generate the top and bottom silkscreen files concurrently
+    fn generate_sides(
+        &self,
+        bounds: &BoardBounds,
+        marks: &MarkPoints,
+        key_material: &encrypt::KeyMaterial,
+        output_dir: &Path,
+    ) -> Result<Vec<(LayerType, PathBuf)>> {
+        // Each side loads, encodes and encrypts its own image independently
+        let (top, bottom) = thread::scope(|scope| {
+            let top = scope
+                .spawn(|| self.generate_side(Side::Top, bounds, marks, key_material, output_dir));
+            let bottom = self.generate_side(Side::Bottom, bounds, marks, key_material, output_dir);
+            (top.join(), bottom)
+        });
+
+        let top = top.map_err(|_| anyhow!("Top colorful silkscreen generation panicked"))??;
+        let bottom = bottom?;
+
+        Ok(top.into_iter().chain(bottom).collect())
+    }
+
+    /// Generate the encrypted silkscreen file for one side, if it has an image
+    fn generate_side(
+        &self,
+        side: Side,
+        bounds: &BoardBounds,
+        marks: &MarkPoints,
+        key_material: &encrypt::KeyMaterial,
+        output_dir: &Path,
+    ) -> Result<Option<(LayerType, PathBuf)>> {
+        let (image_path, placed_images, mask_path) = match side {
+            Side::Top => (
+                &self.options.top_image,
+                self.options.top_images.as_slice(),
+                &self.options.top_solder_mask,
+            ),
+            Side::Bottom => (
+                &self.options.bottom_image,
+                &[][..],
+                &self.options.bottom_solder_mask,
+            ),
+        };
+        let sources: Vec<ImageSource> = image_path
+            .iter()
+            .map(|path| (path.as_path(), None))
+            .chain(
+                placed_images
+                    .iter()
+                    .map(|(path, placement)| (path.as_path(), Some(*placement))),
+            )
+            .collect();
+        if sources.is_empty() {
+            return Ok(None);
+        }
+
+        let mask = load_mask_paths(mask_path.as_deref())?;
+        let svg = self.build_side_svg(
+            &sources,
+            side.svg_builder(),
+            bounds,
+            marks,
+            &mask,
+            key_material,
+        )?;
+        let target = output_dir.join(side.layer().to_jlc_filename());
+        encrypt::encrypt_and_write(&svg, key_material, &target)?;
+
+        Ok(Some((side.layer(), target)))
+    }
+
+    /// Build one side's SVG, downscaling the images until the encrypted
+    /// output fits within `max_output_size`
+    fn build_side_svg(
+        &self,
+        sources: &[ImageSource],
+        build: SvgBuilder,
+        bounds: &BoardBounds,
+        marks: &MarkPoints,
+        mask: &MaskPaths,
+        key_material: &encrypt::KeyMaterial,
+    ) -> Result<String> {
+        let images = load_placed_images(sources, 1.0)?;
+        let mut svg = build(bounds, marks, &images, mask);
+
+        let Some(limit) = self.options.max_output_size else {
+            return Ok(svg);
+        };
+
+        // The smallest image is the first to run out of pixels
+        let (original_width, original_height) = images
+            .iter()
+            .map(|placed| (placed.image.width as f64, placed.image.height as f64))
+            .fold((f64::INFINITY, f64::INFINITY), |(w, h), (iw, ih)| {
+                (w.min(iw), h.min(ih))
+            });
+        let names = sources
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut scale = 1.0;
+        let mut size = encrypt::encrypted_len(&svg, key_material);
+
+        while size > limit {
+            // The payload shrinks roughly with the pixel count, i.e. the square of the scale
+            scale *= (limit as f64 / size as f64).sqrt().min(0.9);
+            if original_width * scale < 1.0 || original_height * scale < 1.0 {
+                bail!(
+                    "Cannot fit colorful silkscreen for {} within {} bytes",
+                    names,
+                    limit
+                );
+            }
+
+            let images = load_placed_images(sources, scale)?;
+            svg = build(bounds, marks, &images, mask);
+            size = encrypt::encrypted_len(&svg, key_material);
+        }
+
+        if scale < 1.0 {
+            info!(
+                "Downscaled {} to {:.1}% to fit colorful output within {} bytes",
+                names,
+                scale * 100.0,
+                limit
+            );
+        }
+
+        Ok(svg)
+    }
+}
+
+/// Load every image source, resized by `scale` when it is below 1
+fn load_placed_images(sources: &[ImageSource], scale: f64) -> Result<Vec<PlacedImage>> {
+    sources
+        .iter()
+        .map(|(path, placement)| {
+            let image = if scale < 1.0 {
+                load_image_scaled(path, scale)?
+            } else {
+                load_image(path)?
+            };
+            Ok(PlacedImage {
+                image,
+                placement: *placement,
+            })
+        })
+        .collect()
 }
 
 fn load_mask_paths(path: Option<&Path>) -> Result<MaskPaths> {
@@ -110,3 +331,251 @@ fn load_mask_paths(path: Option<&Path>) -> Result<MaskPaths> {
         fs::read_to_string(path).with_context(|| format!("Read solder mask {}", path.display()))?;
     mask::parse_solder_mask(&content)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_large_image_is_downscaled_to_fit_limit() {
+        let dir = tempdir().unwrap();
+        let image_path = dir.path().join("large.png");
+
+        // Noise compresses poorly, so the embedded PNG stays large
+        let mut state: u32 = 0x1234_5678;
+        let noise = RgbImage::from_fn(800, 800, |_, _| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let [r, g, b, _] = state.to_le_bytes();
+            image::Rgb([r, g, b])
+        });
+        noise.save(&image_path).unwrap();
+
+        let limit = 200_000;
+        let generator = ColorfulSilkscreenGenerator::new(ColorfulOptions {
+            top_image: Some(image_path.clone()),
+            max_output_size: Some(limit),
+            ..Default::default()
+        });
+        let bounds = BoardBounds {
+            min_x: 0.0,
+            max_x: 50.0,
+            min_y: 0.0,
+            max_y: 30.0,
+        };
+        let marks = compute_mark_points(&bounds, DEFAULT_MARK_INSET_MM);
+        let key_material = encrypt::KeyMaterial::generate(RSA_PUB_KEY).unwrap();
+
+        let full_image = PlacedImage {
+            image: load_image(&image_path).unwrap(),
+            placement: None,
+        };
+        let full_svg = svg::build_top_svg(&bounds, &marks, &[full_image], &Vec::new());
+        assert!(encrypt::encrypted_len(&full_svg, &key_material) > limit);
+
+        let svg = generator
+            .build_side_svg(
+                &[(image_path.as_path(), None)],
+                svg::build_top_svg,
+                &bounds,
+                &marks,
+                &Vec::new(),
+                &key_material,
+            )
+            .unwrap();
+
+        let target = dir.path().join("Fabrication_ColorfulTopSilkscreen.FCTS");
+        encrypt::encrypt_and_write(&svg, &key_material, &target).unwrap();
+        assert!(fs::metadata(&target).unwrap().len() <= limit);
+    }
+
+    #[test]
+    fn test_zero_width_outline_is_rejected() {
+        let dir = tempdir().unwrap();
+        let image_path = dir.path().join("top.png");
+        RgbImage::from_pixel(4, 4, image::Rgb([200, 20, 20]))
+            .save(&image_path)
+            .unwrap();
+        // A single vertical edge: 20 mm high but without any width
+        let outline_path = dir.path().join("outline.gbr");
+        fs::write(
+            &outline_path,
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX10000000Y0D02*\nX10000000Y20000000D01*\nM02*\n",
+        )
+        .unwrap();
+
+        let output_dir = dir.path().join("out");
+        let generator = ColorfulSilkscreenGenerator::new(ColorfulOptions {
+            top_image: Some(image_path),
+            ..Default::default()
+        });
+        let err = generator.generate(&outline_path, &output_dir).unwrap_err();
+
+        assert!(err.to_string().contains("Board outline has no area"));
+        assert!(!output_dir.exists());
+    }
+
+    #[test]
+    fn test_placed_images_share_one_top_layer() {
+        let dir = tempdir().unwrap();
+        let logo = dir.path().join("logo.png");
+        let text = dir.path().join("text.png");
+        RgbImage::from_pixel(10, 10, image::Rgb([200, 20, 20]))
+            .save(&logo)
+            .unwrap();
+        RgbImage::from_pixel(20, 5, image::Rgb([20, 20, 200]))
+            .save(&text)
+            .unwrap();
+
+        let bounds = BoardBounds {
+            min_x: 0.0,
+            max_x: 50.8,
+            min_y: 0.0,
+            max_y: 25.4,
+        };
+        let sources: Vec<ImageSource> = vec![
+            (
+                logo.as_path(),
+                Some(ImagePlacement {
+                    x_mm: 2.54,
+                    y_mm: 2.54,
+                    width_mm: 2.54,
+                    height_mm: 2.54,
+                }),
+            ),
+            (
+                text.as_path(),
+                Some(ImagePlacement {
+                    x_mm: 25.4,
+                    y_mm: 20.32,
+                    width_mm: 20.32,
+                    height_mm: 5.08,
+                }),
+            ),
+        ];
+        let images = load_placed_images(&sources, 1.0).unwrap();
+        let marks = compute_mark_points(&bounds, DEFAULT_MARK_INSET_MM);
+
+        let svg = svg::build_top_svg(&bounds, &marks, &images, &Vec::new());
+
+        assert_eq!(svg.matches("<image").count(), 2);
+        // The logo is 100 x 100 mil, 100 mil in from the lower-left corner
+        assert!(svg.contains(r#"transform="matrix(1 0 0 1 10 -20)""#));
+        assert!(svg.contains(r#"transform="matrix(4 0 0 4 100 -100)""#));
+    }
+
+    #[test]
+    fn test_custom_mark_inset_and_pad_diameter() {
+        let dir = tempdir().unwrap();
+        let image_path = dir.path().join("top.png");
+        RgbImage::from_pixel(4, 4, image::Rgb([200, 20, 20]))
+            .save(&image_path)
+            .unwrap();
+        let outline_path = dir.path().join("outline.gbr");
+        fs::write(
+            &outline_path,
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX50000000Y0D01*\n\
+             X50000000Y30000000D01*\nX0Y30000000D01*\nX0Y0D01*\nM02*\n",
+        )
+        .unwrap();
+
+        let generator = ColorfulSilkscreenGenerator::new(ColorfulOptions {
+            top_image: Some(image_path),
+            mark_inset_mm: 0.5,
+            mark_pad_diameter_mm: 1.6,
+            ..Default::default()
+        });
+        let output_dir = dir.path().join("out");
+        generator.generate(&outline_path, &output_dir).unwrap();
+
+        let marks =
+            fs::read_to_string(output_dir.join("Fabrication_ColorfulBoardOutlineMark.FCBM"))
+                .unwrap();
+        assert!(marks.contains("%ADD11C,1.600*%\nD11*\n"));
+        assert!(marks.contains("X+0050000Y+0050000D03*"));
+        assert!(marks.contains("X+0050000Y+2950000D03*"));
+        assert!(marks.contains("X+4950000Y+2950000D03*"));
+    }
+
+    #[test]
+    fn test_mark_layer_uses_requested_line_ending() {
+        let dir = tempdir().unwrap();
+        let image_path = dir.path().join("top.png");
+        RgbImage::from_pixel(4, 4, image::Rgb([200, 20, 20]))
+            .save(&image_path)
+            .unwrap();
+        let outline_path = dir.path().join("outline.gbr");
+        fs::write(
+            &outline_path,
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX50000000Y0D01*\n\
+             X50000000Y30000000D01*\nX0Y30000000D01*\nX0Y0D01*\nM02*\n",
+        )
+        .unwrap();
+
+        let generator = ColorfulSilkscreenGenerator::new(ColorfulOptions {
+            top_image: Some(image_path),
+            line_ending: Some("\r\n"),
+            ..Default::default()
+        });
+        let output_dir = dir.path().join("out");
+        generator.generate(&outline_path, &output_dir).unwrap();
+
+        let marks =
+            fs::read_to_string(output_dir.join("Fabrication_ColorfulBoardOutlineMark.FCBM"))
+                .unwrap();
+        let lines = marks.matches('\n').count();
+        assert!(lines > 0);
+        assert_eq!(marks.matches("\r\n").count(), lines);
+    }
+
+    #[test]
+    fn test_concurrent_sides_match_serial_generation() {
+        let dir = tempdir().unwrap();
+        let top_image = dir.path().join("top.png");
+        let bottom_image = dir.path().join("bottom.png");
+        RgbImage::from_pixel(16, 8, image::Rgb([200, 20, 20]))
+            .save(&top_image)
+            .unwrap();
+        RgbImage::from_pixel(8, 16, image::Rgb([20, 20, 200]))
+            .save(&bottom_image)
+            .unwrap();
+
+        let generator = ColorfulSilkscreenGenerator::new(ColorfulOptions {
+            top_image: Some(top_image),
+            bottom_image: Some(bottom_image),
+            ..Default::default()
+        });
+        let bounds = BoardBounds {
+            min_x: 0.0,
+            max_x: 40.0,
+            min_y: 0.0,
+            max_y: 20.0,
+        };
+        let marks = compute_mark_points(&bounds, DEFAULT_MARK_INSET_MM);
+        let key_material = encrypt::KeyMaterial::generate(RSA_PUB_KEY).unwrap();
+
+        let concurrent_dir = dir.path().join("concurrent");
+        let serial_dir = dir.path().join("serial");
+        fs::create_dir_all(&concurrent_dir).unwrap();
+        fs::create_dir_all(&serial_dir).unwrap();
+
+        let concurrent = generator
+            .generate_sides(&bounds, &marks, &key_material, &concurrent_dir)
+            .unwrap();
+        assert_eq!(concurrent.len(), 2);
+
+        for side in [Side::Top, Side::Bottom] {
+            let (layer, serial_path) = generator
+                .generate_side(side, &bounds, &marks, &key_material, &serial_dir)
+                .unwrap()
+                .unwrap();
+            let (_, concurrent_path) = concurrent.iter().find(|(l, _)| *l == layer).unwrap();
+
+            assert_eq!(
+                fs::read(concurrent_path).unwrap(),
+                fs::read(serial_path).unwrap()
+            );
+        }
+    }
+}
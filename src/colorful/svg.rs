@@ -1,11 +1,12 @@
-use super::types::{compute_mark_points, mm_to_mil_10, BoardBounds, MaskPaths, SilkscreenImage};
+use super::types::{mm_to_mil_10, BoardBounds, ImagePlacement, MarkPoints, MaskPaths, PlacedImage};
 use xmlwriter::{Indent, Options, XmlWriter};
 
 const SVG_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#;
 
 pub(crate) fn build_bottom_svg(
     bounds: &BoardBounds,
-    image: &SilkscreenImage,
+    marks: &MarkPoints,
+    images: &[PlacedImage],
     mask_paths: &MaskPaths,
 ) -> String {
     const CLIP_MARGIN: f64 = 0.8374; // shrink (10 mil units)
@@ -19,10 +20,8 @@ pub(crate) fn build_bottom_svg(
     let w = mm_to_mil_10(bounds.width());
     let h = mm_to_mil_10(bounds.height());
     let center_x = min_x + w / 2.0;
-    let image_w = image.width;
-    let image_h = image.height;
 
-    let mark_points = compute_mark_points(bounds)
+    let mark_points = marks
         .iter()
         .flat_map(|(x, y)| [mm_to_mil_10(*x).to_string(), mm_to_mil_10(*y).to_string()])
         .collect::<Vec<_>>()
@@ -142,22 +141,28 @@ pub(crate) fn build_bottom_svg(
     writer.write_attribute("id", "background");
     writer.end_element();
 
-    writer.start_element("image");
-    writer.write_attribute("width", &image_w.to_string());
-    writer.write_attribute("height", &image_h.to_string());
-    writer.write_attribute("preserveAspectRatio", "none");
-    writer.write_attribute("xlink:href", &image.data_uri);
-    writer.write_attribute(
-        "transform",
-        &format!(
-            "matrix({} 0 0 {} {} {})",
-            -(w / image_w as f64),
-            h / image_h as f64,
-            max_x,
-            min_y
-        ),
-    );
-    writer.end_element(); // image
+    // Images are pre-flipped so they read correctly inside the mirrored group
+    for placed in images {
+        let (right, y, width, height) = match placed.placement {
+            Some(placement) => {
+                let (x, y, width, height) = placement_box(bounds, &placement);
+                (x + width, y, width, height)
+            }
+            None => (max_x, min_y, w, h),
+        };
+        let (image_w, image_h) = (placed.image.width as f64, placed.image.height as f64);
+        write_image(
+            &mut writer,
+            placed,
+            &format!(
+                "matrix({} 0 0 {} {} {})",
+                -(width / image_w),
+                height / image_h,
+                right,
+                y
+            ),
+        );
+    }
 
     writer.end_element(); // g
     writer.end_element(); // svg
@@ -170,7 +175,8 @@ pub(crate) fn build_bottom_svg(
 
 pub(crate) fn build_top_svg(
     bounds: &BoardBounds,
-    image: &SilkscreenImage,
+    marks: &MarkPoints,
+    images: &[PlacedImage],
     mask_paths: &MaskPaths,
 ) -> String {
     const CLIP_MARGIN: f64 = 0.8374; // in 10-mil units
@@ -183,10 +189,8 @@ pub(crate) fn build_top_svg(
     let max_y = -mm_to_mil_10(bounds.min_y);
     let w = mm_to_mil_10(bounds.width());
     let h = mm_to_mil_10(bounds.height());
-    let image_w = image.width;
-    let image_h = image.height;
 
-    let mark_points = compute_mark_points(bounds)
+    let mark_points = marks
         .iter()
         .flat_map(|(x, y)| [mm_to_mil_10(*x).to_string(), mm_to_mil_10(*y).to_string()])
         .collect::<Vec<_>>()
@@ -300,22 +304,24 @@ pub(crate) fn build_top_svg(
     writer.write_attribute("id", "background");
     writer.end_element(); // path
 
-    writer.start_element("image");
-    writer.write_attribute("width", &image_w.to_string());
-    writer.write_attribute("height", &image_h.to_string());
-    writer.write_attribute("preserveAspectRatio", "none");
-    writer.write_attribute("xlink:href", &image.data_uri);
-    writer.write_attribute(
-        "transform",
-        &format!(
-            "matrix({} 0 0 {} {} {})",
-            w / image_w as f64,
-            h / image_h as f64,
-            min_x,
-            min_y
-        ),
-    );
-    writer.end_element(); // image
+    for placed in images {
+        let (x, y, width, height) = match placed.placement {
+            Some(placement) => placement_box(bounds, &placement),
+            None => (min_x, min_y, w, h),
+        };
+        let (image_w, image_h) = (placed.image.width as f64, placed.image.height as f64);
+        write_image(
+            &mut writer,
+            placed,
+            &format!(
+                "matrix({} 0 0 {} {} {})",
+                width / image_w,
+                height / image_h,
+                x,
+                y
+            ),
+        );
+    }
 
     writer.end_element(); // g
     writer.end_element(); // svg
@@ -360,7 +366,11 @@ pub(crate) fn build_board_outline_svg(bounds: &BoardBounds) -> String {
     result
 }
 
-pub(crate) fn build_outline_mark_gerber(bounds: &BoardBounds, marks: &[(f64, f64)]) -> String {
+pub(crate) fn build_outline_mark_gerber(
+    bounds: &BoardBounds,
+    marks: &MarkPoints,
+    pad_diameter_mm: f64,
+) -> String {
     // Use mm units with 2 integer, 5 decimal places.
     let mut out = String::new();
     out.push_str("G04 Fabrication_ColorfulBoardOutlineMark*\n");
@@ -385,7 +395,7 @@ pub(crate) fn build_outline_mark_gerber(bounds: &BoardBounds, marks: &[(f64, f64
     }
 
     // Mark pads (flash)
-    out.push_str("%ADD11C,1.000*%\n");
+    out.push_str(&format!("%ADD11C,{:.3}*%\n", pad_diameter_mm));
     out.push_str("D11*\n");
     for (x, y) in marks {
         let c = format_coord(*x, *y);
@@ -396,6 +406,28 @@ pub(crate) fn build_outline_mark_gerber(bounds: &BoardBounds, marks: &[(f64, f64
     out
 }
 
+/// SVG box (left, top, width, height) in 10-mil units covered by a placement
+fn placement_box(bounds: &BoardBounds, placement: &ImagePlacement) -> (f64, f64, f64, f64) {
+    (
+        mm_to_mil_10(bounds.min_x + placement.x_mm),
+        // Y axis inverted, so the top edge of the image is its SVG origin
+        -mm_to_mil_10(bounds.min_y + placement.y_mm + placement.height_mm),
+        mm_to_mil_10(placement.width_mm),
+        mm_to_mil_10(placement.height_mm),
+    )
+}
+
+/// Write one `<image>` element with the given transform
+fn write_image(writer: &mut XmlWriter, placed: &PlacedImage, transform: &str) {
+    writer.start_element("image");
+    writer.write_attribute("width", &placed.image.width.to_string());
+    writer.write_attribute("height", &placed.image.height.to_string());
+    writer.write_attribute("preserveAspectRatio", "none");
+    writer.write_attribute("xlink:href", &placed.image.data_uri);
+    writer.write_attribute("transform", transform);
+    writer.end_element(); // image
+}
+
 fn format_coord(x_mm: f64, y_mm: f64) -> (String, String) {
     // 2 integer + 5 decimal -> scale by 1e5
     let scale = 100_000.0;
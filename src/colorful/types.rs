@@ -1,15 +1,27 @@
 use anyhow::{anyhow, bail, Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use gerber_parser::{gerber_types::*, parse};
-use image::ImageReader;
+use image::{imageops::FilterType, ImageFormat, ImageReader};
+use regex::Regex;
 use std::fs;
 use std::io::{BufReader, Cursor};
 use std::path::Path;
+use tracing::warn;
 
 /// Geometry extracted from a solder mask layer, represented as SVG path data strings
 /// in 10-mil coordinates with inverted Y (matching the SVG output).
 pub(crate) type MaskPaths = Vec<String>;
 
+/// Alignment mark positions in millimeters.
+pub(crate) type MarkPoints = Vec<(f64, f64)>;
+
+/// Default mark inset from the board edge, approximately 3 mil, mirroring the
+/// original script behaviour.
+pub const DEFAULT_MARK_INSET_MM: f64 = 0.0762;
+
+/// Default diameter of the flashed mark pads.
+pub const DEFAULT_MARK_PAD_DIAMETER_MM: f64 = 1.0;
+
 /// Board outline bounds expressed in millimeters.
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct BoardBounds {
@@ -31,6 +43,21 @@ impl BoardBounds {
     pub(crate) fn origin(&self) -> (f64, f64) {
         (self.min_x, self.min_y)
     }
+
+    /// Ensure the bounds enclose an area the SVG outline can be drawn from.
+    ///
+    /// An outline made of a single line or point has zero width or height.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if !(self.width() > 0.0 && self.height() > 0.0) {
+            bail!(
+                "Board outline has no area ({:.3} x {:.3} mm); check that the outline layer draws a closed shape",
+                self.width(),
+                self.height()
+            );
+        }
+
+        Ok(())
+    }
 }
 
 /// Loaded image metadata and base64 data URI.
@@ -40,18 +67,82 @@ pub(crate) struct SilkscreenImage {
     pub(crate) data_uri: String,
 }
 
-pub(crate) fn parse_outline_bounds(content: &str) -> Result<BoardBounds> {
-    let reader = BufReader::new(Cursor::new(content));
-    let doc = match parse(reader) {
-        Ok(doc) => doc,
-        Err((partial, err)) => {
-            if partial.commands().is_empty() {
-                return Err(anyhow!("Failed to parse outline: {err}"));
-            }
-            partial
+/// Area of the board an image is stretched onto, in millimeters.
+///
+/// `x_mm`/`y_mm` locate the lower-left corner of the image relative to the
+/// lower-left corner of the board outline, as seen from the image's side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImagePlacement {
+    pub x_mm: f64,
+    pub y_mm: f64,
+    pub width_mm: f64,
+    pub height_mm: f64,
+}
+
+/// A loaded image and its placement; `None` covers the whole board.
+pub(crate) struct PlacedImage {
+    pub(crate) image: SilkscreenImage,
+    pub(crate) placement: Option<ImagePlacement>,
+}
+
+/// Bounds of a layer together with the parser errors skipped to get them.
+pub(crate) struct ParsedBounds {
+    pub(crate) bounds: BoardBounds,
+    /// Number of commands the parser understood
+    pub(crate) commands: usize,
+    /// Parser errors, one line each; empty for a clean parse
+    pub(crate) problems: Vec<String>,
+}
+
+/// Compute the board bounds from an outline layer.
+///
+/// A partially parseable outline still yields bounds from the commands the
+/// parser understood, with the skipped lines logged. With `strict` any parser
+/// error fails instead.
+pub(crate) fn parse_outline_bounds(content: &str, strict: bool) -> Result<BoardBounds> {
+    let parsed = parse_layer_bounds(content)?;
+    if !parsed.problems.is_empty() {
+        let problems = parsed.problems.join("; ");
+        if strict {
+            bail!(
+                "Failed to parse outline after {} commands: {}",
+                parsed.commands,
+                problems
+            );
         }
+        warn!(
+            "Outline parsed partially, using {} recovered commands: {}",
+            parsed.commands, problems
+        );
+    }
+
+    Ok(parsed.bounds)
+}
+
+/// Compute the extents of any Gerber layer, collecting parser errors instead of failing.
+///
+/// Every operation counts, including the moves that start G36/G37 regions,
+/// so outlines drawn as filled regions get the extents of their vertices.
+pub(crate) fn parse_layer_bounds(content: &str) -> Result<ParsedBounds> {
+    let reader = BufReader::new(Cursor::new(content));
+    let (doc, fatal) = match parse(reader) {
+        Ok(doc) => (doc, None),
+        Err((partial, err)) => (partial, Some(err)),
     };
 
+    let commands = doc.commands().len();
+    if let Some(err) = &fatal {
+        if commands == 0 {
+            return Err(anyhow!("Failed to parse outline: {err}"));
+        }
+    }
+    let problems: Vec<String> = fatal
+        .iter()
+        .map(|err| err.to_string())
+        .chain(doc.errors().iter().map(|err| err.to_string()))
+        .map(|problem| problem.replace('\n', " "))
+        .collect();
+
     let mut units = doc.units.unwrap_or(Unit::Millimeters);
 
     let mut min_x = f64::INFINITY;
@@ -97,28 +188,55 @@ pub(crate) fn parse_outline_bounds(content: &str) -> Result<BoardBounds> {
         bail!("Failed to parse board outline bounds");
     }
 
-    Ok(BoardBounds {
-        min_x,
-        max_x,
-        min_y,
-        max_y,
+    Ok(ParsedBounds {
+        bounds: BoardBounds {
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+        },
+        commands,
+        problems,
     })
 }
 
+/// Size used for SVG images that declare neither a viewBox nor a size
+const DEFAULT_SVG_SIZE: u32 = 100;
+
 pub(crate) fn load_image(path: &Path) -> Result<SilkscreenImage> {
     let bytes = fs::read(path).with_context(|| format!("Read image {}", path.display()))?;
+    if is_svg(path, &bytes) {
+        return load_svg_image(path, &bytes);
+    }
+
     let reader = ImageReader::new(std::io::Cursor::new(&bytes))
         .with_guessed_format()
         .context("Guess image format")?;
+
+    // TIFF/BMP and friends are not reliably rendered inside the SVG, so re-encode them as PNG.
+    if !is_web_friendly(reader.format()) {
+        let image = reader.decode().context("Decode image")?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .context("Transcode image to PNG")?;
+        let encoded = general_purpose::STANDARD.encode(png);
+
+        return Ok(SilkscreenImage {
+            width: image.width(),
+            height: image.height(),
+            data_uri: format!("data:image/png;base64,{}", encoded),
+        });
+    }
+
+    // Trust the decoded format rather than the extension, which may be mislabeled.
+    let mime = reader
+        .format()
+        .map_or("image/png", |format| format.to_mime_type());
     let (width, height) = reader.into_dimensions().context("Read image dimensions")?;
 
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|s| s.to_ascii_lowercase())
-        .unwrap_or_else(|| "png".to_string());
     let encoded = general_purpose::STANDARD.encode(bytes);
-    let data_uri = format!("data:image/{};base64,{}", ext, encoded);
+    let data_uri = format!("data:{};base64,{}", mime, encoded);
 
     Ok(SilkscreenImage {
         width,
@@ -127,17 +245,263 @@ pub(crate) fn load_image(path: &Path) -> Result<SilkscreenImage> {
     })
 }
 
+/// Load an image resized by `scale` and re-encoded as PNG
+pub(crate) fn load_image_scaled(path: &Path, scale: f64) -> Result<SilkscreenImage> {
+    let bytes = fs::read(path).with_context(|| format!("Read image {}", path.display()))?;
+    if is_svg(path, &bytes) {
+        // Vector content has no pixels to drop
+        return load_svg_image(path, &bytes);
+    }
+
+    let image = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .context("Guess image format")?
+        .decode()
+        .context("Decode image")?;
+
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    let resized = image.resize_exact(width, height, FilterType::Triangle);
+
+    let mut png = Vec::new();
+    resized
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .context("Encode downscaled image")?;
+    let encoded = general_purpose::STANDARD.encode(png);
+
+    Ok(SilkscreenImage {
+        width,
+        height,
+        data_uri: format!("data:image/png;base64,{}", encoded),
+    })
+}
+
+/// Whether an image file holds SVG markup, by extension or content
+fn is_svg(path: &Path, bytes: &[u8]) -> bool {
+    let by_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+
+    by_extension || head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg"))
+}
+
+/// Embed an SVG image as-is, sized from its viewBox or width/height attributes
+fn load_svg_image(path: &Path, bytes: &[u8]) -> Result<SilkscreenImage> {
+    let text = std::str::from_utf8(bytes)
+        .with_context(|| format!("SVG image {} is not valid UTF-8", path.display()))?;
+    let root_regex = Regex::new(r"<svg\b[^>]*>").context("Failed to compile SVG tag regex")?;
+    let root = root_regex
+        .find(text)
+        .with_context(|| format!("No <svg> element in {}", path.display()))?
+        .as_str();
+
+    let (width, height) = svg_size(root).unwrap_or_else(|| {
+        warn!(
+            "{} has no viewBox or size, assuming {}x{}",
+            path.display(),
+            DEFAULT_SVG_SIZE,
+            DEFAULT_SVG_SIZE
+        );
+        (DEFAULT_SVG_SIZE, DEFAULT_SVG_SIZE)
+    });
+
+    Ok(SilkscreenImage {
+        width,
+        height,
+        data_uri: format!(
+            "data:image/svg+xml;base64,{}",
+            general_purpose::STANDARD.encode(bytes)
+        ),
+    })
+}
+
+/// Intrinsic size of an SVG root element, preferring the viewBox
+fn svg_size(root: &str) -> Option<(u32, u32)> {
+    let attribute = |name: &str| {
+        let pattern = format!(r#"\s{}\s*=\s*["']([^"']*)["']"#, name);
+        Regex::new(&pattern)
+            .ok()?
+            .captures(root)
+            .map(|caps| caps[1].to_string())
+    };
+    // Lengths may carry units ("50mm"); only their number matters for the ratio
+    let length = |value: &str| -> Option<f64> {
+        let number: String = value
+            .trim()
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '-' || *c == '+')
+            .collect();
+        number.parse().ok()
+    };
+    let pixels = |value: f64| (value > 0.0).then(|| (value.round() as u32).max(1));
+
+    if let Some(view_box) = attribute("viewBox") {
+        let values: Vec<f64> = view_box
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|value| !value.is_empty())
+            .filter_map(|value| value.parse().ok())
+            .collect();
+        if let [_, _, width, height] = values[..] {
+            if let (Some(width), Some(height)) = (pixels(width), pixels(height)) {
+                return Some((width, height));
+            }
+        }
+    }
+
+    let width = pixels(length(&attribute("width")?)?)?;
+    let height = pixels(length(&attribute("height")?)?)?;
+    Some((width, height))
+}
+
+/// Whether a format can be embedded in the SVG data URI as-is
+fn is_web_friendly(format: Option<ImageFormat>) -> bool {
+    matches!(
+        format,
+        Some(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif)
+    )
+}
+
 pub(crate) fn mm_to_mil_10(val: f64) -> f64 {
     val / 0.254
 }
 
-pub(crate) fn compute_mark_points(bounds: &BoardBounds) -> Vec<(f64, f64)> {
-    // Inset by approximately 3 mil (0.0762mm) to mirror the original script behaviour.
-    const INSET_MM: f64 = 0.0762;
-    let min_x = bounds.min_x + INSET_MM;
-    let max_x = bounds.max_x - INSET_MM;
-    let min_y = bounds.min_y + INSET_MM;
-    let max_y = bounds.max_y - INSET_MM;
+/// Mark points at three corners of the board, moved inwards by `inset_mm`.
+pub(crate) fn compute_mark_points(bounds: &BoardBounds, inset_mm: f64) -> MarkPoints {
+    let min_x = bounds.min_x + inset_mm;
+    let max_x = bounds.max_x - inset_mm;
+    let min_y = bounds.min_y + inset_mm;
+    let max_y = bounds.max_y - inset_mm;
 
     vec![(min_x, min_y), (min_x, max_y), (max_x, max_y)]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    /// Outline cut off in the middle of its last coordinate, without `M02*`
+    const TRUNCATED_OUTLINE: &str = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\nD10*\n\
+        X0Y0D02*\nX50000000Y0D01*\nX50000000Y30000000D01*\nX0Y30000000D01*\nX0Y0D01*\nX25000000Y3";
+
+    #[test]
+    fn test_truncated_outline_uses_partial_commands() {
+        let parsed = parse_layer_bounds(TRUNCATED_OUTLINE).unwrap();
+        assert_eq!(parsed.commands, 9);
+        assert_eq!(parsed.problems.len(), 2, "{:?}", parsed.problems);
+        assert!(parsed.problems[0].contains("Line 10: 'X25000000Y3'"));
+        assert!(parsed.problems[1].contains("Missing M02"));
+
+        let bounds = parse_outline_bounds(TRUNCATED_OUTLINE, false).unwrap();
+        assert_eq!((bounds.min_x, bounds.max_x), (0.0, 50.0));
+        assert_eq!((bounds.min_y, bounds.max_y), (0.0, 30.0));
+    }
+
+    #[test]
+    fn test_truncated_outline_fails_in_strict_mode() {
+        let err = parse_outline_bounds(TRUNCATED_OUTLINE, true).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Failed to parse outline after 9 commands"));
+        assert!(message.contains("X25000000Y3"));
+
+        // A file missing only its M02 is a parser warning too
+        let unterminated = TRUNCATED_OUTLINE.replace("X25000000Y3", "");
+        assert!(parse_outline_bounds(&unterminated, true).is_err());
+        assert!(parse_outline_bounds(&format!("{}M02*\n", unterminated), true).is_ok());
+    }
+
+    #[test]
+    fn test_region_outline_bounds() {
+        // Closed contour starting from a move to its leftmost vertex
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\nD10*\nG01*\nG36*\n\
+            X-5000000Y10000000D02*\nX0Y0D01*\nX40000000Y0D01*\nX40000000Y20000000D01*\n\
+            X0Y20000000D01*\nX-5000000Y10000000D01*\nG37*\nM02*\n";
+
+        let bounds = parse_outline_bounds(content, true).unwrap();
+
+        assert_eq!((bounds.min_x, bounds.max_x), (-5.0, 40.0));
+        assert_eq!((bounds.min_y, bounds.max_y), (0.0, 20.0));
+    }
+
+    #[test]
+    fn test_load_image_transcodes_bmp_to_png() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("art.bmp");
+        RgbImage::new(4, 3).save(&path).unwrap();
+
+        let image = load_image(&path).unwrap();
+
+        assert_eq!((image.width, image.height), (4, 3));
+        assert!(image.data_uri.starts_with("data:image/png;base64,"));
+    }
+
+    #[test]
+    fn test_svg_image_is_embedded_unchanged() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("logo.svg");
+        let svg = r##"<?xml version="1.0"?>
+<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 120 40"><rect width="120" height="40" fill="#c00"/></svg>"##;
+        fs::write(&path, svg).unwrap();
+
+        let image = load_image(&path).unwrap();
+
+        assert_eq!((image.width, image.height), (120, 40));
+        let encoded = image
+            .data_uri
+            .strip_prefix("data:image/svg+xml;base64,")
+            .expect("SVG should not be converted to PNG");
+        assert_eq!(
+            general_purpose::STANDARD.decode(encoded).unwrap(),
+            svg.as_bytes()
+        );
+
+        // Scaling leaves vector content alone
+        assert_eq!(
+            load_image_scaled(&path, 0.5).unwrap().data_uri,
+            image.data_uri
+        );
+    }
+
+    #[test]
+    fn test_svg_size_without_view_box() {
+        assert_eq!(
+            svg_size(r#"<svg width="30mm" height='12.4mm'>"#),
+            Some((30, 12))
+        );
+        assert_eq!(
+            svg_size(r#"<svg viewBox="0,0,64,32" width="100%">"#),
+            Some((64, 32))
+        );
+        assert_eq!(svg_size("<svg xmlns=\"http://www.w3.org/2000/svg\">"), None);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bare");
+        fs::write(
+            &path,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"><circle r=\"5\"/></svg>",
+        )
+        .unwrap();
+        let image = load_image(&path).unwrap();
+        assert_eq!(
+            (image.width, image.height),
+            (DEFAULT_SVG_SIZE, DEFAULT_SVG_SIZE)
+        );
+    }
+
+    #[test]
+    fn test_load_image_mime_follows_detected_format() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mislabeled.jpg");
+        RgbImage::new(2, 2)
+            .save_with_format(&path, ImageFormat::Png)
+            .unwrap();
+
+        let image = load_image(&path).unwrap();
+
+        assert!(image.data_uri.starts_with("data:image/png;base64,"));
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! This module handles CLI argument parsing and application settings.
 
+use crate::archive::{Compression, ZipSettings, STDIN_PATH};
+use crate::patterns::{LayerType, DEFAULT_MAX_LAYERS, DEFAULT_MIN_LAYER_TYPES};
+use crate::report::DEFAULT_MIN_BOARD_SIZE_MM;
 use anyhow::{anyhow, Context, Result};
-use clap::{ColorChoice, Parser};
-use std::path::PathBuf;
+use clap::{ColorChoice, Parser, Subcommand};
+use regex::Regex;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 #[derive(Debug, Clone, Parser)]
@@ -16,33 +20,85 @@ use tracing::info;
     color = ColorChoice::Auto
 )]
 pub struct Config {
+    /// Optional subcommand (defaults to conversion)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// EDA software type for input files
     #[arg(
         short = 'e',
         long = "eda",
         default_value = "auto",
-        value_parser = ["auto", "kicad", "jlc", "protel"],
-        help = "EDA software type (auto, kicad, jlc, protel)"
+        value_parser = ["auto", "kicad", "jlc", "protel", "easyeda", "pads", "allegro", "diptrace", "proteus", "custom"],
+        help = "EDA software type (auto, kicad, jlc, protel, easyeda, pads, allegro, diptrace, proteus, custom)"
     )]
     pub eda: String,
 
+    /// Layer pattern file used with `--eda custom`
+    #[arg(
+        long = "custom-patterns",
+        value_name = "FILE",
+        help = "TOML file mapping layer type names to filename regexes (for --eda custom)"
+    )]
+    pub custom_patterns: Option<PathBuf>,
+
+    /// Additional layer patterns appended to the selected matcher
+    #[arg(
+        long = "extra-pattern",
+        value_name = "LAYER=REGEX",
+        value_parser = parse_extra_pattern,
+        help = "Add a filename regex for a layer type to the matcher, e.g. TopCopper='-Front\\.gbr$' (repeatable)"
+    )]
+    pub extra_patterns: Vec<(LayerType, String)>,
+
     /// Input path (file or directory)
     #[arg(
         short = 'p',
         long = "path",
         default_value = ".",
         value_name = "PATH",
-        help = "Input file or directory path"
+        help = "Input file or directory path (- reads a ZIP archive from stdin)"
     )]
     pub path: PathBuf,
 
+    /// Additional boards to convert concurrently
+    #[arg(
+        long = "board",
+        value_name = "PATH",
+        help = "Convert this board folder or ZIP into its own output subfolder (repeatable, replaces --path)"
+    )]
+    pub boards: Vec<PathBuf>,
+
+    /// Text file listing the exact input files to convert
+    #[arg(
+        long = "input-list",
+        value_name = "FILE",
+        help = "Convert only the files listed in FILE, one path per line, instead of scanning the input folder"
+    )]
+    pub input_list: Option<PathBuf>,
+
+    /// Treat the input path as a directory of boards
+    #[arg(
+        long = "batch",
+        help = "Convert every ZIP and folder inside --path as a separate board"
+    )]
+    pub batch: bool,
+
+    /// Worker threads for multi-board conversion
+    #[arg(
+        long = "threads",
+        value_name = "N",
+        help = "Maximum number of boards converted in parallel (defaults to the CPU count)"
+    )]
+    pub threads: Option<usize>,
+
     /// Output directory path
     #[arg(
         short = 'o',
         long = "output_path",
         default_value = "./output",
         value_name = "OUTPUT",
-        help = "Output file or directory path"
+        help = "Output file or directory path ({board} is replaced by the board name)"
     )]
     pub output_path: PathBuf,
 
@@ -59,21 +115,329 @@ pub struct Config {
         short = 'n',
         long = "zip_name",
         default_value = "Gerber",
-        help = "Name for the output ZIP archive"
+        help = "Name for the output ZIP archive ({board} is replaced by the board name)"
     )]
     pub zip_name: String,
 
+    /// Exact path for the output ZIP file
+    #[arg(
+        long = "zip-path",
+        value_name = "FILE",
+        help = "Write the ZIP archive to this exact path (implies --zip)"
+    )]
+    pub zip_path: Option<PathBuf>,
+
+    /// Keep the loose output files next to the ZIP archive
+    #[arg(
+        long = "keep-files",
+        help = "With --zip, also keep the converted files in the output directory"
+    )]
+    pub keep_files: bool,
+
+    /// Write converted layers straight into the ZIP archive
+    #[arg(
+        long = "stream-zip",
+        conflicts_with_all = ["keep_files", "checksums", "deterministic", "top_color_image", "bottom_color_image"],
+        help = "With --zip, write converted layers straight into the archive instead of staging them on disk"
+    )]
+    pub stream_zip: bool,
+
+    /// Skip the embedded ordering-instructions text file
+    #[arg(
+        long = "no-readme",
+        help = "Do not add the embedded ordering-instructions file to the output"
+    )]
+    pub no_asset: bool,
+
+    /// Language of the embedded ordering-instructions file
+    #[arg(
+        long = "asset-lang",
+        default_value = "zh",
+        value_parser = ["zh", "en"],
+        help = "Language of the embedded ordering-instructions file (zh, en)"
+    )]
+    pub asset_lang: String,
+
+    /// Compression method for the output ZIP
+    #[arg(
+        long = "compression",
+        default_value = "stored",
+        value_parser = ["stored", "deflate"],
+        help = "Compression method for the ZIP archive (stored, deflate)"
+    )]
+    pub compression: String,
+
+    /// Compression level for deflate
+    #[arg(
+        long = "compression-level",
+        value_name = "LEVEL",
+        value_parser = clap::value_parser!(i64).range(0..=9),
+        help = "Deflate compression level (0-9)"
+    )]
+    pub compression_level: Option<i64>,
+
+    /// Line ending used for every written Gerber and drill file
+    #[arg(
+        long = "normalize-line-endings",
+        value_name = "STYLE",
+        num_args = 0..=1,
+        default_missing_value = "lf",
+        value_parser = ["lf", "crlf"],
+        help = "Write every Gerber and drill file with one line ending style (lf, crlf; default lf)"
+    )]
+    pub normalize_line_endings: Option<String>,
+
+    /// Produce reproducible output
+    #[arg(
+        long = "deterministic",
+        help = "Produce byte-identical output for identical inputs (fixed timestamps, sorted entries)"
+    )]
+    pub deterministic: bool,
+
+    /// Seed for the hash aperture random source
+    #[arg(
+        long = "seed",
+        value_name = "U64",
+        help = "Seed hash aperture generation and fix the header time so repeated runs produce identical Gerbers"
+    )]
+    pub seed: Option<u64>,
+
+    /// Remove X2 attribute commands from Gerber files
+    #[arg(
+        long = "strip-attributes",
+        help = "Remove Gerber X2 attribute commands (%TF, %TA, %TO, %TD) from the output"
+    )]
+    pub strip_attributes: bool,
+
+    /// Remove source tool, author and date comments from Gerber files
+    #[arg(
+        long = "scrub-comments",
+        help = "Remove G04 comments and file attributes naming the source EDA tool, author or date"
+    )]
+    pub scrub_comments: bool,
+
+    /// Print the hash fingerprint embedded in each layer
+    #[arg(
+        long = "explain-hash",
+        help = "Print the MD5 fingerprint and hash aperture inserted into each layer"
+    )]
+    pub explain_hash: bool,
+
+    /// Print the coordinate extents of every converted layer
+    #[arg(
+        long = "bounds-report",
+        help = "Print the coordinate extents of each layer and flag layers reaching past the outline"
+    )]
+    pub bounds_report: bool,
+
+    /// Skip hash aperture insertion
+    #[arg(
+        long = "no-hash",
+        help = "Do not insert the fingerprint hash aperture into Gerber files"
+    )]
+    pub no_hash: bool,
+
+    /// Maximum Gerber size for hash aperture insertion
+    #[arg(
+        long = "max-hash-size",
+        value_name = "BYTES",
+        default_value_t = 30_000_000,
+        help = "Skip hash aperture insertion for Gerber files larger than BYTES"
+    )]
+    pub max_hash_size: usize,
+
+    /// Compute fingerprints as for an imported PCB document
+    #[arg(
+        long = "imported-pcb",
+        help = "Compute hash apertures in imported-PCB-document mode"
+    )]
+    pub imported_pcb: bool,
+
+    /// Free space to keep when extracting ZIP input
+    #[arg(
+        long = "min-free-space",
+        value_name = "BYTES",
+        default_value_t = 0,
+        help = "Extra free space required on the temp volume beyond the uncompressed ZIP size"
+    )]
+    pub min_free_space: u64,
+
+    /// Write a checksum manifest of the outputs
+    #[arg(
+        long = "checksums",
+        help = "Write CHECKSUMS.txt with the MD5 of every output file (included in the ZIP)"
+    )]
+    pub checksums: bool,
+
+    /// File listing every produced output path
+    #[arg(
+        long = "list-output",
+        value_name = "FILE",
+        help = "Write the absolute path of every produced file to FILE, one per line"
+    )]
+    pub list_output: Option<PathBuf>,
+
+    /// Layer types a naming convention must recognize during auto-detection
+    #[arg(
+        long = "min-layer-types",
+        value_name = "N",
+        default_value_t = DEFAULT_MIN_LAYER_TYPES,
+        help = "Distinct layer types an EDA naming convention must match to be auto-detected"
+    )]
+    pub min_layer_types: usize,
+
+    /// Highest inner layer number a filename may map to
+    #[arg(
+        long = "max-layers",
+        value_name = "N",
+        default_value_t = DEFAULT_MAX_LAYERS,
+        help = "Skip files whose name maps to an inner layer number above N"
+    )]
+    pub max_layers: u32,
+
+    /// Number inner layers 1..N in stackup order
+    #[arg(
+        long = "renumber-inner",
+        help = "Renumber matched inner layers contiguously from 1, e.g. In2 and In5 become InnerLayer1 and InnerLayer2"
+    )]
+    pub renumber_inner: bool,
+
+    /// Trim trailing spaces/dots from extracted ZIP entry names
+    #[arg(
+        long = "trim-whitespace-filenames",
+        help = "Trim trailing spaces and dots from file names extracted from a ZIP input"
+    )]
+    pub trim_whitespace_filenames: bool,
+
+    /// Extract nested ZIP entries into a single folder
+    #[arg(
+        long = "flatten",
+        help = "Ignore folders inside a ZIP input and extract every file into one directory"
+    )]
+    pub flatten: bool,
+
+    /// File names or glob patterns to exclude from discovery
+    #[arg(
+        long = "ignore",
+        value_name = "PATTERN",
+        help = "Ignore input files matching this name or glob (repeatable)"
+    )]
+    pub ignore: Vec<String>,
+
+    /// Bundle IPC-D-356 netlists with the output
+    #[arg(
+        long = "include-netlist",
+        help = "Include IPC-D-356 netlists (.d356/.ipc) in the output"
+    )]
+    pub include_netlist: bool,
+
+    /// Carry fabrication/courtyard layers through to the output
+    #[arg(
+        long = "keep-extra",
+        help = "Keep fabrication and courtyard layers JLC does not need in the output"
+    )]
+    pub keep_extra: bool,
+
+    /// Convert inch drill files to millimeters
+    #[arg(
+        long = "normalize-drill",
+        help = "Convert inch Excellon drill files to millimeters"
+    )]
+    pub normalize_drill: bool,
+
+    /// Rewrite drill headers into the form JLC expects
+    #[arg(
+        long = "normalize-drill-header",
+        help = "Add a missing M48 header and FMAT,2 to drill files and rewrite LZ coordinates with decimal points"
+    )]
+    pub normalize_drill_header: bool,
+
+    /// Split drill files that mix plated and non-plated tools
+    #[arg(
+        long = "split-drill",
+        help = "Split combined drill files into PTH and NPTH outputs by tool plating"
+    )]
+    pub split_drill: bool,
+
+    /// Per-file drill classification overrides
+    #[arg(
+        long = "drill-as",
+        value_name = "FILE=TYPE",
+        value_parser = parse_drill_override,
+        help = "Classify the named drill file as npth or pth, e.g. holes.drl=npth (repeatable)"
+    )]
+    pub drill_as: Vec<(String, LayerType)>,
+
+    /// Only write these layer types
+    #[arg(
+        long = "only",
+        value_name = "LAYERS",
+        value_delimiter = ',',
+        value_parser = parse_layer_name,
+        help = "Only write the given comma-separated layer types, e.g. TopCopper,BoardOutline (see --list-layers)"
+    )]
+    pub only: Vec<LayerType>,
+
+    /// Never write these layer types
+    #[arg(
+        long = "skip",
+        value_name = "LAYERS",
+        value_delimiter = ',',
+        value_parser = parse_layer_name,
+        help = "Skip the given comma-separated layer types, e.g. TopPasteMask,BottomPasteMask"
+    )]
+    pub skip: Vec<LayerType>,
+
+    /// List the supported layer mappings and exit
+    #[arg(
+        long = "list-layers",
+        help = "Print every supported layer type with its JLC filename and exit"
+    )]
+    pub list_layers: bool,
+
+    /// Check the installation and exit
+    #[arg(
+        long = "doctor",
+        help = "Check that embedded assets, the RSA key and Gerber processing work, then exit"
+    )]
+    pub doctor: bool,
+
     /// Enable verbose logging
     #[arg(short = 'v', long = "verbose", help = "Enable verbose logging output")]
     pub verbose: bool,
 
+    /// Also write the full debug log to a file
+    #[arg(
+        long = "log-file",
+        value_name = "PATH",
+        help = "Write a complete debug log to PATH, independent of console verbosity"
+    )]
+    pub log_file: Option<PathBuf>,
+
+    /// Suppress all non-error output
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        conflicts_with = "verbose",
+        help = "Only print errors (implies --no-progress)"
+    )]
+    pub quiet: bool,
+
     /// Disable progress bars
     #[arg(long = "no-progress", help = "Disable progress indicators")]
     pub no_progress: bool,
 
+    /// Convert each detected EDA format separately
+    #[arg(
+        long = "multi-eda",
+        help = "Convert each EDA format found in the input into its own output folder"
+    )]
+    pub multi_eda: bool,
+
     /// Optional colorful silkscreen image for top layer
     #[arg(
         long = "top_color_image",
+        visible_alias = "colorful-top",
         value_name = "PATH",
         help = "Path to colorful silkscreen image for the top layer"
     )]
@@ -82,10 +446,118 @@ pub struct Config {
     /// Optional colorful silkscreen image for bottom layer
     #[arg(
         long = "bottom_color_image",
+        visible_alias = "colorful-bottom",
         value_name = "PATH",
         help = "Path to colorful silkscreen image for the bottom layer"
     )]
     pub bottom_color_image: Option<PathBuf>,
+
+    /// Solder mask layer used to cut the top colorful silkscreen
+    #[arg(
+        long = "colorful-top-mask",
+        value_name = "PATH",
+        help = "Solder mask Gerber for the top colorful silkscreen (default: the converted top mask)"
+    )]
+    pub colorful_top_mask: Option<PathBuf>,
+
+    /// Solder mask layer used to cut the bottom colorful silkscreen
+    #[arg(
+        long = "colorful-bottom-mask",
+        value_name = "PATH",
+        help = "Solder mask Gerber for the bottom colorful silkscreen (default: the converted bottom mask)"
+    )]
+    pub colorful_bottom_mask: Option<PathBuf>,
+
+    /// Maximum size of each colorful silkscreen file
+    #[arg(
+        long = "colorful-max-size",
+        value_name = "BYTES",
+        help = "Downscale colorful silkscreen images until each output file fits in BYTES"
+    )]
+    pub colorful_max_size: Option<u64>,
+
+    /// Reject board outlines that only parse partially
+    #[arg(
+        long = "strict-outline",
+        help = "Fail when the board outline cannot be parsed completely instead of using the readable part"
+    )]
+    pub strict_outline: bool,
+
+    /// Smallest board edge accepted without a warning
+    #[arg(
+        long = "min-board-size",
+        value_name = "MM",
+        default_value_t = DEFAULT_MIN_BOARD_SIZE_MM,
+        help = "Warn when the board outline is smaller than MM millimeters in either direction"
+    )]
+    pub min_board_size: f64,
+
+    /// Treat manufacturability warnings as errors
+    #[arg(
+        long = "strict",
+        help = "Fail instead of warning when the board is smaller than --min-board-size, an inner layer exceeds --max-layers or an embedded asset is missing"
+    )]
+    pub strict: bool,
+}
+
+/// Parse an `--extra-pattern LAYER=REGEX` value
+fn parse_extra_pattern(value: &str) -> std::result::Result<(LayerType, String), String> {
+    let (name, pattern) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected <layer>=<regex>, got '{}'", value))?;
+
+    let layer = LayerType::from_name(name)
+        .ok_or_else(|| format!("unknown layer type '{}' (see --list-layers)", name.trim()))?;
+    Regex::new(pattern).map_err(|e| format!("invalid regex '{}': {}", pattern, e))?;
+
+    Ok((layer, pattern.to_string()))
+}
+
+fn parse_layer_name(name: &str) -> std::result::Result<LayerType, String> {
+    LayerType::from_name(name)
+        .ok_or_else(|| format!("unknown layer type '{}' (see --list-layers)", name.trim()))
+}
+
+/// Parse a `--drill-as <file>=<npth|pth>` override
+fn parse_drill_override(value: &str) -> std::result::Result<(String, LayerType), String> {
+    let (file, kind) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected <file>=<npth|pth>, got '{}'", value))?;
+
+    let layer = match kind.trim().to_ascii_lowercase().as_str() {
+        "npth" => LayerType::NpthThrough,
+        "pth" => LayerType::PthThrough,
+        other => {
+            return Err(format!(
+                "unknown drill type '{}' (expected npth or pth)",
+                other
+            ))
+        }
+    };
+
+    Ok((file.trim().to_string(), layer))
+}
+
+/// Auxiliary commands besides the default conversion
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Compare two converted JLC packages layer-by-layer
+    Diff {
+        /// First package directory
+        #[arg(value_name = "DIR_A")]
+        left: PathBuf,
+
+        /// Second package directory
+        #[arg(value_name = "DIR_B")]
+        right: PathBuf,
+    },
+}
+
+impl Default for Config {
+    /// Configuration equivalent to running the CLI without arguments
+    fn default() -> Self {
+        Config::parse_from(["transjlc"])
+    }
 }
 
 impl Config {
@@ -93,12 +565,7 @@ impl Config {
     pub fn from_args() -> Result<Self> {
         let config = Config::parse();
 
-        // Set up tracing with environment variable support
-        // RUST_LOG takes precedence over verbose flag
-        let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("off"));
-
-        tracing_subscriber::fmt().with_env_filter(env_filter).init();
+        crate::logging::init(config.log_file.as_deref())?;
 
         if config.verbose {
             info!("Configuration: {:?}", config);
@@ -107,6 +574,21 @@ impl Config {
         Ok(config)
     }
 
+    /// Whether `--only`/`--skip` let a matched layer through
+    ///
+    /// `InnerLayer` and `Extra` cover every inner layer and extra file.
+    pub fn layer_selected(&self, layer: &LayerType) -> bool {
+        let same_kind =
+            |other: &LayerType| std::mem::discriminant(other) == std::mem::discriminant(layer);
+        (self.only.is_empty() || self.only.iter().any(same_kind))
+            && !self.skip.iter().any(same_kind)
+    }
+
+    /// Whether progress bars should be shown
+    pub fn show_progress(&self) -> bool {
+        !self.no_progress && !self.quiet
+    }
+
     /// Get normalized EDA type
     pub fn get_eda_type(&self) -> EdaType {
         match self.eda.to_lowercase().as_str() {
@@ -114,20 +596,145 @@ impl Config {
             "kicad" => EdaType::KiCad,
             "protel" => EdaType::Protel,
             "jlc" => EdaType::Jlc,
+            "easyeda" => EdaType::EasyEdaStd,
+            "pads" => EdaType::Pads,
+            "allegro" => EdaType::Allegro,
+            "diptrace" => EdaType::DipTrace,
+            "proteus" => EdaType::Proteus,
             custom => EdaType::Custom(custom.to_string()),
         }
     }
 
+    /// Get ZIP writer settings from the compression options
+    pub fn get_zip_settings(&self) -> ZipSettings {
+        let compression = match self.compression.to_lowercase().as_str() {
+            "deflate" => Compression::Deflate,
+            _ => Compression::Stored,
+        };
+
+        ZipSettings::new()
+            .with_compression(compression)
+            .with_compression_level(self.compression_level)
+            .with_deterministic(self.deterministic)
+    }
+
+    /// Line terminator requested with `--normalize-line-endings`, if any
+    pub fn output_line_ending(&self) -> Option<&'static str> {
+        self.normalize_line_endings
+            .as_deref()
+            .map(|style| match style.to_lowercase().as_str() {
+                "crlf" => "\r\n",
+                _ => "\n",
+            })
+    }
+
+    /// Path of the ZIP archive to create, if the output should be zipped
+    ///
+    /// An explicit `--zip-path` wins over the `output_path`/`zip_name` composition.
+    pub fn zip_archive_path(&self) -> Option<PathBuf> {
+        if let Some(zip_path) = &self.zip_path {
+            return Some(zip_path.clone());
+        }
+
+        self.zip
+            .then(|| self.output_path.join(format!("{}.zip", self.zip_name)))
+    }
+
+    /// `--zip-path` with `-<suffix>` appended to its file stem
+    ///
+    /// Runs that convert several boards or EDA groups write one archive each,
+    /// so they cannot all use the exact path given.
+    pub fn suffixed_zip_path(&self, suffix: &str) -> Option<PathBuf> {
+        let zip_path = self.zip_path.as_ref()?;
+        let stem = zip_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let mut name = format!("{}-{}", stem, suffix);
+        if let Some(extension) = zip_path.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        Some(zip_path.with_file_name(name))
+    }
+
+    /// Name of the board being converted, used for `{board}` templates
+    ///
+    /// This is the stem of a Gerber job file directly inside an input
+    /// directory, otherwise the input folder name or file stem.
+    pub fn board_name(&self) -> String {
+        const FALLBACK: &str = "board";
+
+        if self.path == Path::new(STDIN_PATH) {
+            return FALLBACK.to_string();
+        }
+
+        let path = self
+            .path
+            .canonicalize()
+            .unwrap_or_else(|_| self.path.clone());
+        let name = if path.is_dir() {
+            let job = std::fs::read_dir(&path)
+                .ok()
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| crate::gbrjob::is_job_file(file))
+                .min();
+            match job {
+                Some(job) => job.file_stem().map(|stem| stem.to_os_string()),
+                None => path.file_name().map(|name| name.to_os_string()),
+            }
+        } else {
+            path.file_stem().map(|stem| stem.to_os_string())
+        };
+
+        name.map(|name| name.to_string_lossy().into_owned())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| FALLBACK.to_string())
+    }
+
+    /// Replace `{board}` in `output_path` and `zip_name` with the board name
+    pub fn expand_board_template(&mut self) {
+        const PLACEHOLDER: &str = "{board}";
+
+        let output_path = self.output_path.to_string_lossy();
+        if !output_path.contains(PLACEHOLDER) && !self.zip_name.contains(PLACEHOLDER) {
+            return;
+        }
+
+        let board = self.board_name();
+        self.output_path = PathBuf::from(output_path.replace(PLACEHOLDER, &board));
+        self.zip_name = self.zip_name.replace(PLACEHOLDER, &board);
+        info!("Using board name '{}' for output templates", board);
+    }
+
     /// Validate configuration settings
     pub fn validate(&self) -> Result<()> {
-        // Validate input path exists
-        if !self.path.exists() {
+        if self.quiet && self.verbose {
+            return Err(anyhow!("--quiet and --verbose cannot be used together"));
+        }
+
+        // Validate input path exists (`-` reads a ZIP from stdin)
+        if self.path != Path::new(STDIN_PATH) && !self.path.exists() {
             return Err(anyhow!(
                 "Input path does not exist: {}",
                 self.path.display()
             ));
         }
 
+        // Custom matchers are defined entirely by the pattern file
+        if let EdaType::Custom(_) = self.get_eda_type() {
+            if self.custom_patterns.is_none() {
+                return Err(anyhow!("--eda custom requires --custom-patterns <FILE>"));
+            }
+        }
+        if let Some(path) = &self.custom_patterns {
+            if !path.exists() {
+                return Err(anyhow!("Custom pattern file not found: {}", path.display()));
+            }
+        }
+
         // Create output directory if it doesn't exist
         if !self.output_path.exists() {
             std::fs::create_dir_all(&self.output_path).with_context(|| {
@@ -139,6 +746,10 @@ impl Config {
             info!("Created output directory: {}", self.output_path.display());
         }
 
+        if self.stream_zip && self.zip_archive_path().is_none() {
+            return Err(anyhow!("--stream-zip requires --zip or --zip-path"));
+        }
+
         // Validate optional colorful silkscreen images
         if let Some(path) = &self.top_color_image {
             if !path.exists() {
@@ -158,6 +769,18 @@ impl Config {
             }
         }
 
+        for path in [&self.colorful_top_mask, &self.colorful_bottom_mask]
+            .into_iter()
+            .flatten()
+        {
+            if !path.exists() {
+                return Err(anyhow!(
+                    "Colorful silkscreen solder mask not found: {}",
+                    path.display()
+                ));
+            }
+        }
+
         info!("Configuration validation completed successfully");
         Ok(())
     }
@@ -170,6 +793,11 @@ pub enum EdaType {
     KiCad,
     Protel,
     Jlc,
+    EasyEdaStd,
+    Pads,
+    Allegro,
+    DipTrace,
+    Proteus,
     Custom(String),
 }
 
@@ -180,6 +808,11 @@ impl EdaType {
             EdaType::KiCad => "kicad",
             EdaType::Protel => "protel",
             EdaType::Jlc => "jlc",
+            EdaType::EasyEdaStd => "easyeda",
+            EdaType::Pads => "pads",
+            EdaType::Allegro => "allegro",
+            EdaType::DipTrace => "diptrace",
+            EdaType::Proteus => "proteus",
             EdaType::Custom(name) => name,
         }
     }
@@ -201,11 +834,219 @@ mod tests {
             no_progress: false,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         assert_eq!(config.get_eda_type(), EdaType::KiCad);
     }
 
+    #[test]
+    fn test_default_matches_cli_defaults() {
+        let config = Config::default();
+
+        assert!(config.command.is_none());
+        assert_eq!(config.get_eda_type(), EdaType::Auto);
+        assert_eq!(config.output_path, PathBuf::from("./output"));
+        assert_eq!(config.zip_name, "Gerber");
+    }
+
+    #[test]
+    fn test_list_layers_needs_no_input_path() {
+        let config = Config::parse_from(["transjlc", "--list-layers"]);
+
+        assert!(config.list_layers);
+        assert!(config.command.is_none());
+    }
+
+    #[test]
+    fn test_custom_eda_requires_pattern_file() {
+        let output_dir = tempfile::tempdir().expect("Failed to create output dir");
+        let config = Config {
+            eda: "custom".to_string(),
+            path: PathBuf::from("."),
+            output_path: output_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = Config {
+            custom_patterns: Some(output_dir.path().join("missing.toml")),
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_extra_pattern_parsing() {
+        let config = Config::parse_from([
+            "transjlc",
+            "--extra-pattern",
+            r"TopCopper=-Front\.gbr$",
+            "--extra-pattern",
+            "innerlayer=-Mid(\\d+)\\.gbr$",
+        ]);
+        assert_eq!(
+            config.extra_patterns,
+            vec![
+                (LayerType::TopCopper, r"-Front\.gbr$".to_string()),
+                (LayerType::InnerLayer(0), r"-Mid(\d+)\.gbr$".to_string()),
+            ]
+        );
+
+        for bad in ["TopCopper", "Copper=x", "TopCopper=("] {
+            assert!(Config::try_parse_from(["transjlc", "--extra-pattern", bad]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_only_and_skip_select_layers() {
+        let config = Config::parse_from([
+            "transjlc",
+            "--only",
+            "TopCopper,innerlayer",
+            "--skip",
+            "BoardOutline",
+        ]);
+        assert_eq!(
+            config.only,
+            vec![LayerType::TopCopper, LayerType::InnerLayer(0)]
+        );
+        assert!(config.layer_selected(&LayerType::TopCopper));
+        assert!(config.layer_selected(&LayerType::InnerLayer(3)));
+        assert!(!config.layer_selected(&LayerType::BottomCopper));
+
+        let config = Config::parse_from(["transjlc", "--skip", "BoardOutline"]);
+        assert!(config.layer_selected(&LayerType::TopPasteMask));
+        assert!(!config.layer_selected(&LayerType::BoardOutline));
+
+        assert!(Config::try_parse_from(["transjlc", "--only", "TopCopper,Copper"]).is_err());
+    }
+
+    #[test]
+    fn test_quiet_conflicts_with_verbose() {
+        let err = Config::try_parse_from(["transjlc", "--quiet", "--verbose"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        let config = Config::parse_from(["transjlc", "-q"]);
+        assert!(config.quiet);
+        assert!(!config.show_progress());
+
+        let config = Config {
+            quiet: true,
+            verbose: true,
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("--quiet and --verbose"));
+    }
+
+    #[test]
+    fn test_stream_zip_requires_zip_output() {
+        let err = Config::try_parse_from(["transjlc", "--zip", "--stream-zip", "--keep-files"])
+            .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        let output_dir = tempfile::tempdir().expect("Failed to create output dir");
+        let config = Config {
+            path: PathBuf::from("."),
+            output_path: output_dir.path().to_path_buf(),
+            stream_zip: true,
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("--stream-zip requires --zip"));
+
+        let config = Config {
+            zip: true,
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_zip_archive_path() {
+        let config = Config::default();
+        assert_eq!(config.zip_archive_path(), None);
+
+        let config = Config::parse_from(["transjlc", "-z", "-n", "board"]);
+        assert_eq!(
+            config.zip_archive_path(),
+            Some(PathBuf::from("./output").join("board.zip"))
+        );
+
+        let config = Config::parse_from(["transjlc", "--zip-path", "dist/rev-b.zip"]);
+        assert_eq!(
+            config.zip_archive_path(),
+            Some(PathBuf::from("dist/rev-b.zip"))
+        );
+    }
+
+    #[test]
+    fn test_compression_settings() {
+        let settings = Config::default().get_zip_settings();
+        assert_eq!(settings.compression, Compression::Stored);
+
+        let config = Config::parse_from([
+            "transjlc",
+            "--compression",
+            "deflate",
+            "--compression-level",
+            "6",
+        ]);
+        let settings = config.get_zip_settings();
+        assert_eq!(settings.compression, Compression::Deflate);
+        assert_eq!(settings.compression_level, Some(6));
+
+        assert!(Config::try_parse_from(["transjlc", "--compression-level", "12"]).is_err());
+    }
+
+    #[test]
+    fn test_line_ending_option() {
+        assert_eq!(Config::default().output_line_ending(), None);
+
+        let config = Config::parse_from(["transjlc", "--normalize-line-endings"]);
+        assert_eq!(config.output_line_ending(), Some("\n"));
+
+        let config = Config::parse_from(["transjlc", "--normalize-line-endings", "crlf"]);
+        assert_eq!(config.output_line_ending(), Some("\r\n"));
+
+        assert!(Config::try_parse_from(["transjlc", "--normalize-line-endings", "cr"]).is_err());
+    }
+
+    #[test]
+    fn test_drill_override_parsing() {
+        let config = Config::parse_from([
+            "transjlc",
+            "--drill-as",
+            "holes.drl=npth",
+            "--drill-as",
+            "plated.txt=PTH",
+        ]);
+        assert_eq!(
+            config.drill_as,
+            vec![
+                ("holes.drl".to_string(), LayerType::NpthThrough),
+                ("plated.txt".to_string(), LayerType::PthThrough),
+            ]
+        );
+
+        assert!(Config::try_parse_from(["transjlc", "--drill-as", "holes.drl"]).is_err());
+        assert!(Config::try_parse_from(["transjlc", "--drill-as", "holes.drl=via"]).is_err());
+    }
+
+    #[test]
+    fn test_diff_subcommand_parsing() {
+        let config = Config::parse_from(["transjlc", "diff", "old", "new"]);
+
+        match config.command {
+            Some(Command::Diff { left, right }) => {
+                assert_eq!(left, PathBuf::from("old"));
+                assert_eq!(right, PathBuf::from("new"));
+            }
+            other => panic!("Expected diff subcommand, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_custom_eda_type() {
         let config = Config {
@@ -218,6 +1059,7 @@ mod tests {
             no_progress: false,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         assert_eq!(
@@ -4,56 +4,261 @@
 //! to JLC format, handling file discovery, pattern matching, and processing.
 
 use crate::{
-    archive::{ArchiveCreator, ArchiveExtractor},
-    colorful::{ColorfulOptions, ColorfulSilkscreenGenerator},
+    archive::{
+        is_junk_path, is_odb_layout, ArchiveCreator, ArchiveExtractor, ZipStream, STDIN_PATH,
+    },
+    colorful::{
+        parse_layer_bounds, parse_outline_bounds, ColorfulOptions, ColorfulSilkscreenGenerator,
+    },
     config::{Config, EdaType},
+    drill::DrillProcessor,
     error::{Result, ResultExt, TransJlcError},
-    gerber::GerberProcessor,
-    patterns::{EdaPatterns, LayerType, PatternMatcher},
+    gbrjob::{self, GerberJob},
+    gerber::{file_function_layer, GerberProcessor, HashAperture},
+    patterns::{is_netlist_file, EdaPatterns, LayerType, PatternMatcher},
     progress::ProgressTracker,
+    report::{BoardDimensions, LayerBounds},
 };
-use anyhow::Context;
+use anyhow::{bail, Context};
+use flate2::read::GzDecoder;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use indicatif::ProgressBar;
+use md5::{Digest, Md5};
 use rust_embed::RustEmbed;
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fs,
+    io::{BufRead, BufReader, Read},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
 };
+use tempfile::TempDir;
 use tracing::{debug, info, warn};
 
+/// Name of the checksum manifest written by `--checksums`
+const CHECKSUMS_FILE_NAME: &str = "CHECKSUMS.txt";
+
+/// Leading bytes of gzip-compressed input
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 #[derive(RustEmbed)]
 #[folder = "Assets/"]
 struct Asset;
 
+/// Embedded ordering-instructions file for the given `--asset-lang`
+fn required_asset_name(lang: &str) -> &'static str {
+    match lang {
+        "en" => "PCB_Order_ReadMe.txt",
+        _ => "PCB下单必读.txt",
+    }
+}
+
+/// Make sure every `--asset-lang` variant of the required asset is embedded
+pub(crate) fn check_embedded_assets() -> Result<()> {
+    for lang in ["zh", "en"] {
+        let name = required_asset_name(lang);
+        if Asset::get(name).is_none() {
+            bail!("Required asset '{}' not found in embedded files", name);
+        }
+    }
+    Ok(())
+}
+
 /// The main conversion engine
 pub struct Converter {
     config: Config,
     progress_tracker: ProgressTracker,
     archive_extractor: ArchiveExtractor,
     gerber_processor: GerberProcessor,
+    drill_processor: DrillProcessor,
     processed_files: HashMap<LayerType, PathBuf>,
+    event_sender: Option<Sender<ConversionEvent>>,
+    input_files: Option<Vec<PathBuf>>,
+    group_stats: Vec<ConversionStats>,
+    hash_apertures: Vec<(LayerType, HashAperture)>,
+    unmatched_files: Vec<String>,
+    output_files: Vec<PathBuf>,
+    staging_dir: Option<TempDir>,
+    zip_stream: Option<ZipStream>,
+    detected_eda: Option<String>,
+    layer_mappings: Vec<LayerMapping>,
+    gerber_job: Option<GerberJob>,
+    input_bytes: u64,
+    output_bytes: u64,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    board_size: Option<BoardDimensions>,
+    layer_bounds: Vec<LayerBounds>,
+    inner_layer_numbers: HashMap<u32, u32>,
+    content_layers: HashMap<PathBuf, Option<LayerType>>,
+    required_asset: &'static str,
+}
+
+/// Events emitted while a conversion is running
+#[derive(Debug, Clone)]
+pub enum ConversionEvent {
+    /// The conversion pipeline has started
+    Started,
+    /// An input file was matched to a layer type
+    FileMatched { source: PathBuf, layer: LayerType },
+    /// An output layer file was written
+    LayerWritten { layer: LayerType, path: PathBuf },
+    /// The conversion completed successfully
+    Finished(Box<ConversionStats>),
+    /// The conversion failed with the given error message
+    Failed(String),
+    /// The conversion was cancelled through the cancel flag
+    Cancelled,
 }
 
 impl Converter {
     /// Create a new converter with the given configuration
     pub fn new(config: Config) -> Self {
-        let progress_enabled = !config.no_progress;
+        let progress_enabled = config.show_progress();
+        let archive_extractor = ArchiveExtractor::new()
+            .with_min_free_space(config.min_free_space)
+            .with_trim_names(config.trim_whitespace_filenames)
+            .with_flatten(config.flatten);
+        let gerber_processor = GerberProcessor::new()
+            .with_ignore_hash(config.no_hash)
+            .with_max_hash_file_size(config.max_hash_size)
+            .with_imported_pcb_doc(config.imported_pcb)
+            .with_deterministic(config.deterministic)
+            .with_seed(config.seed)
+            .with_strip_attributes(config.strip_attributes)
+            .with_scrub_comments(config.scrub_comments);
+        let drill_processor = DrillProcessor::new()
+            .with_normalize_units(config.normalize_drill)
+            .with_normalize_header(config.normalize_drill_header)
+            .with_split_plating(config.split_drill);
+        let required_asset = required_asset_name(&config.asset_lang);
 
         Self {
             config,
-            progress_tracker: ProgressTracker::new(progress_enabled),
-            archive_extractor: ArchiveExtractor::new(),
-            gerber_processor: GerberProcessor::new(),
+            progress_tracker: ProgressTracker::for_stderr(progress_enabled),
+            archive_extractor,
+            gerber_processor,
+            drill_processor,
             processed_files: HashMap::new(),
+            event_sender: None,
+            input_files: None,
+            group_stats: Vec::new(),
+            hash_apertures: Vec::new(),
+            unmatched_files: Vec::new(),
+            output_files: Vec::new(),
+            staging_dir: None,
+            zip_stream: None,
+            detected_eda: None,
+            layer_mappings: Vec::new(),
+            gerber_job: None,
+            input_bytes: 0,
+            output_bytes: 0,
+            cancel_flag: None,
+            board_size: None,
+            layer_bounds: Vec::new(),
+            inner_layer_numbers: HashMap::new(),
+            content_layers: HashMap::new(),
+            required_asset,
+        }
+    }
+
+    /// Convert exactly the given files instead of discovering them from the input path
+    pub fn with_input_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.input_files = Some(files);
+        self
+    }
+
+    /// Send conversion events to the given channel (e.g. for a GUI)
+    pub fn with_event_sender(mut self, sender: Sender<ConversionEvent>) -> Self {
+        self.event_sender = Some(sender);
+        self
+    }
+
+    /// Abort the conversion between files once the flag is set
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(flag);
+        self
+    }
+
+    /// Fail with [`TransJlcError::Cancelled`] if cancellation was requested
+    fn check_cancelled(&self) -> Result<()> {
+        match &self.cancel_flag {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(TransJlcError::Cancelled.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Emit an event if a listener is attached
+    fn emit(&self, event: ConversionEvent) {
+        if let Some(sender) = &self.event_sender {
+            // A dropped receiver only means nobody is listening anymore
+            let _ = sender.send(event);
         }
     }
 
     /// Run the complete conversion process
     pub fn run(&mut self) -> Result<()> {
+        self.emit(ConversionEvent::Started);
+
+        let result = self.execute();
+        match &result {
+            Ok(()) => self.emit(ConversionEvent::Finished(Box::new(
+                self.get_conversion_stats(),
+            ))),
+            Err(e) if is_cancelled(e) => self.emit(ConversionEvent::Cancelled),
+            Err(e) => self.emit(ConversionEvent::Failed(format!("{:#}", e))),
+        }
+
+        result
+    }
+
+    /// Run the pipeline and clean up after failures, without lifecycle events
+    ///
+    /// EDA group sub-converters use this so a listener sees a single
+    /// started/finished pair for the whole run.
+    fn execute(&mut self) -> Result<()> {
+        let result = self.run_pipeline().and_then(|()| self.write_output_list());
+        let streamed = self.zip_stream.is_some();
+        if result.is_err() {
+            if let Some(stream) = self.zip_stream.take() {
+                stream.discard();
+            }
+        }
+        if matches!(&result, Err(e) if is_cancelled(e)) {
+            info!("Conversion cancelled");
+            if self.staging_dir.take().is_none() && !streamed {
+                self.remove_written_files();
+            }
+            self.archive_extractor.cleanup();
+        }
+
+        result
+    }
+
+    /// Delete the files a cancelled run already wrote to the output directory
+    ///
+    /// Staged files go away with the staging directory and streamed layers
+    /// with the discarded archive, so only direct writes are removed here.
+    fn remove_written_files(&mut self) {
+        for (_, path) in self.processed_files.drain() {
+            match fs::remove_file(&path) {
+                Ok(()) => debug!("Removed partial output {}", path.display()),
+                Err(e) => warn!("Failed to remove partial output {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Run every conversion stage in order
+    fn run_pipeline(&mut self) -> Result<()> {
         let start = std::time::Instant::now();
         info!("Starting conversion process...");
 
         // Validate configuration
+        self.config.expand_board_template();
         self.config
             .validate()
             .context("Configuration validation failed")?;
@@ -64,22 +269,48 @@ impl Converter {
             .context("Failed to extract input files")?;
 
         // Discover and analyze files
-        let files = self
-            .discover_files(&working_path)
-            .context("Failed to discover input files")?;
+        let files = match (&self.input_files, &self.config.input_list) {
+            (Some(files), _) => files.clone(),
+            (None, Some(list_path)) => read_input_list(list_path)?,
+            (None, None) => self
+                .discover_files(&working_path)
+                .context("Failed to discover input files")?,
+        };
+
+        // Convert each EDA format separately when the input mixes several
+        if self.config.multi_eda {
+            let groups = Self::viable_eda_groups(&files, self.config.min_layer_types);
+            if groups.len() > 1 {
+                self.convert_eda_groups(groups, &working_path)?;
+                info!("Conversion completed in {} ms", start.elapsed().as_millis());
+                return Ok(());
+            }
+        }
+
+        self.prepare_output_dir()
+            .context("Failed to prepare output directory")?;
+
+        // A Gerber job file describes the stackup better than any file name
+        if let Some(job_path) = GerberJob::find(&files) {
+            self.gerber_job = Some(GerberJob::load(job_path)?);
+        }
 
         // Detect EDA format and create pattern matcher
         let patterns = self
             .create_pattern_matcher(&files)
             .context("Failed to create pattern matcher")?;
+        self.detected_eda = Some(patterns.name.clone());
 
         // Process files
         self.process_files(&files, &patterns, &working_path)
             .context("Failed to process files")?;
+        self.check_board_size()?;
 
         // Add required assets
-        self.add_required_assets()
-            .context("Failed to add required assets")?;
+        if !self.config.no_asset {
+            self.add_required_assets()
+                .context("Failed to add required assets")?;
+        }
 
         // Optional colorful silkscreen generation
         self.generate_colorful_silkscreens()
@@ -92,13 +323,33 @@ impl Converter {
         Ok(())
     }
 
+    /// Write the absolute paths of all produced files for `--list-output`
+    fn write_output_list(&self) -> Result<()> {
+        let Some(list_path) = &self.config.list_output else {
+            return Ok(());
+        };
+
+        let listing: String = self
+            .get_conversion_stats()
+            .output_files
+            .iter()
+            .map(|path| format!("{}\n", path.display()))
+            .collect();
+
+        self.write_output_file(list_path, &listing)
+            .with_path_context("write output list", list_path)?;
+
+        info!("Wrote output file list: {}", list_path.display());
+        Ok(())
+    }
+
     /// Extract input files from archive if necessary
     fn extract_input_files(&mut self) -> Result<PathBuf> {
         let progress = self.progress_tracker.create_spinner("Analyzing input...");
 
         let working_path = self
             .archive_extractor
-            .extract_if_needed(&self.config.path, !self.config.no_progress)
+            .extract_if_needed(&self.config.path, self.config.show_progress())
             .with_path_context("analyze input", &self.config.path)?;
 
         ProgressTracker::finish_progress(progress, "Input analysis completed");
@@ -109,6 +360,18 @@ impl Converter {
     fn discover_files(&self, working_path: &Path) -> Result<Vec<PathBuf>> {
         info!("Processing files in {}", working_path.display());
 
+        if is_odb_layout(working_path) {
+            return Err(TransJlcError::UnsupportedFormat {
+                hint: format!(
+                    "{} looks like an ODB++ export; export Gerber (RS-274X) and Excellon drill files from your EDA tool instead",
+                    self.config.path.display()
+                ),
+            }
+            .into());
+        }
+
+        let ignored = self.build_ignore_set()?;
+
         let files = fs::read_dir(working_path)
             .with_path_context("read directory", working_path)?
             .filter_map(|entry| {
@@ -121,11 +384,30 @@ impl Converter {
                     }
                 })
             })
+            .filter(|path| {
+                let is_ignored = is_junk_path(path)
+                    || path
+                        .file_name()
+                        .is_some_and(|name| ignored.is_match(Path::new(name)));
+                if is_ignored {
+                    debug!("Ignoring file: {}", path.display());
+                }
+                !is_ignored
+            })
             .collect::<Vec<_>>();
 
         info!("Discovered {} files", files.len());
         debug!("Files found: {:?}", files);
 
+        // The extraction folder means nothing to the user, so name the archive
+        if files.is_empty() && self.archive_extractor.temp_path() == Some(working_path) {
+            let path = if self.config.path == Path::new(STDIN_PATH) {
+                "from stdin".to_string()
+            } else {
+                self.config.path.display().to_string()
+            };
+            return Err(TransJlcError::EmptyArchive { path }.into());
+        }
         if files.is_empty() {
             return Err(TransJlcError::FileNotFound {
                 path: working_path.display().to_string(),
@@ -136,36 +418,156 @@ impl Converter {
         Ok(files)
     }
 
+    /// Compile the user-provided `--ignore` patterns (case-insensitive)
+    fn build_ignore_set(&self) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.config.ignore {
+            let glob = GlobBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .with_context(|| format!("Invalid ignore pattern: {}", pattern))?;
+            builder.add(glob);
+        }
+
+        builder.build().context("Failed to build ignore patterns")
+    }
+
     /// Create appropriate pattern matcher based on configuration and file analysis
     fn create_pattern_matcher(&self, files: &[PathBuf]) -> Result<EdaPatterns> {
         info!("Detecting EDA tool type for {} files...", files.len());
 
-        let patterns = match self.config.get_eda_type() {
-            EdaType::Auto => {
-                info!("Attempting to auto-detect an EDA format");
-                PatternMatcher::auto_detect_eda(files)?
-            }
-            EdaType::KiCad => {
-                info!("Using KiCad naming patterns");
+        let eda_type = self.config.get_eda_type();
+        let is_auto = eda_type == EdaType::Auto;
+        let mut patterns = match eda_type {
+            EdaType::Auto if self.gerber_job.is_some() => {
+                info!("Gerber job file found, using KiCad patterns for files it does not list");
                 PatternMatcher::create_kicad_patterns()
             }
-            EdaType::Protel => {
-                info!("Using Protel naming patterns");
-                PatternMatcher::create_protel_patterns()
-            }
-            EdaType::Jlc => {
-                info!("Using JLC naming patterns");
-                PatternMatcher::create_jlc_patterns()
+            EdaType::Auto => {
+                info!("Attempting to auto-detect an EDA format");
+                let groups = Self::viable_eda_groups(files, self.config.min_layer_types);
+                if groups.len() > 1 {
+                    let names: Vec<&str> = groups.iter().map(|(p, _)| p.name.as_str()).collect();
+                    warn!(
+                        "Input mixes files from several EDA formats ({}); only one will be converted. Use --multi-eda to convert each format",
+                        names.join(", ")
+                    );
+                }
+                // Extra patterns take part in detection, not just matching
+                let mut candidates = PatternMatcher::detection_candidates();
+                for candidate in &mut candidates {
+                    candidate.add_patterns(&self.config.extra_patterns)?;
+                }
+                let mut names: Vec<String> = files
+                    .iter()
+                    .filter_map(|path| path.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .collect();
+                if !candidates
+                    .iter()
+                    .any(|candidate| candidate.count_matches(&names) > 0)
+                {
+                    names.sort();
+                    return Err(TransJlcError::NoRecognizableFiles {
+                        files: summarize_file_names(&names),
+                    }
+                    .into());
+                }
+                PatternMatcher::auto_detect_eda_among(
+                    files,
+                    self.config.min_layer_types,
+                    candidates,
+                )?
             }
-            EdaType::Custom(name) => {
-                warn!("Using custom pattern matcher for: {}", name);
-                PatternMatcher::create_custom_patterns(name)
+            EdaType::Custom(name) => match &self.config.custom_patterns {
+                Some(path) => {
+                    info!("Using custom naming patterns from {}", path.display());
+                    PatternMatcher::load_custom_patterns(name, path)?
+                }
+                None => {
+                    warn!("Using custom pattern matcher for: {}", name);
+                    PatternMatcher::create_custom_patterns(name)?
+                }
+            },
+            builtin => {
+                let patterns = PatternMatcher::builtin(&builtin)?;
+                info!("Using {} naming patterns", patterns.name);
+                patterns
             }
         };
 
+        if !is_auto && !self.config.extra_patterns.is_empty() {
+            info!(
+                "Adding {} extra patterns to {}",
+                self.config.extra_patterns.len(),
+                patterns.name
+            );
+            patterns.add_patterns(&self.config.extra_patterns)?;
+        }
+
         Ok(patterns)
     }
 
+    /// Group files by EDA format, keeping only groups with enough layers to convert
+    fn viable_eda_groups(
+        files: &[PathBuf],
+        min_layer_types: usize,
+    ) -> Vec<(EdaPatterns, Vec<PathBuf>)> {
+        PatternMatcher::group_files_by_eda(files)
+            .into_iter()
+            .filter(|(patterns, group)| {
+                let filenames: Vec<String> = group
+                    .iter()
+                    .filter_map(|p| p.file_name().and_then(|n| n.to_str()))
+                    .map(|n| n.to_string())
+                    .collect();
+                patterns.can_handle_files_with_threshold(&filenames, min_layer_types)
+            })
+            .collect()
+    }
+
+    /// Convert each EDA group into its own subfolder of the output path
+    fn convert_eda_groups(
+        &mut self,
+        groups: Vec<(EdaPatterns, Vec<PathBuf>)>,
+        working_path: &Path,
+    ) -> Result<()> {
+        let summary: Vec<String> = groups
+            .iter()
+            .map(|(patterns, files)| format!("{} ({} files)", patterns.name, files.len()))
+            .collect();
+        warn!(
+            "Input mixes files from several EDA formats: {}",
+            summary.join(", ")
+        );
+
+        for (patterns, files) in groups {
+            let mut config = self.config.clone();
+            config.eda = patterns.name.to_lowercase();
+            config.path = working_path.to_path_buf();
+            config.output_path = self.config.output_path.join(&patterns.name);
+            // Each group would otherwise overwrite the previous group's archive
+            config.zip_path = self.config.suffixed_zip_path(&patterns.name);
+            config.multi_eda = false;
+            config.list_output = None;
+
+            info!(
+                "Converting {} group into {}",
+                patterns.name,
+                config.output_path.display()
+            );
+
+            let mut converter = Converter::new(config).with_input_files(files);
+            // Share the listener and cancel flag; lifecycle events stay with this run
+            converter.event_sender = self.event_sender.clone();
+            converter.cancel_flag = self.cancel_flag.clone();
+            converter.execute().with_eda_context(&patterns.name)?;
+            self.group_stats.push(converter.get_conversion_stats());
+        }
+
+        Ok(())
+    }
+
     /// Process all discovered files using the pattern matcher
     fn process_files(
         &mut self,
@@ -177,17 +579,58 @@ impl Converter {
 
         let progress = self
             .progress_tracker
-            .create_conversion_progress(files.len());
+            .create_detailed_conversion_progress(files.len());
+        // Layers are resolved several times per file, but each file the job and
+        // naming patterns miss has its content read once
+        self.content_layers = files
+            .iter()
+            .filter(|file| {
+                let filename = file.file_name().and_then(|name| name.to_str());
+                filename.is_some_and(|name| self.named_layer(name, patterns).is_none())
+            })
+            .map(|file| {
+                let layer = attribute_layer(file).or_else(|| sniffed_layer(file));
+                (file.clone(), layer)
+            })
+            .collect();
         let needs_g54_aperture_prefix = self.determine_g54_requirement(files, patterns)?;
+        if self.config.renumber_inner {
+            self.inner_layer_numbers = self.contiguous_inner_layers(files, patterns);
+        }
 
-        for file in files {
-            self.process_single_file(file, patterns, working_path, needs_g54_aperture_prefix)
-                .with_path_context("process file", file)?;
+        for (index, file) in files.iter().enumerate() {
+            self.check_cancelled()?;
+            if let Some(progress) = &progress {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                progress.start_file(&name);
+            }
+            self.process_single_file(
+                file,
+                patterns,
+                working_path,
+                needs_g54_aperture_prefix,
+                progress.as_ref().map(|progress| &progress.current),
+            )
+            .with_path_context("process file", file)?;
+
+            if let Some(progress) = &progress {
+                progress.files.inc(1);
+            }
+            self.progress_tracker
+                .report_line(index + 1, files.len(), "Converting files");
+        }
 
-            ProgressTracker::update_progress(&progress, 1, None);
+        if let Some(progress) = progress {
+            progress.finish("File processing completed");
         }
 
-        ProgressTracker::finish_progress(progress, "File processing completed");
+        if !self.unmatched_files.is_empty() {
+            warn!(
+                "{} file(s) did not match any layer pattern: {}",
+                self.unmatched_files.len(),
+                self.unmatched_files.join(", ")
+            );
+        }
 
         info!("Processed {} files", self.processed_files.len());
         Ok(())
@@ -200,6 +643,7 @@ impl Converter {
         patterns: &EdaPatterns,
         _working_path: &Path,
         needs_g54_aperture_prefix: bool,
+        stage_progress: Option<&ProgressBar>,
     ) -> Result<()> {
         let filename = file_path
             .file_name()
@@ -208,39 +652,214 @@ impl Converter {
 
         debug!("Processing file: {}", filename);
 
+        if gbrjob::is_job_file(file_path) {
+            debug!("Skipping Gerber job file {}", filename);
+            return Ok(());
+        }
+
         // Try to match the file to a layer type
-        if let Some(layer_type) = patterns.match_filename(filename) {
+        if let Some(layer_type) = self.match_layer(file_path, filename, patterns) {
             info!("Matched {} to layer type: {:?}", filename, layer_type);
-
-            // Determine output filename and path
-            let output_filename = layer_type.to_jlc_filename();
-            let output_path = self.get_output_file_path(&output_filename);
+            if let LayerType::InnerLayer(num) = layer_type {
+                if num > self.config.max_layers {
+                    let message = format!(
+                        "{} maps to inner layer {}, above --max-layers {}",
+                        filename, num, self.config.max_layers
+                    );
+                    if self.config.strict {
+                        bail!(message);
+                    }
+                    warn!("{}; skipping it", message);
+                    self.unmatched_files.push(filename.to_string());
+                    return Ok(());
+                }
+            }
+            let layer_type = match layer_type {
+                LayerType::InnerLayer(num) if self.inner_layer_numbers.contains_key(&num) => {
+                    let renumbered = self.inner_layer_numbers[&num];
+                    info!("Renumbering inner layer {} to {}", num, renumbered);
+                    LayerType::InnerLayer(renumbered)
+                }
+                other => other,
+            };
+            if !self.config.layer_selected(&layer_type) {
+                info!(
+                    "Skipping {}: {:?} excluded by --only/--skip",
+                    filename, layer_type
+                );
+                return Ok(());
+            }
+            self.emit(ConversionEvent::FileMatched {
+                source: file_path.to_path_buf(),
+                layer: layer_type.clone(),
+            });
 
             // Read and process file content
-            let content =
-                fs::read_to_string(file_path).with_path_context("read file content", file_path)?;
+            let content = read_input_content(file_path)?;
+            self.input_bytes += fs::metadata(file_path)
+                .with_path_context("read metadata of", file_path)?
+                .len();
+
+            // Combined drill files may be split into PTH and NPTH outputs
+            if self.should_process_drill(&layer_type) {
+                if let Some(split) = self.drill_processor.split_plating(&content) {
+                    let plated = self.drill_processor.process_drill_content(split.plated)?;
+                    let non_plated = self
+                        .drill_processor
+                        .process_drill_content(split.non_plated)?;
+                    self.write_layer(filename, LayerType::PthThrough, &plated)?;
+                    self.write_layer(filename, LayerType::NpthThrough, &non_plated)?;
+                    return Ok(());
+                }
+            }
 
             // Apply processing if it's a Gerber file (not drill files)
             let processed_content = if self.should_process_gerber(&layer_type) {
-                self.gerber_processor
-                    .process_gerber_content(content, needs_g54_aperture_prefix)?
+                let (processed, hash_aperture) =
+                    self.gerber_processor.process_gerber_content_with_progress(
+                        content,
+                        needs_g54_aperture_prefix,
+                        stage_progress,
+                    )?;
+                if let Some(hash_aperture) = hash_aperture {
+                    self.hash_apertures
+                        .push((layer_type.clone(), hash_aperture));
+                }
+                processed
+            } else if self.should_process_drill(&layer_type) {
+                self.drill_processor.process_drill_content(content)?
             } else {
                 content
             };
 
-            // Write processed content
-            self.write_output_file(&output_path, &processed_content)
-                .with_path_context("write output file", &output_path)?;
-
-            // Track the processed file
-            self.processed_files.insert(layer_type, output_path);
+            self.write_layer(filename, layer_type, &processed_content)?;
         } else {
             debug!("No pattern match for file: {}", filename);
+            self.unmatched_files.push(filename.to_string());
         }
 
         Ok(())
     }
 
+    /// Write processed content under the layer's JLC filename and track it
+    ///
+    /// With `--stream-zip` the content goes straight into the archive and the
+    /// layer is tracked by its entry name.
+    fn write_layer(&mut self, source: &str, layer_type: LayerType, content: &str) -> Result<()> {
+        let output_filename = layer_type.to_jlc_filename();
+        let output_path = if let Some(stream) = &mut self.zip_stream {
+            // A ZIP entry cannot be replaced once written
+            if stream.contains(&output_filename) {
+                bail!(
+                    "{} maps to {}, which was already written to the ZIP; \
+                     rerun without --stream-zip to keep the last match",
+                    source,
+                    output_filename
+                );
+            }
+            let content = with_line_ending(content, self.config.output_line_ending());
+            stream.add(&output_filename, content.as_bytes())?;
+            PathBuf::from(&output_filename)
+        } else {
+            let output_path = self.get_output_file_path(&output_filename);
+            self.write_output_file(&output_path, content)
+                .with_path_context("write output file", &output_path)?;
+            output_path
+        };
+        self.measure_layer(&layer_type, content);
+
+        self.emit(ConversionEvent::LayerWritten {
+            layer: layer_type.clone(),
+            path: output_path.clone(),
+        });
+        self.layer_mappings.push(LayerMapping {
+            layer: layer_type.clone(),
+            source: source.to_string(),
+            output: output_filename,
+        });
+        self.processed_files.insert(layer_type, output_path);
+
+        Ok(())
+    }
+
+    /// Resolve the layer type of an input file
+    fn match_layer(
+        &self,
+        file_path: &Path,
+        filename: &str,
+        patterns: &EdaPatterns,
+    ) -> Option<LayerType> {
+        // User overrides win over any naming convention
+        if let Some((_, layer)) = self
+            .config
+            .drill_as
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(filename))
+        {
+            debug!("Drill override: {} -> {:?}", filename, layer);
+            return Some(layer.clone());
+        }
+
+        if is_netlist_file(filename) {
+            if !self.config.include_netlist {
+                debug!("Skipping netlist {} (use --include-netlist)", filename);
+            }
+            return self.config.include_netlist.then_some(LayerType::IpcNetlist);
+        }
+
+        let layer = self.job_or_pattern_layer(file_path, filename, patterns)?;
+        if layer.is_extra() && !self.config.keep_extra {
+            debug!("Skipping extra layer {} (use --keep-extra)", filename);
+            return None;
+        }
+
+        Some(layer)
+    }
+
+    /// Map every matched inner layer number to its position in the stackup
+    ///
+    /// Numbers above `--max-layers` are left out, since those files are skipped.
+    fn contiguous_inner_layers(
+        &self,
+        files: &[PathBuf],
+        patterns: &EdaPatterns,
+    ) -> HashMap<u32, u32> {
+        let mut numbers: Vec<u32> = files
+            .iter()
+            .filter_map(|file| {
+                let filename = file.file_name()?.to_str()?;
+                match self.match_layer(file, filename, patterns)? {
+                    LayerType::InnerLayer(num) if num <= self.config.max_layers => Some(num),
+                    _ => None,
+                }
+            })
+            .collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        numbers.into_iter().zip(1..).collect()
+    }
+
+    /// Layer type from the Gerber job file, falling back to the naming patterns,
+    /// the file's own X2 `FileFunction` attribute and finally its content
+    fn job_or_pattern_layer(
+        &self,
+        file_path: &Path,
+        filename: &str,
+        patterns: &EdaPatterns,
+    ) -> Option<LayerType> {
+        self.named_layer(filename, patterns)
+            .or_else(|| self.content_layers.get(file_path).cloned().flatten())
+    }
+
+    /// Layer type from the Gerber job file or the naming patterns alone
+    fn named_layer(&self, filename: &str, patterns: &EdaPatterns) -> Option<LayerType> {
+        self.gerber_job
+            .as_ref()
+            .and_then(|job| job.layer_for(filename))
+            .or_else(|| patterns.match_filename(filename))
+    }
+
     /// Determine whether any target file is missing the required G54 aperture prefix
     fn determine_g54_requirement(&self, files: &[PathBuf], patterns: &EdaPatterns) -> Result<bool> {
         for file in files {
@@ -248,7 +867,7 @@ impl Converter {
                 continue;
             };
 
-            let Some(layer_type) = patterns.match_filename(filename) else {
+            let Some(layer_type) = self.job_or_pattern_layer(file, filename, patterns) else {
                 continue;
             };
 
@@ -256,7 +875,7 @@ impl Converter {
                 continue;
             }
 
-            let content = fs::read_to_string(file).with_path_context("read file content", file)?;
+            let content = read_input_content(file)?;
 
             if self
                 .gerber_processor
@@ -269,12 +888,85 @@ impl Converter {
         Ok(false)
     }
 
+    /// Measure a converted layer as it is written
+    ///
+    /// The outline gives the board size, and with `--bounds-report` every
+    /// Gerber layer records its extents.
+    fn measure_layer(&mut self, layer_type: &LayerType, content: &str) {
+        let is_outline = *layer_type == LayerType::BoardOutline;
+        let report_bounds = self.config.bounds_report && is_gerber_layer(layer_type);
+        if !is_outline && !report_bounds {
+            return;
+        }
+
+        // Only the outline warns about lines the parser skipped
+        let bounds = if is_outline {
+            parse_outline_bounds(content, false)
+        } else {
+            parse_layer_bounds(content).map(|parsed| parsed.bounds)
+        };
+        let bounds = match bounds {
+            Ok(bounds) => bounds,
+            Err(e) if is_outline => {
+                warn!("Could not measure the board outline: {:#}", e);
+                return;
+            }
+            // Layers without any coordinates have no extents to report
+            Err(e) => {
+                debug!("Could not measure {:?}: {:#}", layer_type, e);
+                return;
+            }
+        };
+
+        if is_outline {
+            let size = BoardDimensions {
+                width: bounds.width(),
+                height: bounds.height(),
+            };
+            info!("Board size: {}", size);
+            self.board_size = Some(size);
+        }
+        if report_bounds {
+            self.layer_bounds.retain(|layer| layer.layer != *layer_type);
+            self.layer_bounds.push(LayerBounds {
+                layer: layer_type.clone(),
+                output: layer_type.to_jlc_filename(),
+                min_x: bounds.min_x,
+                max_x: bounds.max_x,
+                min_y: bounds.min_y,
+                max_y: bounds.max_y,
+            });
+        }
+    }
+
+    /// Flag boards too small to manufacture, usually an outline read in the wrong unit
+    fn check_board_size(&self) -> Result<()> {
+        let Some(size) = self.board_size else {
+            return Ok(());
+        };
+        if !size.is_smaller_than(self.config.min_board_size) {
+            return Ok(());
+        }
+
+        let message = format!(
+            "Board size {} is below the {} mm minimum; check the outline units",
+            size, self.config.min_board_size
+        );
+        if self.config.strict {
+            bail!(message);
+        }
+        warn!("{}", message);
+        Ok(())
+    }
+
     /// Determine if a layer type should undergo Gerber processing
     fn should_process_gerber(&self, layer_type: &LayerType) -> bool {
-        !matches!(
-            layer_type,
-            LayerType::NpthThrough | LayerType::PthThrough | LayerType::PthThroughVia
-        )
+        is_gerber_layer(layer_type)
+    }
+
+    /// Determine if a layer type is an Excellon drill file
+    fn should_process_drill(&self, layer_type: &LayerType) -> bool {
+        is_drill_layer(layer_type)
     }
 
     /// Get the full output file path
@@ -282,14 +974,43 @@ impl Converter {
         self.get_working_output_dir().join(filename)
     }
 
-    /// Get the working output directory (temporary or final output directory)
+    /// Get the directory processed files are written to
+    ///
+    /// Files go straight to the output directory, or to a staging directory
+    /// when they only exist to be packed into the ZIP archive.
     fn get_working_output_dir(&self) -> PathBuf {
-        // If we have a temporary extraction directory, use it for intermediate processing
-        if let Some(temp_path) = self.archive_extractor.temp_path() {
-            temp_path.to_path_buf()
-        } else {
-            self.config.output_path.clone()
+        match &self.staging_dir {
+            Some(staging_dir) => staging_dir.path().to_path_buf(),
+            None => self.config.output_path.clone(),
+        }
+    }
+
+    /// Create the staging directory for ZIP output, kept apart from the extracted input
+    ///
+    /// With `--keep-files` the files are written to the output directory and
+    /// zipped from there, so no staging directory is needed. With `--stream-zip`
+    /// the archive itself is opened instead.
+    fn prepare_output_dir(&mut self) -> Result<()> {
+        if self.config.stream_zip {
+            if let Some(zip_path) = self.config.zip_archive_path() {
+                let stream = ZipStream::create(&zip_path, &self.config.get_zip_settings())?;
+                debug!("Streaming converted layers into {}", zip_path.display());
+                self.zip_stream = Some(stream);
+                return Ok(());
+            }
+        }
+
+        if self.config.zip_archive_path().is_some()
+            && !self.config.keep_files
+            && self.staging_dir.is_none()
+        {
+            let staging_dir =
+                TempDir::new().context("Failed to create staging directory for ZIP output")?;
+            debug!("Staging ZIP contents in {}", staging_dir.path().display());
+            self.staging_dir = Some(staging_dir);
         }
+
+        Ok(())
     }
 
     /// Write content to output file, creating directories as needed
@@ -298,7 +1019,8 @@ impl Converter {
             fs::create_dir_all(parent).with_path_context("create output directory", parent)?;
         }
 
-        fs::write(output_path, content).with_path_context("write file", output_path)?;
+        let content = with_line_ending(content, self.config.output_line_ending());
+        fs::write(output_path, content.as_bytes()).with_path_context("write file", output_path)?;
 
         debug!("Written output file: {}", output_path.display());
         Ok(())
@@ -308,80 +1030,179 @@ impl Converter {
     fn add_required_assets(&mut self) -> Result<()> {
         info!("Adding required assets");
 
-        const ASSET_NAME: &str = "PCB下单必读.txt";
-
-        let content =
-            Asset::get(ASSET_NAME).context("Required asset not found in embedded files")?;
-
-        let output_path = self.get_working_output_dir().join(ASSET_NAME);
+        let asset_name = self.required_asset;
+
+        // The Gerbers are the important output, so a missing asset only
+        // fails the conversion in strict mode
+        let Some(content) = Asset::get(asset_name) else {
+            let message = format!(
+                "Required asset '{}' not found in embedded files",
+                asset_name
+            );
+            if self.config.strict {
+                bail!(message);
+            }
+            warn!("{}; skipping it", message);
+            return Ok(());
+        };
 
-        fs::write(&output_path, content.data.as_ref())
-            .with_path_context("write required asset", &output_path)?;
+        let output_path = if let Some(stream) = &mut self.zip_stream {
+            stream.add(asset_name, content.data.as_ref())?;
+            PathBuf::from(asset_name)
+        } else {
+            let output_path = self.get_working_output_dir().join(asset_name);
+            fs::write(&output_path, content.data.as_ref())
+                .with_path_context("write required asset", &output_path)?;
+            output_path
+        };
 
         // Track the asset as an "other" file
         self.processed_files.insert(LayerType::Other, output_path);
 
-        info!("Added required asset: {}", ASSET_NAME);
+        info!("Added required asset: {}", asset_name);
         Ok(())
     }
 
     /// Create the final output (files or ZIP archive)
-    fn create_output(&self) -> Result<()> {
+    fn create_output(&mut self) -> Result<()> {
         info!("Creating final output");
 
-        let file_paths: Vec<PathBuf> = self.processed_files.values().cloned().collect();
+        // Streamed layers are already in the archive, which only needs closing
+        if let Some(stream) = self.zip_stream.take() {
+            let zip_path = stream.path().to_path_buf();
+            let entries = stream.finish()?;
+            ArchiveCreator::verify_zip_entries(&zip_path, &entries)?;
 
-        if self.config.zip {
-            // Create ZIP archive
-            let zip_path = self
-                .config
-                .output_path
-                .join(format!("{}.zip", self.config.zip_name));
+            info!("Created ZIP archive: {}", zip_path.display());
+            self.output_bytes += fs::metadata(&zip_path)
+                .with_path_context("read metadata of", &zip_path)?
+                .len();
+            self.output_files = vec![absolute_path(&zip_path)];
+            return Ok(());
+        }
+
+        let mut file_paths = self.sorted_output_files();
+        if self.config.checksums {
+            let manifest = self.write_checksums(&file_paths)?;
+            file_paths.push(manifest);
+        }
 
-            ArchiveCreator::create_zip(&file_paths, &zip_path, !self.config.no_progress)?;
+        if let Some(zip_path) = self.config.zip_archive_path() {
+            // Create ZIP archive
+            ArchiveCreator::create_zip_with_settings(
+                &file_paths,
+                &zip_path,
+                self.config.show_progress(),
+                &self.config.get_zip_settings(),
+            )?;
+            ArchiveCreator::verify_zip(&zip_path, &file_paths)?;
 
             info!("Created ZIP archive: {}", zip_path.display());
+            self.output_files = vec![absolute_path(&zip_path)];
+            if self.config.keep_files {
+                info!("Kept {} files in output directory", file_paths.len());
+                self.output_files
+                    .extend(file_paths.iter().map(|path| absolute_path(path)));
+            }
         } else {
-            // Copy files to final output directory
-            self.copy_files_to_output(&file_paths)?;
-            info!("Copied {} files to output directory", file_paths.len());
+            // Files were already written to the output directory
+            info!("Wrote {} files to output directory", file_paths.len());
+            self.output_files = file_paths.iter().map(|path| absolute_path(path)).collect();
+        }
+
+        for path in &self.output_files {
+            self.output_bytes += fs::metadata(path)
+                .with_path_context("read metadata of", path)?
+                .len();
         }
 
         Ok(())
     }
 
-    /// Copy processed files to the final output directory
-    fn copy_files_to_output(&self, file_paths: &[PathBuf]) -> Result<()> {
-        let progress = self
-            .progress_tracker
-            .create_file_progress(file_paths.len(), "Copying files to output");
-
-        // Ensure output directory exists
-        fs::create_dir_all(&self.config.output_path)
-            .with_path_context("create output directory", &self.config.output_path)?;
-
-        for file_path in file_paths {
-            if let Some(filename) = file_path.file_name() {
-                let dest_path = self.config.output_path.join(filename);
+    /// Write an MD5 manifest (`<hash>  <filename>`) of the given files next to them
+    fn write_checksums(&self, file_paths: &[PathBuf]) -> Result<PathBuf> {
+        let mut manifest = String::new();
+        for path in file_paths {
+            let content = fs::read(path).with_path_context("read file for checksum", path)?;
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            manifest.push_str(&format!("{:x}  {}\n", Md5::digest(&content), name));
+        }
 
-                fs::copy(file_path, &dest_path)
-                    .with_path_context("copy file to output", &dest_path)?;
+        let manifest_path = self.get_working_output_dir().join(CHECKSUMS_FILE_NAME);
+        self.write_output_file(&manifest_path, &manifest)?;
 
-                ProgressTracker::update_progress(&progress, 1, None);
-            }
-        }
+        info!("Wrote checksums for {} files", file_paths.len());
+        Ok(manifest_path)
+    }
 
-        ProgressTracker::finish_progress(progress, "File copying completed");
-        Ok(())
+    /// Processed files ordered by output filename, independent of map iteration order
+    fn sorted_output_files(&self) -> Vec<PathBuf> {
+        let mut file_paths: Vec<PathBuf> = self.processed_files.values().cloned().collect();
+        file_paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        file_paths
     }
 
     /// Get statistics about the conversion process
     pub fn get_conversion_stats(&self) -> ConversionStats {
-        ConversionStats {
+        let mut stats = ConversionStats {
             total_files_processed: self.processed_files.len(),
             layer_types_found: self.processed_files.keys().cloned().collect(),
-            output_format: if self.config.zip { "ZIP" } else { "Files" }.to_string(),
+            output_format: if self.config.zip_archive_path().is_none() {
+                "Files"
+            } else if self.config.keep_files {
+                "ZIP + Files"
+            } else {
+                "ZIP"
+            }
+            .to_string(),
+            hash_apertures: self.hash_apertures.clone(),
+            unmatched_files: self.unmatched_files.clone(),
+            output_files: self.output_files.clone(),
+            detected_eda: self.detected_eda.iter().cloned().collect(),
+            layer_mappings: self.layer_mappings.clone(),
+            output_location: self
+                .config
+                .zip_archive_path()
+                .unwrap_or_else(|| self.config.output_path.clone()),
+            input_bytes: self.input_bytes,
+            output_bytes: self.output_bytes,
+            board_size: self.board_size,
+            layer_bounds: self.layer_bounds.clone(),
+        };
+
+        // Fold in the results of per-EDA group conversions
+        for group in &self.group_stats {
+            stats.total_files_processed += group.total_files_processed;
+            stats
+                .layer_types_found
+                .extend(group.layer_types_found.iter().cloned());
+            stats
+                .hash_apertures
+                .extend(group.hash_apertures.iter().cloned());
+            stats
+                .unmatched_files
+                .extend(group.unmatched_files.iter().cloned());
+            stats
+                .output_files
+                .extend(group.output_files.iter().cloned());
+            stats
+                .detected_eda
+                .extend(group.detected_eda.iter().cloned());
+            stats
+                .layer_mappings
+                .extend(group.layer_mappings.iter().cloned());
+            stats.input_bytes += group.input_bytes;
+            stats.output_bytes += group.output_bytes;
+            stats.board_size = stats.board_size.or(group.board_size);
+            stats
+                .layer_bounds
+                .extend(group.layer_bounds.iter().cloned());
         }
+
+        stats
     }
 
     /// Generate colorful silkscreen outputs if requested
@@ -401,11 +1222,20 @@ impl Converter {
         let options = ColorfulOptions {
             top_image: self.config.top_color_image.clone(),
             bottom_image: self.config.bottom_color_image.clone(),
-            top_solder_mask: self.processed_files.get(&LayerType::TopSoldermask).cloned(),
-            bottom_solder_mask: self
-                .processed_files
-                .get(&LayerType::BottomSoldermask)
-                .cloned(),
+            top_solder_mask: self
+                .config
+                .colorful_top_mask
+                .clone()
+                .or_else(|| self.processed_files.get(&LayerType::TopSoldermask).cloned()),
+            bottom_solder_mask: self.config.colorful_bottom_mask.clone().or_else(|| {
+                self.processed_files
+                    .get(&LayerType::BottomSoldermask)
+                    .cloned()
+            }),
+            max_output_size: self.config.colorful_max_size,
+            strict_outline: self.config.strict_outline,
+            line_ending: self.config.output_line_ending(),
+            ..Default::default()
         };
 
         let generator = ColorfulSilkscreenGenerator::new(options);
@@ -422,17 +1252,271 @@ impl Converter {
     }
 }
 
+/// Resolve a produced file to an absolute path, falling back to the path as given
+fn absolute_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Read the files named in an `--input-list` file, one path per line
+///
+/// Blank lines and `#` comments are skipped. Relative paths are resolved
+/// against the directory of the list file, and every path must exist.
+fn read_input_list(list_path: &Path) -> Result<Vec<PathBuf>> {
+    let listing = fs::read_to_string(list_path).with_path_context("read input list", list_path)?;
+    let base = list_path.parent().unwrap_or(Path::new(""));
+
+    let mut files = Vec::new();
+    for line in listing.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let path = base.join(line);
+        if !path.is_file() {
+            return Err(TransJlcError::FileNotFound {
+                path: format!("{} (listed in {})", path.display(), list_path.display()),
+            }
+            .into());
+        }
+        files.push(path);
+    }
+
+    info!(
+        "Read {} input files from {}",
+        files.len(),
+        list_path.display()
+    );
+    Ok(files)
+}
+
+/// Rewrite every line ending in `content` to `ending`, if one was requested
+fn with_line_ending<'a>(content: &'a str, ending: Option<&str>) -> Cow<'a, str> {
+    let Some(ending) = ending else {
+        return Cow::Borrowed(content);
+    };
+    if ending == "\n" && !content.contains('\r') {
+        return Cow::Borrowed(content);
+    }
+
+    let unix = content.replace("\r\n", "\n").replace('\r', "\n");
+    if ending == "\n" {
+        Cow::Owned(unix)
+    } else {
+        Cow::Owned(unix.replace('\n', ending))
+    }
+}
+
+/// File names for an error message, shortened after the first few
+fn summarize_file_names(names: &[String]) -> String {
+    const SHOWN: usize = 10;
+
+    let mut summary = names
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > SHOWN {
+        summary.push_str(&format!(" and {} more", names.len() - SHOWN));
+    }
+    summary
+}
+
+/// Whether an error is, or was caused by, a cancellation
+fn is_cancelled(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<TransJlcError>(),
+        Some(TransJlcError::Cancelled)
+    )
+}
+
+/// Read up to `limit` bytes of an input file as text, decompressing gzip content
+fn read_input_prefix(path: &Path, limit: u64) -> Result<String> {
+    let file = fs::File::open(path).with_path_context("open file", path)?;
+    let mut reader = BufReader::new(file);
+    let is_gzip = reader
+        .fill_buf()
+        .with_path_context("read file content", path)?
+        .starts_with(&GZIP_MAGIC);
+
+    let mut bytes = Vec::new();
+    let read = if is_gzip {
+        GzDecoder::new(reader).take(limit).read_to_end(&mut bytes)
+    } else {
+        reader.take(limit).read_to_end(&mut bytes)
+    };
+    read.with_path_context("read file content", path)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Read an input file as text, transparently decompressing gzip content
+fn read_input_content(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_path_context("read file content", path)?;
+    decode_input_content(bytes).with_path_context("decode file content", path)
+}
+
+/// Layer type declared by the X2 `FileFunction` attribute inside a file
+///
+/// File attributes belong in the header, so only the start of the file is
+/// read. Files that cannot be read as text simply have no attribute.
+fn attribute_layer(path: &Path) -> Option<LayerType> {
+    const ATTRIBUTE_BYTES: u64 = 64 * 1024;
+
+    let content = read_input_prefix(path, ATTRIBUTE_BYTES).ok()?;
+    let layer = file_function_layer(&content)?;
+    debug!(
+        "Matched {} by its FileFunction attribute: {:?}",
+        path.display(),
+        layer
+    );
+    Some(layer)
+}
+
+/// Layer type guessed from the content of a file without an extension
+///
+/// Some CAM tools write drill files named just `NCDRILL`, which no naming
+/// pattern matches. Excellon headers are recognizable from their first lines
+/// (`M48`, or a unit line followed by a tool definition) and are treated as
+/// plated drills. Only the start of the file is read. Gerber files say
+/// nothing about their layer beyond the `FileFunction` attribute, so they stay
+/// unmatched.
+fn sniffed_layer(path: &Path) -> Option<LayerType> {
+    const SNIFF_BYTES: u64 = 4096;
+    const SNIFF_LINES: usize = 20;
+
+    if path.extension().is_some() {
+        return None;
+    }
+
+    let content = read_input_prefix(path, SNIFF_BYTES).ok()?;
+    let lines: Vec<&str> = content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(SNIFF_LINES)
+        .collect();
+
+    let unit_then_tool = lines
+        .iter()
+        .position(|line| line.starts_with("METRIC") || line.starts_with("INCH"))
+        .is_some_and(|unit| {
+            lines[unit + 1..]
+                .iter()
+                .any(|line| is_tool_definition(line))
+        });
+    if lines.contains(&"M48") || unit_then_tool {
+        debug!(
+            "Matched {} as an Excellon drill file by its content",
+            path.display()
+        );
+        return Some(LayerType::PthThrough);
+    }
+    if lines
+        .iter()
+        .any(|line| line.starts_with("G04") || line.starts_with("%FS"))
+    {
+        debug!(
+            "{} looks like a Gerber file but does not name its layer",
+            path.display()
+        );
+    }
+    None
+}
+
+/// Whether an Excellon header line defines a tool, such as `T1C0.300`
+fn is_tool_definition(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix('T') else {
+        return false;
+    };
+    let size = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    size.len() < rest.len() && size.starts_with('C')
+}
+
+/// Decode input bytes as text, transparently decompressing gzip content
+///
+/// Invalid UTF-8 (such as Latin-1 bytes in comments) is replaced instead of
+/// rejected; Gerber commands are plain ASCII and pass through unchanged.
+pub(crate) fn decode_input_content(bytes: Vec<u8>) -> Result<String> {
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        debug!("Decompressing gzip input");
+        let mut content = Vec::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut content)
+            .context("Failed to decompress gzip content")?;
+        content
+    } else {
+        bytes
+    };
+
+    Ok(String::from_utf8(bytes).unwrap_or_else(|err| {
+        warn!("Content is not valid UTF-8; replacing invalid bytes");
+        String::from_utf8_lossy(err.as_bytes()).into_owned()
+    }))
+}
+
+/// Whether a layer is a Gerber file that gets the JLC header and hash aperture
+pub(crate) fn is_gerber_layer(layer_type: &LayerType) -> bool {
+    !matches!(
+        layer_type,
+        LayerType::NpthThrough
+            | LayerType::PthThrough
+            | LayerType::PthThroughVia
+            | LayerType::IpcNetlist
+    ) && !layer_type.is_extra()
+}
+
+/// Whether a layer is an Excellon drill file
+pub(crate) fn is_drill_layer(layer_type: &LayerType) -> bool {
+    matches!(
+        layer_type,
+        LayerType::NpthThrough | LayerType::PthThrough | LayerType::PthThroughVia
+    )
+}
+
+/// An input file and the JLC file it was converted to
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerMapping {
+    pub layer: LayerType,
+    /// Input file name
+    pub source: String,
+    /// Output file name
+    pub output: String,
+}
+
 /// Statistics about the conversion process
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct ConversionStats {
     pub total_files_processed: usize,
     pub layer_types_found: Vec<LayerType>,
     pub output_format: String,
+    /// Hash apertures inserted per layer, for `--explain-hash`
+    pub hash_apertures: Vec<(LayerType, HashAperture)>,
+    /// Input file names that matched no layer pattern and were skipped
+    pub unmatched_files: Vec<String>,
+    /// Absolute paths of the produced files (the archive when zipping)
+    pub output_files: Vec<PathBuf>,
+    /// Names of the matchers used, one per converted EDA format
+    pub detected_eda: Vec<String>,
+    /// Source and output file of every converted layer
+    pub layer_mappings: Vec<LayerMapping>,
+    /// Output directory, or the archive path when zipping
+    pub output_location: PathBuf,
+    /// Total size of the matched input files as read from disk
+    pub input_bytes: u64,
+    /// Total size of the produced files (the archive when zipping)
+    pub output_bytes: u64,
+    /// Board width and height measured from the outline layer
+    pub board_size: Option<BoardDimensions>,
+    /// Extents of every converted Gerber layer, for `--bounds-report`
+    pub layer_bounds: Vec<LayerBounds>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::archive::ZipSettings;
+    use clap::Parser;
     use tempfile::tempdir;
 
     #[test]
@@ -447,6 +1531,7 @@ mod tests {
             no_progress: true,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         let converter = Converter::new(config);
@@ -465,6 +1550,7 @@ mod tests {
             no_progress: true,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         let converter = Converter::new(config);
@@ -486,6 +1572,7 @@ mod tests {
             no_progress: true,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         let converter = Converter::new(config);
@@ -513,6 +1600,7 @@ mod tests {
             no_progress: true,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         let mut converter = Converter::new(config);
@@ -533,6 +1621,1649 @@ mod tests {
         assert!(stats.layer_types_found.contains(&LayerType::BottomCopper));
     }
 
+    #[test]
+    fn test_event_sender_reports_failure() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: temp_dir.path().join("missing"),
+            output_path: temp_dir.path().join("output"),
+            no_progress: true,
+            ..Default::default()
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut converter = Converter::new(config).with_event_sender(tx);
+
+        assert!(converter.run().is_err());
+        assert!(matches!(rx.try_recv(), Ok(ConversionEvent::Started)));
+        assert!(matches!(rx.try_recv(), Ok(ConversionEvent::Failed(_))));
+    }
+
+    #[test]
+    fn test_event_sender_reports_written_layers() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut converter = Converter::new(config).with_event_sender(tx);
+        converter.run().expect("Conversion should succeed");
+
+        let events: Vec<ConversionEvent> = rx.try_iter().collect();
+        let written = events
+            .iter()
+            .filter(|event| matches!(event, ConversionEvent::LayerWritten { .. }))
+            .count();
+
+        assert!(matches!(events.first(), Some(ConversionEvent::Started)));
+        assert!(matches!(events.last(), Some(ConversionEvent::Finished(_))));
+        assert_eq!(written, 3);
+    }
+
+    /// Run a conversion of `layer_count` inner layers that is cancelled as
+    /// soon as the first file has been matched
+    fn run_cancelled(
+        input_dir: &Path,
+        config: &Config,
+        layer_count: usize,
+    ) -> (anyhow::Error, Vec<ConversionEvent>) {
+        for layer in 1..=layer_count {
+            fs::write(
+                input_dir.join(format!("project-In{}_Cu.gbr", layer)),
+                "G04 test*\nD10*\nM02*\n",
+            )
+            .expect("Failed to write input file");
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let listener = {
+            let cancel = Arc::clone(&cancel);
+            std::thread::spawn(move || {
+                let mut events = Vec::new();
+                for event in rx {
+                    if matches!(event, ConversionEvent::FileMatched { .. }) {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                    events.push(event);
+                }
+                events
+            })
+        };
+
+        let mut converter = Converter::new(config.clone())
+            .with_event_sender(tx)
+            .with_cancel_flag(cancel);
+        let err = converter.run().expect_err("Conversion should be cancelled");
+        drop(converter);
+        (err, listener.join().expect("Listener panicked"))
+    }
+
+    #[test]
+    fn test_cancel_flag_aborts_between_files() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let layer_count = 300;
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            zip: true,
+            no_progress: true,
+            ..Default::default()
+        };
+
+        let (err, events) = run_cancelled(input_dir.path(), &config, layer_count);
+
+        assert!(is_cancelled(&err), "unexpected error: {:#}", err);
+        assert!(matches!(events.last(), Some(ConversionEvent::Cancelled)));
+        let written = events
+            .iter()
+            .filter(|event| matches!(event, ConversionEvent::LayerWritten { .. }))
+            .count();
+        assert!(written < layer_count, "all {} layers were written", written);
+        assert!(!config.zip_archive_path().unwrap().exists());
+    }
+
+    #[test]
+    fn test_cancelled_run_leaves_no_partial_output() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+
+        let (err, events) = run_cancelled(input_dir.path(), &config, 300);
+
+        assert!(is_cancelled(&err), "unexpected error: {:#}", err);
+        // The matched file is written before cancellation is checked again
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ConversionEvent::LayerWritten { .. })));
+        let leftovers: Vec<_> = fs::read_dir(output_dir.path())
+            .expect("Failed to read output dir")
+            .collect();
+        assert!(leftovers.is_empty(), "partial output left: {:?}", leftovers);
+    }
+
+    #[test]
+    fn test_output_files_are_sorted_consistently() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-F_Mask.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let run_once = || {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                ..Default::default()
+            };
+            let mut converter = Converter::new(config);
+            converter.run().expect("Conversion should succeed");
+
+            converter
+                .sorted_output_files()
+                .iter()
+                .filter_map(|path| path.file_name().map(|name| name.to_owned()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = run_once();
+        let second = run_once();
+
+        assert_eq!(first, second);
+        let mut expected = first.clone();
+        expected.sort();
+        assert_eq!(first, expected);
+    }
+
+    #[test]
+    fn test_ignore_patterns_exclude_files() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+            "Thumbs.db",
+            "assembly.pdf",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            path: input_dir.path().to_path_buf(),
+            no_progress: true,
+            ignore: vec!["thumbs.db".to_string(), "*.pdf".to_string()],
+            ..Default::default()
+        };
+        let converter = Converter::new(config);
+
+        let files = converter.discover_files(input_dir.path()).unwrap();
+        let names: Vec<String> = files
+            .iter()
+            .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+            .collect();
+
+        assert_eq!(files.len(), 3);
+        assert!(!names.iter().any(|name| name == "Thumbs.db"));
+        assert!(!names.iter().any(|name| name.ends_with(".pdf")));
+    }
+
+    #[test]
+    fn test_netlist_bundled_with_include_netlist() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let netlist =
+            "P  JOB project\n327GND    VIA   D0300PA00X+001000Y+001000X0600Y0000R000S0\n999\n";
+        fs::write(input_dir.path().join("project.d356"), netlist).expect("Failed to write netlist");
+
+        for include_netlist in [false, true] {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                include_netlist,
+                ..Default::default()
+            };
+            let mut converter = Converter::new(config);
+            converter.run().expect("Conversion should succeed");
+
+            assert_eq!(
+                converter
+                    .processed_files
+                    .contains_key(&LayerType::IpcNetlist),
+                include_netlist
+            );
+            assert_eq!(
+                output_dir
+                    .path()
+                    .join(LayerType::IpcNetlist.to_jlc_filename())
+                    .exists(),
+                include_netlist
+            );
+        }
+    }
+
+    #[test]
+    fn test_extensionless_excellon_is_sniffed() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in ["project-F_Cu.gbr", "project-B_Cu.gbr"] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let drill = "M48\nMETRIC,TZ\nT1C0.300\n%\nT1\nX10.0Y10.0\nM30\n";
+        fs::write(input_dir.path().join("NCDRILL"), drill).expect("Failed to write drill");
+        fs::write(input_dir.path().join("README"), "Fabrication notes\n")
+            .expect("Failed to write notes");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let written = fs::read_to_string(output_dir.path().join("Drill_PTH_Through.DRL"))
+            .expect("Drill file should be written");
+        assert!(written.contains("T1C0.300"));
+        let stats = converter.get_conversion_stats();
+        assert_eq!(stats.unmatched_files, vec!["README".to_string()]);
+    }
+
+    #[test]
+    fn test_sniffing_requires_an_excellon_header() {
+        let dir = tempdir().expect("Failed to create dir");
+        let sniff = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).expect("Failed to write file");
+            sniffed_layer(&path)
+        };
+
+        assert_eq!(
+            sniff("NCDRILL", "METRIC,TZ\nT1C0.300\n%\nT1\nX10.0Y10.0\nM30\n"),
+            Some(LayerType::PthThrough)
+        );
+        assert_eq!(
+            sniff(
+                "NOTES",
+                "INCHES ARE USED THROUGHOUT\nT1 IS THE FIRST TOOL\n"
+            ),
+            None
+        );
+        assert_eq!(sniff("TOOLS", "T1C0.300\nMETRIC\n"), None);
+
+        // Only the start of the file is read
+        let late_header = format!("{}M48\n", "G04 padding*\n".repeat(1000));
+        assert_eq!(sniff("LATE", &late_header), None);
+    }
+
+    #[test]
+    fn test_renumber_inner_layers_contiguously() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-In2_Cu.gbr",
+            "project-In5_Cu.gbr",
+        ] {
+            fs::write(
+                input_dir.path().join(name),
+                format!("G04 {}*\nM02*\n", name),
+            )
+            .expect("Failed to write input file");
+        }
+
+        for (renumber_inner, expected) in [
+            (false, ["Gerber_InnerLayer2.G2", "Gerber_InnerLayer5.G5"]),
+            (true, ["Gerber_InnerLayer1.G1", "Gerber_InnerLayer2.G2"]),
+        ] {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                renumber_inner,
+                ..Default::default()
+            };
+            Converter::new(config)
+                .run()
+                .expect("Conversion should succeed");
+
+            let mut inner: Vec<String> = fs::read_dir(output_dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with("Gerber_InnerLayer"))
+                .collect();
+            inner.sort();
+            assert_eq!(inner, expected);
+
+            // In5 keeps its place after In2
+            let second = fs::read_to_string(output_dir.path().join(expected[1])).unwrap();
+            assert!(second.contains("G04 project-In5_Cu.gbr*"));
+        }
+    }
+
+    #[test]
+    fn test_zip_name_board_template() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let input_dir = temp_dir.path().join("motor_driver");
+        fs::create_dir(&input_dir).expect("Failed to create input dir");
+        for name in ["project-F_Cu.gbr", "project-B_Cu.gbr"] {
+            fs::write(input_dir.join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.clone(),
+            output_path: temp_dir.path().join("out-{board}"),
+            no_progress: true,
+            zip: true,
+            zip_name: "{board}".to_string(),
+            ..Default::default()
+        };
+        Converter::new(config)
+            .run()
+            .expect("Conversion should succeed");
+
+        assert!(temp_dir
+            .path()
+            .join("out-motor_driver")
+            .join("motor_driver.zip")
+            .exists());
+
+        // A Gerber job file names the board instead of the folder
+        fs::write(input_dir.join("rev_b.gbrjob"), "{}").expect("Failed to write job file");
+        let config = Config {
+            path: input_dir,
+            ..Default::default()
+        };
+        assert_eq!(config.board_name(), "rev_b");
+    }
+
+    #[test]
+    fn test_inner_layer_above_max_layers_is_skipped() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-In2_Cu.gbr",
+            "project-In99_Cu.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config.clone());
+        converter.run().expect("Conversion should only warn");
+
+        assert!(output_dir.path().join("Gerber_InnerLayer2.G2").exists());
+        assert!(!output_dir.path().join("Gerber_InnerLayer99.G99").exists());
+        let stats = converter.get_conversion_stats();
+        assert_eq!(
+            stats.unmatched_files,
+            vec!["project-In99_Cu.gbr".to_string()]
+        );
+
+        let err = Converter::new(Config {
+            output_path: output_dir.path().join("strict"),
+            strict: true,
+            ..config
+        })
+        .run()
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("inner layer 99, above --max-layers 32"));
+    }
+
+    #[test]
+    fn test_only_writes_selected_layers() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+            "project-F_Paste.gbr",
+            "project-F_Silkscreen.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Config::parse_from(["transjlc", "--only", "TopCopper,BottomCopper,BoardOutline"])
+        };
+        Converter::new(config)
+            .run()
+            .expect("Conversion should succeed");
+
+        let mut produced: Vec<String> = fs::read_dir(output_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        produced.sort();
+        assert_eq!(
+            produced,
+            vec![
+                "Gerber_BoardOutlineLayer.GKO",
+                "Gerber_BottomLayer.GBL",
+                "Gerber_TopLayer.GTL",
+                "PCB下单必读.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drill_override_classifies_generic_drill_as_npth() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        fs::write(
+            input_dir.path().join("mounting.drl"),
+            "M48\nT1C3.2\n%\nM30\n",
+        )
+        .expect("Failed to write drill file");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            drill_as: vec![("mounting.drl".to_string(), LayerType::NpthThrough)],
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        assert!(converter
+            .processed_files
+            .contains_key(&LayerType::NpthThrough));
+        assert!(!converter
+            .processed_files
+            .contains_key(&LayerType::PthThrough));
+        assert!(output_dir.path().join("Drill_NPTH_Through.DRL").exists());
+    }
+
+    #[test]
+    fn test_gzipped_gerber_is_decompressed() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in ["project-B_Cu.gbr", "project-Edge_Cuts.gbr"] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"G04 gzipped top*\nD10*\nM02*\n")
+            .expect("Failed to compress");
+        fs::write(
+            input_dir.path().join("project-F_Cu.gbr.gz"),
+            encoder.finish().expect("Failed to finish gzip stream"),
+        )
+        .expect("Failed to write gzipped file");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            zip: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let zip_file = fs::File::open(output_dir.path().join("Gerber.zip")).unwrap();
+        let mut archive = zip::ZipArchive::new(zip_file).unwrap();
+        let mut top = String::new();
+        archive
+            .by_name("Gerber_TopLayer.GTL")
+            .expect("Top layer should be archived")
+            .read_to_string(&mut top)
+            .unwrap();
+
+        assert!(top.contains("G04 gzipped top*"));
+    }
+
+    #[test]
+    fn test_unmatched_files_are_reported() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+            "project-F_Cuu.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        fs::write(input_dir.path().join(".DS_Store"), "junk").expect("Failed to write junk");
+        fs::write(input_dir.path().join("notes.txt"), "ignored").expect("Failed to write notes");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ignore: vec!["*.txt".to_string()],
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let stats = converter.get_conversion_stats();
+        assert_eq!(stats.unmatched_files, vec!["project-F_Cuu.gbr".to_string()]);
+    }
+
+    #[test]
+    fn test_byte_counters_cover_inputs_and_outputs() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let content = "G04 test*\nD10*\nM02*\n";
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), content).expect("Failed to write input file");
+        }
+        fs::write(input_dir.path().join("unrelated.bin"), [0u8; 4096])
+            .expect("Failed to write unrelated file");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let stats = converter.get_conversion_stats();
+        // Only matched inputs count, skipped files are never read
+        assert_eq!(stats.input_bytes, 3 * content.len() as u64);
+        let written: u64 = stats
+            .output_files
+            .iter()
+            .map(|path| fs::metadata(path).unwrap().len())
+            .sum();
+        assert_eq!(stats.output_bytes, written);
+        // Every layer gains a header and the ordering notes are added
+        assert!(stats.output_bytes > stats.input_bytes);
+    }
+
+    #[test]
+    fn test_board_size_is_measured_in_millimeters() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in ["project-F_Cu.gbr", "project-B_Cu.gbr"] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        // A 2 x 1 inch rectangle
+        fs::write(
+            input_dir.path().join("project-Edge_Cuts.gbr"),
+            "%FSLAX46Y46*%\n%MOIN*%\n%ADD10C,0.004*%\nD10*\nX0Y0D02*\nX2000000Y0D01*\n\
+             X2000000Y1000000D01*\nX0Y1000000D01*\nX0Y0D01*\nM02*\n",
+        )
+        .expect("Failed to write outline");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let size = converter
+            .get_conversion_stats()
+            .board_size
+            .expect("Board should be measured");
+        assert!((size.width - 50.8).abs() < 1e-6, "width {}", size.width);
+        assert!((size.height - 25.4).abs() < 1e-6, "height {}", size.height);
+    }
+
+    #[test]
+    fn test_bounds_report_measures_each_layer() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        fs::write(
+            input_dir.path().join("project-F_Cu.gbr"),
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX40000000Y20000000D01*\nM02*\n",
+        )
+        .expect("Failed to write top copper");
+        fs::write(
+            input_dir.path().join("project-B_Cu.gbr"),
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX5000000Y5000000D02*\nX15000000Y8000000D01*\nM02*\n",
+        )
+        .expect("Failed to write bottom copper");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            bounds_report: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let stats = converter.get_conversion_stats();
+        assert_eq!(stats.layer_bounds.len(), 2);
+        let find = |layer: LayerType| {
+            stats
+                .layer_bounds
+                .iter()
+                .find(|bounds| bounds.layer == layer)
+                .expect("Layer should be measured")
+        };
+        let top = find(LayerType::TopCopper);
+        assert!((top.max_x - 40.0).abs() < 1e-6, "top max_x {}", top.max_x);
+        assert!((top.max_y - 20.0).abs() < 1e-6, "top max_y {}", top.max_y);
+        let bottom = find(LayerType::BottomCopper);
+        assert!(
+            (bottom.min_x - 5.0).abs() < 1e-6,
+            "bottom min_x {}",
+            bottom.min_x
+        );
+        assert!(
+            (bottom.max_x - 15.0).abs() < 1e-6,
+            "bottom max_x {}",
+            bottom.max_x
+        );
+        assert!(
+            (bottom.max_y - 8.0).abs() < 1e-6,
+            "bottom max_y {}",
+            bottom.max_y
+        );
+    }
+
+    /// Write a KiCad board whose outline is a 0.2 mm square
+    fn write_tiny_board(dir: &Path) {
+        for name in ["project-F_Cu.gbr", "project-B_Cu.gbr"] {
+            fs::write(dir.join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        fs::write(
+            dir.join("project-Edge_Cuts.gbr"),
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX200000Y0D01*\n\
+             X200000Y200000D01*\nX0Y200000D01*\nX0Y0D01*\nM02*\n",
+        )
+        .expect("Failed to write outline");
+    }
+
+    #[test]
+    fn test_tiny_board_warns() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        write_tiny_board(input_dir.path());
+        let log_path = input_dir.path().join("conversion.log");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let subscriber =
+            tracing_subscriber::registry().with(crate::logging::file_layer(&log_path).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            Converter::new(config)
+                .run()
+                .expect("Conversion should only warn");
+        });
+
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(
+            log.contains("WARN")
+                && log.contains("Board size 0.20 x 0.20 mm is below the 5 mm minimum"),
+            "missing warning in log:\n{}",
+            log
+        );
+    }
+
+    #[test]
+    fn test_tiny_board_fails_in_strict_mode() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        write_tiny_board(input_dir.path());
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            strict: true,
+            ..Default::default()
+        };
+        let err = Converter::new(config.clone()).run().unwrap_err();
+        assert!(format!("{:#}", err).contains("below the 5 mm minimum"));
+
+        // A lower limit accepts the same board
+        let config = Config {
+            min_board_size: 0.1,
+            ..config
+        };
+        Converter::new(config)
+            .run()
+            .expect("Board meets the lower limit");
+    }
+
+    #[test]
+    fn test_missing_asset_only_warns() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        write_tiny_board(input_dir.path());
+        let log_path = input_dir.path().join("conversion.log");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            min_board_size: 0.1,
+            ..Default::default()
+        };
+        let subscriber =
+            tracing_subscriber::registry().with(crate::logging::file_layer(&log_path).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            let mut converter = Converter::new(config.clone());
+            converter.required_asset = "Missing.txt";
+            converter.run().expect("Conversion should only warn");
+            assert!(!converter.processed_files.contains_key(&LayerType::Other));
+        });
+        assert!(output_dir.path().join("Gerber_TopLayer.GTL").exists());
+
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(
+            log.contains("WARN") && log.contains("Required asset 'Missing.txt' not found"),
+            "missing warning in log:\n{}",
+            log
+        );
+
+        let mut converter = Converter::new(Config {
+            strict: true,
+            ..config
+        });
+        converter.required_asset = "Missing.txt";
+        let err = converter.run().unwrap_err();
+        assert!(format!("{:#}", err).contains("Required asset 'Missing.txt' not found"));
+    }
+
+    #[test]
+    fn test_latin1_comment_is_decoded_lossily() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let mut content = b"G04 Caf\xe9 board*\n".to_vec();
+        content.extend_from_slice(b"%ADD10C,0.1*%\nG54D10*\nX0Y0D03*\nM02*\n");
+        fs::write(input_dir.path().join("project-F_Cu.gbr"), content)
+            .expect("Failed to write input file");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            no_hash: true,
+            ..Default::default()
+        };
+        Converter::new(config)
+            .run()
+            .expect("Non-UTF-8 comment should not abort the conversion");
+
+        let top = fs::read_to_string(output_dir.path().join("Gerber_TopLayer.GTL")).unwrap();
+        assert!(top.contains("G04 Caf\u{FFFD} board*"));
+        assert!(top.contains("%ADD10C,0.1*%\nG54D10*\nX0Y0D03*\nM02*"));
+    }
+
+    #[test]
+    fn test_list_output_has_one_line_per_file() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let list_path = output_dir.path().join("outputs.txt");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().join("out"),
+            no_progress: true,
+            list_output: Some(list_path.clone()),
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let listing = fs::read_to_string(&list_path).expect("Output list should exist");
+        let lines: Vec<&str> = listing.lines().collect();
+        // Three layers plus the ordering instructions
+        assert_eq!(lines.len(), 4);
+        for line in lines {
+            let path = Path::new(line);
+            assert!(path.is_absolute());
+            assert!(path.is_file());
+        }
+    }
+
+    #[test]
+    fn test_zip_input_is_written_once_to_output() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let mut sources = Vec::new();
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            let path = input_dir.path().join(name);
+            fs::write(&path, "G04 test*\nD10*\nM02*\n").expect("Failed to write input file");
+            sources.push(path);
+        }
+        let zip_input = input_dir.path().join("board.zip");
+        ArchiveCreator::create_zip(&sources, &zip_input, false).expect("Failed to zip input");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: zip_input,
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let mut produced: Vec<String> = fs::read_dir(output_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        produced.sort();
+        assert_eq!(
+            produced,
+            vec![
+                "Gerber_BoardOutlineLayer.GKO",
+                "Gerber_BottomLayer.GBL",
+                "Gerber_TopLayer.GTL",
+                "PCB下单必读.txt",
+            ]
+        );
+        for path in converter.processed_files.values() {
+            assert!(path.starts_with(output_dir.path()));
+            assert!(fs::metadata(path).unwrap().len() > 0);
+        }
+
+        // The extracted input is left untouched
+        let extracted = converter.archive_extractor.temp_path().unwrap();
+        assert_eq!(fs::read_dir(extracted).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_zip_output_leaves_only_archive() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            zip: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let produced: Vec<String> = fs::read_dir(output_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(produced, vec!["Gerber.zip".to_string()]);
+    }
+
+    #[test]
+    fn test_line_endings_are_uniform_across_outputs() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for (name, content) in [
+            ("project-F_Cu.gbr", "G04 test*\nD10*\nM02*\n"),
+            ("project-B_Cu.gbr", "G04 test*\r\nD10*\r\nM02*\r\n"),
+            ("project-Edge_Cuts.gbr", "G04 test*\rD10*\rM02*\r"),
+            (
+                "project-PTH.drl",
+                "M48\r\nMETRIC\nT1C0.300\r\n%\nT1\nX1.0Y1.0\r\nM30\n",
+            ),
+        ] {
+            fs::write(input_dir.path().join(name), content).expect("Failed to write input file");
+        }
+
+        for (style, ending) in [("lf", "\n"), ("crlf", "\r\n")] {
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().join(style),
+                no_progress: true,
+                no_asset: true,
+                normalize_line_endings: Some(style.to_string()),
+                ..Default::default()
+            };
+            let mut converter = Converter::new(config);
+            converter.run().expect("Conversion should succeed");
+
+            assert_eq!(converter.processed_files.len(), 4);
+            for path in converter.processed_files.values() {
+                let content = fs::read_to_string(path).expect("Failed to read output");
+                let lines = content.matches('\n').count();
+                assert!(lines > 0);
+                assert_eq!(
+                    content.matches(ending).count(),
+                    lines,
+                    "{} has mixed line endings",
+                    path.display()
+                );
+                assert_eq!(content.matches('\r').count(), lines * (ending.len() - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_archive_is_reported_by_name() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let zip_input = input_dir.path().join("board.zip");
+        ArchiveCreator::create_zip_from_contents(Vec::new(), &zip_input, &ZipSettings::new())
+            .expect("Failed to write empty ZIP");
+
+        let config = Config {
+            path: zip_input.clone(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let err = Converter::new(config).run().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::EmptyArchive { .. })
+        ));
+        assert!(format!("{:#}", err)
+            .contains(&format!("Input archive {} was empty", zip_input.display())));
+    }
+
+    #[test]
+    fn test_unrecognized_files_are_listed() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let zip_input = input_dir.path().join("board.zip");
+        let entries = [("notes.pdf", "%PDF-1.4\n"), ("readme.txt", "Order notes\n")]
+            .map(|(name, content)| (name.to_string(), content.to_string()));
+        ArchiveCreator::create_zip_from_contents(entries, &zip_input, &ZipSettings::new())
+            .expect("Failed to write input ZIP");
+
+        let config = Config {
+            path: zip_input,
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let err = Converter::new(config).run().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::NoRecognizableFiles { .. })
+        ));
+        assert!(format!("{:#}", err)
+            .contains("No recognizable Gerber/drill files found, got: notes.pdf, readme.txt"));
+    }
+
+    #[test]
+    fn test_odb_export_is_rejected_with_a_hint() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let job = input_dir.path().join("board");
+        fs::create_dir_all(job.join("matrix")).expect("Failed to create matrix folder");
+        fs::create_dir_all(job.join("steps").join("pcb")).expect("Failed to create steps folder");
+        fs::write(
+            job.join("matrix").join("matrix"),
+            "STEP {\n   NAME=PCB\n}\n",
+        )
+        .expect("Failed to write matrix");
+
+        let config = Config {
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let err = Converter::new(config).run().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::UnsupportedFormat { .. })
+        ));
+        assert!(format!("{:#}", err).contains("export Gerber"));
+    }
+
+    #[test]
+    fn test_input_list_converts_only_listed_files() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+            "other-F_Cu.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let list_path = input_dir.path().join("files.txt");
+        fs::write(
+            &list_path,
+            "# board files\nproject-F_Cu.gbr\n\nproject-Edge_Cuts.gbr\n",
+        )
+        .expect("Failed to write input list");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            input_list: Some(list_path.clone()),
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config.clone());
+        converter.run().expect("Conversion should succeed");
+
+        let mut sources: Vec<&str> = converter
+            .layer_mappings
+            .iter()
+            .map(|mapping| mapping.source.as_str())
+            .collect();
+        sources.sort();
+        assert_eq!(sources, vec!["project-Edge_Cuts.gbr", "project-F_Cu.gbr"]);
+        assert!(!converter
+            .processed_files
+            .contains_key(&LayerType::BottomCopper));
+
+        // Every listed path must exist
+        fs::write(&list_path, "project-F_Cu.gbr\nproject-In1_Cu.gbr\n")
+            .expect("Failed to rewrite input list");
+        let err = Converter::new(config).run().unwrap_err();
+        assert!(format!("{:#}", err).contains("project-In1_Cu.gbr (listed in"));
+    }
+
+    #[test]
+    fn test_stream_zip_writes_no_intermediate_files() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        let mut sources = Vec::new();
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            let path = input_dir.path().join(name);
+            fs::write(&path, "G04 test*\nD10*\nM02*\n").expect("Failed to write input file");
+            sources.push(path);
+        }
+        let zip_input = input_dir.path().join("board.zip");
+        ArchiveCreator::create_zip(&sources, &zip_input, false).expect("Failed to zip input");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: zip_input,
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            zip: true,
+            stream_zip: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        // Nothing was staged, and the extracted input holds only the inputs
+        assert!(converter.staging_dir.is_none());
+        let extracted = converter.archive_extractor.temp_path().unwrap();
+        assert_eq!(fs::read_dir(extracted).unwrap().count(), 3);
+        let produced: Vec<String> = fs::read_dir(output_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(produced, vec!["Gerber.zip".to_string()]);
+
+        let archive = zip::ZipArchive::new(
+            fs::File::open(output_dir.path().join("Gerber.zip")).expect("Failed to open ZIP"),
+        )
+        .expect("Failed to read ZIP");
+        let mut entries: Vec<&str> = archive.file_names().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                "Gerber_BoardOutlineLayer.GKO",
+                "Gerber_BottomLayer.GBL",
+                "Gerber_TopLayer.GTL",
+                "PCB下单必读.txt",
+            ]
+        );
+        let stats = converter.get_conversion_stats();
+        assert_eq!(stats.total_files_processed, 4);
+        assert!(stats.output_bytes > 0);
+    }
+
+    #[test]
+    fn test_keep_files_writes_zip_and_loose_files() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            zip: true,
+            keep_files: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let zip_path = output_dir.path().join("Gerber.zip");
+        assert!(zip_path.exists());
+        for name in [
+            "Gerber_TopLayer.GTL",
+            "Gerber_BottomLayer.GBL",
+            "Gerber_BoardOutlineLayer.GKO",
+        ] {
+            assert!(output_dir.path().join(name).exists(), "{} missing", name);
+        }
+
+        let archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        assert!(archive
+            .file_names()
+            .any(|name| name == "Gerber_TopLayer.GTL"));
+
+        let stats = converter.get_conversion_stats();
+        assert_eq!(stats.output_format, "ZIP + Files");
+        assert!(stats.output_files.contains(&absolute_path(&zip_path)));
+        assert!(stats.output_files.len() > 1);
+    }
+
+    #[test]
+    fn test_no_readme_skips_ordering_instructions() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        fs::write(
+            input_dir.path().join("project-F_Cu.gbr"),
+            "G04 test*\nD10*\nM02*\n",
+        )
+        .expect("Failed to write input file");
+
+        for no_asset in [false, true] {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                no_asset,
+                ..Default::default()
+            };
+            let mut converter = Converter::new(config);
+            converter.run().expect("Conversion should succeed");
+
+            let readme = output_dir.path().join("PCB下单必读.txt");
+            assert_eq!(readme.exists(), !no_asset);
+            assert_eq!(
+                converter.processed_files.contains_key(&LayerType::Other),
+                !no_asset
+            );
+        }
+    }
+
+    #[test]
+    fn test_asset_lang_selects_embedded_readme() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        fs::write(
+            input_dir.path().join("project-F_Cu.gbr"),
+            "G04 test*\nD10*\nM02*\n",
+        )
+        .expect("Failed to write input file");
+
+        for (lang, name, other) in [
+            ("zh", "PCB下单必读.txt", "PCB_Order_ReadMe.txt"),
+            ("en", "PCB_Order_ReadMe.txt", "PCB下单必读.txt"),
+        ] {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                asset_lang: lang.to_string(),
+                ..Default::default()
+            };
+            Converter::new(config)
+                .run()
+                .expect("Conversion should succeed");
+
+            let written = fs::read(output_dir.path().join(name)).expect("asset written");
+            assert_eq!(written, Asset::get(name).unwrap().data.as_ref());
+            assert!(!output_dir.path().join(other).exists());
+        }
+    }
+
+    #[test]
+    fn test_checksums_manifest_lists_every_output() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            checksums: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let manifest = fs::read_to_string(output_dir.path().join(CHECKSUMS_FILE_NAME))
+            .expect("Manifest should be written");
+        let listed: Vec<(&str, &str)> = manifest
+            .lines()
+            .map(|line| line.split_once("  ").expect("<hash>  <filename>"))
+            .collect();
+
+        assert_eq!(listed.len(), converter.processed_files.len());
+        for (hash, name) in listed {
+            assert_eq!(hash.len(), 32);
+            assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+            let content = fs::read(output_dir.path().join(name)).expect("Listed file exists");
+            assert_eq!(hash, format!("{:x}", Md5::digest(&content)));
+        }
+    }
+
+    #[test]
+    fn test_fab_layers_pass_through_with_keep_extra() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+            "project-F_Fab.gbr",
+            "project-B_Courtyard.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let convert = |keep_extra: bool| {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                keep_extra,
+                ..Default::default()
+            };
+            let mut converter = Converter::new(config);
+            converter.run().expect("Conversion should succeed");
+            (output_dir, converter)
+        };
+
+        let (_output_dir, converter) = convert(false);
+        assert!(!converter.processed_files.contains_key(&LayerType::TopFab));
+
+        let (output_dir, converter) = convert(true);
+        assert!(converter.processed_files.contains_key(&LayerType::TopFab));
+        let fab = fs::read_to_string(output_dir.path().join("Fabrication_TopFabLayer.GTF"))
+            .expect("Fab layer should be written");
+        assert_eq!(fab, "G04 test*\nD10*\nM02*\n");
+        assert!(output_dir.path().join("project-B_Courtyard.gbr").is_file());
+    }
+
+    #[test]
+    fn test_split_drill_writes_pth_and_npth() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        fs::write(
+            input_dir.path().join("project.drl"),
+            "M48\n;TYPE=PLATED\nT1C0.800\n;TYPE=NON_PLATED\nT2C3.200\n%\nT1\nX10.0Y10.0\nT2\nX2.0Y2.0\nM30\n",
+        )
+        .expect("Failed to write drill file");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            split_drill: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let pth = fs::read_to_string(output_dir.path().join("Drill_PTH_Through.DRL"))
+            .expect("PTH drill should be written");
+        let npth = fs::read_to_string(output_dir.path().join("Drill_NPTH_Through.DRL"))
+            .expect("NPTH drill should be written");
+        assert!(pth.contains("T1C0.800") && pth.contains("X10.0Y10.0"));
+        assert!(!pth.contains("T2"));
+        assert!(npth.contains("T2C3.200") && npth.contains("X2.0Y2.0"));
+        assert!(!npth.contains("T1"));
+    }
+
+    #[test]
+    fn test_custom_pattern_file_converts_matching_files() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in ["board.top", "board.bot", "board.outline", "board.notes"] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let pattern_file = input_dir.path().join("patterns.toml");
+        fs::write(
+            &pattern_file,
+            "TopCopper = '(?i)\\.top$'\nBottomCopper = '(?i)\\.bot$'\nBoardOutline = ['(?i)\\.outline$']\n",
+        )
+        .expect("Failed to write pattern file");
+
+        let config = Config {
+            eda: "custom".to_string(),
+            custom_patterns: Some(pattern_file),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        for name in [
+            "Gerber_TopLayer.GTL",
+            "Gerber_BottomLayer.GBL",
+            "Gerber_BoardOutlineLayer.GKO",
+        ] {
+            assert!(output_dir.path().join(name).is_file(), "{} missing", name);
+        }
+        let stats = converter.get_conversion_stats();
+        assert!(stats.unmatched_files.contains(&"board.notes".to_string()));
+    }
+
+    #[test]
+    fn test_gerber_job_file_drives_layer_mapping() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        // None of these names match a naming convention, and "front.gbr" would
+        // not be guessed as the bottom layer
+        for name in [
+            "front.gbr",
+            "middle.gbr",
+            "back.gbr",
+            "edge.gbr",
+            "board-PTH.drl",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        fs::write(
+            input_dir.path().join("board-job.gbrjob"),
+            r#"{
+                "Header": { "GenerationSoftware": { "Vendor": "KiCad", "Application": "Pcbnew" } },
+                "FilesAttributes": [
+                    { "Path": "front.gbr", "FileFunction": "Copper,L3,Bot", "FilePolarity": "Positive" },
+                    { "Path": "middle.gbr", "FileFunction": "Copper,L2,Inr", "FilePolarity": "Positive" },
+                    { "Path": "back.gbr", "FileFunction": "Copper,L1,Top", "FilePolarity": "Positive" },
+                    { "Path": "edge.gbr", "FileFunction": "Profile,NP", "FilePolarity": "Positive" }
+                ]
+            }"#,
+        )
+        .expect("Failed to write job file");
+
+        let config = Config {
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let stats = converter.get_conversion_stats();
+        let source_of = |output: &str| {
+            stats
+                .layer_mappings
+                .iter()
+                .find(|mapping| mapping.output == output)
+                .map(|mapping| mapping.source.as_str())
+        };
+        assert_eq!(source_of("Gerber_BottomLayer.GBL"), Some("front.gbr"));
+        assert_eq!(source_of("Gerber_TopLayer.GTL"), Some("back.gbr"));
+        assert_eq!(source_of("Gerber_InnerLayer1.G1"), Some("middle.gbr"));
+        assert_eq!(source_of("Gerber_BoardOutlineLayer.GKO"), Some("edge.gbr"));
+        // Files missing from the job still go through the naming patterns
+        assert_eq!(source_of("Drill_PTH_Through.DRL"), Some("board-PTH.drl"));
+        assert!(stats.unmatched_files.is_empty());
+    }
+
+    #[test]
+    fn test_file_function_attribute_identifies_unmatched_file() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in ["project-F_Cu.gbr", "project-Edge_Cuts.gbr"] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        fs::write(
+            input_dir.path().join("x7Qk2_export.art"),
+            "%TF.GenerationSoftware,KiCad,Pcbnew,8.0.0*%\n%TF.FileFunction,Copper,L2,Bot*%\n%FSLAX46Y46*%\n%MOMM*%\nM02*\n",
+        )
+        .expect("Failed to write input file");
+        fs::write(input_dir.path().join("notes.txt"), "not a layer\n")
+            .expect("Failed to write notes");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().to_path_buf(),
+            no_progress: true,
+            ..Default::default()
+        };
+        let mut converter = Converter::new(config);
+        converter.run().expect("Conversion should succeed");
+
+        let stats = converter.get_conversion_stats();
+        let bottom = stats
+            .layer_mappings
+            .iter()
+            .find(|mapping| mapping.output == "Gerber_BottomLayer.GBL")
+            .expect("Bottom layer should be derived from the attribute");
+        assert_eq!(bottom.source, "x7Qk2_export.art");
+        assert_eq!(stats.unmatched_files, vec!["notes.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_file_function_attribute_is_read_from_the_header() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let dir = tempdir().expect("Failed to create dir");
+        let header = "%TF.FileFunction,Soldermask,Top*%\n%FSLAX46Y46*%\n";
+        let body = "G04 padding*\n".repeat(10_000);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(format!("{}{}M02*\n", header, body).as_bytes())
+            .expect("Failed to compress");
+        let gzipped = dir.path().join("mask.art.gz");
+        fs::write(&gzipped, encoder.finish().expect("Failed to compress"))
+            .expect("Failed to write input file");
+        assert_eq!(attribute_layer(&gzipped), Some(LayerType::TopSoldermask));
+
+        // Attributes buried after the header are not looked for
+        let late = dir.path().join("late.art");
+        fs::write(&late, format!("{}{}M02*\n", body, header)).expect("Failed to write input file");
+        assert_eq!(attribute_layer(&late), None);
+    }
+
+    #[test]
+    fn test_extra_pattern_matches_plugin_filename() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+            "project-Front_Legend.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+
+        let convert = |extra_patterns: Vec<(LayerType, String)>| {
+            let output_dir = tempdir().expect("Failed to create output dir");
+            let config = Config {
+                eda: "kicad".to_string(),
+                path: input_dir.path().to_path_buf(),
+                output_path: output_dir.path().to_path_buf(),
+                no_progress: true,
+                extra_patterns,
+                ..Default::default()
+            };
+            let mut converter = Converter::new(config);
+            converter.run().expect("Conversion should succeed");
+            converter
+        };
+
+        let converter = convert(Vec::new());
+        assert!(converter
+            .unmatched_files
+            .contains(&"project-Front_Legend.gbr".to_string()));
+
+        let converter = convert(vec![(
+            LayerType::TopSilkscreen,
+            r"-Front_Legend\.gbr$".to_string(),
+        )]);
+        assert!(converter.unmatched_files.is_empty());
+        assert!(converter
+            .processed_files
+            .contains_key(&LayerType::TopSilkscreen));
+    }
+
     #[test]
     fn test_determine_g54_requirement() {
         let temp_dir = tempdir().expect("Failed to create temp dir");
@@ -552,6 +3283,7 @@ mod tests {
             no_progress: true,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         let converter = Converter::new(config);
@@ -0,0 +1,204 @@
+//! Gerber coordinate format handling
+//!
+//! Passes that rewrite coordinates work on the raw text instead of going
+//! through `gerber_parser`, so they have to interpret the `%FS` statement
+//! themselves. Both leading (`%FSLA...`) and trailing (`%FSTA...`) zero
+//! suppression are supported, and values are re-emitted in the same mode.
+
+use crate::error::Result;
+use anyhow::Context;
+use regex::Regex;
+
+/// Which zeros the file omits from coordinate numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZeroSuppression {
+    /// Leading zeros omitted (`L`), also used for files without suppression (`D`)
+    Leading,
+    /// Trailing zeros omitted (`T`, deprecated but still produced by older CAM tools)
+    Trailing,
+}
+
+/// Coordinate format declared by a `%FS` statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoordinateFormat {
+    pub zero_suppression: ZeroSuppression,
+    pub integer_digits: u32,
+    pub decimal_digits: u32,
+}
+
+impl CoordinateFormat {
+    /// Find and parse the `%FS` statement of a Gerber file
+    pub fn from_gerber(content: &str) -> Result<Option<Self>> {
+        let fs_regex = Regex::new(r"%FS([LTD])[AI]X(\d)(\d)Y\d\d\*%")
+            .context("Failed to compile format statement regex")?;
+
+        Ok(fs_regex.captures(content).map(|caps| {
+            let zero_suppression = if &caps[1] == "T" {
+                ZeroSuppression::Trailing
+            } else {
+                ZeroSuppression::Leading
+            };
+
+            Self {
+                zero_suppression,
+                integer_digits: caps[2].parse().unwrap_or(0),
+                decimal_digits: caps[3].parse().unwrap_or(0),
+            }
+        }))
+    }
+
+    /// Total number of digits in an unsuppressed coordinate
+    fn total_digits(&self) -> usize {
+        (self.integer_digits + self.decimal_digits) as usize
+    }
+
+    /// Interpret a coordinate number (without the axis letter)
+    pub fn parse(&self, number: &str) -> Option<f64> {
+        if number.contains('.') {
+            return number.parse().ok();
+        }
+
+        let (negative, digits) = match number.as_bytes().first()? {
+            b'-' => (true, &number[1..]),
+            b'+' => (false, &number[1..]),
+            _ => (false, number),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        // Trailing suppression drops zeros from the right, so pad them back
+        let digits = match self.zero_suppression {
+            ZeroSuppression::Trailing => {
+                format!("{:0<width$}", digits, width = self.total_digits())
+            }
+            ZeroSuppression::Leading => digits.to_string(),
+        };
+
+        let value = digits.parse::<f64>().ok()? / 10f64.powi(self.decimal_digits as i32);
+        Some(if negative { -value } else { value })
+    }
+
+    /// Format a value as a coordinate number in this format's suppression mode
+    pub fn format(&self, value: f64) -> String {
+        let scaled = (value * 10f64.powi(self.decimal_digits as i32)).round() as i64;
+        let sign = if scaled < 0 { "-" } else { "" };
+        let digits = format!(
+            "{:0>width$}",
+            scaled.unsigned_abs(),
+            width = self.total_digits()
+        );
+
+        let trimmed = match self.zero_suppression {
+            ZeroSuppression::Leading => digits.trim_start_matches('0'),
+            ZeroSuppression::Trailing => digits.trim_end_matches('0'),
+        };
+
+        if trimmed.is_empty() {
+            "0".to_string()
+        } else {
+            format!("{}{}", sign, trimmed)
+        }
+    }
+}
+
+/// Apply a function to every X/Y/I/J coordinate value in a Gerber file
+///
+/// The closure receives the axis letter and the value in file units. Files
+/// without a `%FS` statement are returned unchanged.
+pub fn map_coordinates<F>(content: &str, mut map: F) -> Result<String>
+where
+    F: FnMut(char, f64) -> f64,
+{
+    let Some(format) = CoordinateFormat::from_gerber(content)? else {
+        return Ok(content.to_string());
+    };
+
+    let coordinate_regex =
+        Regex::new(r"([XYIJ])([+-]?[0-9.]+)").context("Failed to compile coordinate regex")?;
+
+    let lines: Vec<String> = content
+        .split('\n')
+        .map(|line| {
+            // Extended commands and comments carry no plotting coordinates
+            if line.starts_with('%') || line.starts_with("G04") {
+                return line.to_string();
+            }
+
+            coordinate_regex
+                .replace_all(line, |caps: &regex::Captures| {
+                    let axis = caps[1].chars().next().unwrap_or('X');
+                    match format.parse(&caps[2]) {
+                        Some(value) => format!("{}{}", axis, format.format(map(axis, value))),
+                        None => caps[0].to_string(),
+                    }
+                })
+                .into_owned()
+        })
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRAILING: &str =
+        "%FSTAX24Y24*%\n%MOIN*%\n%ADD10C,0.010*%\nD10*\nX0125Y005D02*\nG01X1Y-0125D01*\nM02*\n";
+
+    #[test]
+    fn test_parse_format_statement() {
+        let format = CoordinateFormat::from_gerber(TRAILING).unwrap().unwrap();
+        assert_eq!(format.zero_suppression, ZeroSuppression::Trailing);
+        assert_eq!((format.integer_digits, format.decimal_digits), (2, 4));
+
+        let format = CoordinateFormat::from_gerber("%FSLAX46Y46*%\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(format.zero_suppression, ZeroSuppression::Leading);
+        assert_eq!((format.integer_digits, format.decimal_digits), (4, 6));
+    }
+
+    #[test]
+    fn test_trailing_zero_values() {
+        let format = CoordinateFormat::from_gerber(TRAILING).unwrap().unwrap();
+
+        assert_eq!(format.parse("0125"), Some(1.25));
+        assert_eq!(format.parse("005"), Some(0.5));
+        assert_eq!(format.parse("1"), Some(10.0));
+        assert_eq!(format.parse("-0125"), Some(-1.25));
+        assert_eq!(format.format(1.25), "0125");
+        assert_eq!(format.format(10.0), "1");
+        assert_eq!(format.format(0.0), "0");
+    }
+
+    #[test]
+    fn test_leading_zero_values() {
+        let format = CoordinateFormat {
+            zero_suppression: ZeroSuppression::Leading,
+            integer_digits: 4,
+            decimal_digits: 6,
+        };
+
+        assert_eq!(format.parse("1250000"), Some(1.25));
+        assert_eq!(format.format(1.25), "1250000");
+        assert_eq!(format.format(-0.5), "-500000");
+    }
+
+    #[test]
+    fn test_trailing_zero_file_round_trips() {
+        let mut seen = Vec::new();
+        let rewritten = map_coordinates(TRAILING, |axis, value| {
+            seen.push((axis, value));
+            value
+        })
+        .unwrap();
+
+        assert_eq!(rewritten, TRAILING);
+        assert_eq!(
+            seen,
+            vec![('X', 1.25), ('Y', 0.5), ('X', 10.0), ('Y', -1.25)]
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Comparison of converted JLC packages
+//!
+//! This module compares two converted output directories layer-by-layer,
+//! ignoring the per-run noise (header timestamps and hash apertures) that
+//! the converter injects into every Gerber file.
+
+use crate::{
+    error::{Result, ResultExt},
+    gerber::MAX_APERTURE_NUMBER,
+    patterns::LayerType,
+};
+use anyhow::Context;
+use md5::{Digest, Md5};
+use regex::Regex;
+use std::{collections::BTreeSet, fmt, fs, path::Path};
+use tracing::{debug, info};
+
+/// Result of comparing two converted packages
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PackageDiff {
+    /// Files present in both packages whose normalized content differs
+    pub changed: Vec<String>,
+    /// Files present in both packages with equivalent content
+    pub unchanged: Vec<String>,
+    /// Files only present in the first package
+    pub only_in_left: Vec<String>,
+    /// Files only present in the second package
+    pub only_in_right: Vec<String>,
+}
+
+impl PackageDiff {
+    /// Whether both packages contain the same layers with equivalent content
+    pub fn is_identical(&self) -> bool {
+        self.changed.is_empty() && self.only_in_left.is_empty() && self.only_in_right.is_empty()
+    }
+}
+
+impl fmt::Display for PackageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return writeln!(
+                f,
+                "Packages are equivalent ({} files)",
+                self.unchanged.len()
+            );
+        }
+
+        for name in &self.changed {
+            writeln!(f, "changed:  {}", name)?;
+        }
+        for name in &self.only_in_left {
+            writeln!(f, "removed:  {}", name)?;
+        }
+        for name in &self.only_in_right {
+            writeln!(f, "added:    {}", name)?;
+        }
+        writeln!(f, "{} file(s) unchanged", self.unchanged.len())
+    }
+}
+
+/// Normalize Gerber content so that two conversions of the same input compare equal
+///
+/// Removes the injected hash aperture and undoes the renumbering it caused,
+/// then strips the generated header lines (which carry a timestamp). Every
+/// real aperture definition stays part of the comparison.
+pub fn normalize_gerber_content(content: &str) -> Result<String> {
+    let content = strip_hash_aperture(&content.replace("\r\n", "\n"))?;
+
+    let normalized = content
+        .lines()
+        .filter(|line| {
+            !line.starts_with("G04 EasyEDA Pro") && !line.starts_with("G04 Gerber Generator")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(normalized)
+}
+
+/// Remove the hash aperture inserted by the converter, restoring the original numbering
+///
+/// The hash aperture is either a copy of the aperture right after it with a
+/// different size, or the only aperture of a file that had none. The last two
+/// digits of its size come from the MD5 of the file before it was inserted,
+/// which confirms the match. Content without a hash aperture is returned unchanged.
+fn strip_hash_aperture(content: &str) -> Result<String> {
+    let definition_regex =
+        Regex::new(r"^%ADD(\d{2,4})\D").context("Failed to compile aperture definition regex")?;
+    let size_regex = Regex::new(r",([\d.]+)").context("Failed to compile size regex")?;
+
+    let lines: Vec<&str> = content.split('\n').collect();
+    let definitions = lines
+        .iter()
+        .filter(|line| definition_regex.is_match(line))
+        .count();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(number) = definition_regex
+            .captures(line)
+            .and_then(|caps| caps[1].parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Some(size) = size_regex.captures(line).map(|caps| caps[1].to_string()) else {
+            continue;
+        };
+
+        let copies_next = lines.get(index + 1).is_some_and(|next| {
+            next.starts_with(&format!("%ADD{}", number + 1))
+                && size_regex.replace(next, format!(",{}", size).as_str())
+                    == line.replacen(
+                        &format!("%ADD{}", number),
+                        &format!("%ADD{}", number + 1),
+                        1,
+                    )
+        });
+        let only_definition = definitions == 1 && *line == format!("%ADD{}C,{}*%", number, size);
+        if !copies_next && !only_definition {
+            continue;
+        }
+
+        let restored = restore_aperture_numbers(&lines, index, number)?;
+        if hash_suffix_matches(&restored, &size) {
+            debug!("Removed hash aperture D{}", number);
+            return Ok(restored);
+        }
+    }
+
+    Ok(content.to_string())
+}
+
+/// Drop the definition at `index` and shift the apertures after `number` back down by one
+fn restore_aperture_numbers(lines: &[&str], index: usize, number: u32) -> Result<String> {
+    let renumbered_regex =
+        Regex::new(r"^(%ADD|G54D)(\d{2,4})(.*)$").context("Failed to compile renumber regex")?;
+
+    let restored = lines
+        .iter()
+        .enumerate()
+        .filter(|(position, _)| *position != index)
+        .map(|(_, line)| {
+            let Some(caps) = renumbered_regex.captures(line) else {
+                return line.to_string();
+            };
+            let shifted: u32 = caps[2].parse().unwrap_or(0);
+            if shifted <= number || shifted == MAX_APERTURE_NUMBER {
+                return line.to_string();
+            }
+            format!("{}{}{}", &caps[1], shifted - 1, &caps[3])
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(restored)
+}
+
+/// Whether an aperture size carries the hash suffix of `content`
+///
+/// Imported PCB documents are hashed with a `494d` prefix, so both forms are tried.
+fn hash_suffix_matches(content: &str, size: &str) -> bool {
+    let Some(suffix) = size.get(size.len().saturating_sub(2)..) else {
+        return false;
+    };
+
+    [content.to_string(), format!("494d{}", content)]
+        .iter()
+        .any(|hashed| {
+            let digest = Md5::digest(hashed.as_bytes());
+            format!("{:02}", digest[digest.len() - 1] % 100) == suffix
+        })
+}
+
+/// Compare two converted packages layer-by-layer
+pub fn diff_packages(left: &Path, right: &Path) -> Result<PackageDiff> {
+    info!(
+        "Comparing packages {} and {}",
+        left.display(),
+        right.display()
+    );
+
+    let left_files = list_package_files(left)?;
+    let right_files = list_package_files(right)?;
+
+    let mut diff = PackageDiff::default();
+
+    for name in left_files.union(&right_files) {
+        match (left_files.contains(name), right_files.contains(name)) {
+            (true, false) => diff.only_in_left.push(name.clone()),
+            (false, true) => diff.only_in_right.push(name.clone()),
+            _ => {
+                if files_equivalent(&left.join(name), &right.join(name), name)? {
+                    diff.unchanged.push(name.clone());
+                } else {
+                    debug!("Layer {} differs between packages", name);
+                    diff.changed.push(name.clone());
+                }
+            }
+        }
+    }
+
+    Ok(diff)
+}
+
+/// List the regular files at the top level of a package directory
+fn list_package_files(dir: &Path) -> Result<BTreeSet<String>> {
+    let files = fs::read_dir(dir)
+        .with_path_context("read directory", dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.to_string())
+        })
+        .collect();
+
+    Ok(files)
+}
+
+/// Compare two files after normalization appropriate for their type
+fn files_equivalent(left: &Path, right: &Path, name: &str) -> Result<bool> {
+    // Encrypted colorful layers use fresh key material on every run,
+    // so only their presence can be compared.
+    let encrypted = [
+        LayerType::ColorfulTopSilkscreen,
+        LayerType::ColorfulBottomSilkscreen,
+        LayerType::ColorfulBoardOutline,
+    ];
+    if encrypted
+        .iter()
+        .any(|layer| layer.to_jlc_filename() == name)
+    {
+        return Ok(true);
+    }
+
+    let left_bytes = fs::read(left).with_path_context("read package file", left)?;
+    let right_bytes = fs::read(right).with_path_context("read package file", right)?;
+
+    match (
+        std::str::from_utf8(&left_bytes),
+        std::str::from_utf8(&right_bytes),
+    ) {
+        (Ok(left_text), Ok(right_text)) => {
+            Ok(normalize_gerber_content(left_text)? == normalize_gerber_content(right_text)?)
+        }
+        _ => Ok(left_bytes == right_bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gerber::GerberProcessor;
+    use tempfile::tempdir;
+
+    const TOP: &str = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\n%ADD11R,1.000000X1.000000*%\n%ADD12C,0.200000*%\nD10*\nX0Y0D02*\nX1000000Y0D01*\nD11*\nX500000Y500000D03*\nM02*\n";
+    const BOTTOM: &str =
+        "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\nD10*\nX0Y0D02*\nX0Y1000000D01*\nM02*\n";
+
+    fn write_package(dir: &Path, top: &str, bottom: &str) {
+        let processor = GerberProcessor::new();
+        let top = processor
+            .process_gerber_content(top.to_string(), false)
+            .unwrap();
+        let bottom = processor
+            .process_gerber_content(bottom.to_string(), false)
+            .unwrap();
+
+        fs::write(dir.join("Gerber_TopLayer.GTL"), top).unwrap();
+        fs::write(dir.join("Gerber_BottomLayer.GBL"), bottom).unwrap();
+        fs::write(dir.join("Drill_PTH_Through.DRL"), "M48\nT1C0.8\nM30\n").unwrap();
+    }
+
+    #[test]
+    fn test_normalize_ignores_header_and_hash_aperture() {
+        let processor = GerberProcessor::new();
+        let first = processor
+            .process_gerber_content(TOP.to_string(), false)
+            .unwrap();
+        let second = processor
+            .process_gerber_content(TOP.to_string(), false)
+            .unwrap();
+
+        assert_eq!(
+            normalize_gerber_content(&first).unwrap(),
+            normalize_gerber_content(&second).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize_restores_the_original_apertures() {
+        let processor = GerberProcessor::new();
+        let original = normalize_gerber_content(TOP).unwrap();
+
+        // The hash aperture lands at a random position, so try several
+        for _ in 0..20 {
+            let processed = processor
+                .process_gerber_content(TOP.to_string(), false)
+                .unwrap();
+            assert_ne!(processed.lines().count(), TOP.lines().count());
+            assert_eq!(normalize_gerber_content(&processed).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_changed_aperture_size() {
+        let left = tempdir().unwrap();
+        let right = tempdir().unwrap();
+
+        write_package(left.path(), TOP, BOTTOM);
+        write_package(
+            right.path(),
+            TOP,
+            &BOTTOM.replace("%ADD10C,0.100000*%", "%ADD10C,0.500000*%"),
+        );
+
+        let diff = diff_packages(left.path(), right.path()).unwrap();
+
+        assert_eq!(diff.changed, vec!["Gerber_BottomLayer.GBL".to_string()]);
+        assert_eq!(diff.unchanged.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_reports_only_changed_layer() {
+        let left = tempdir().unwrap();
+        let right = tempdir().unwrap();
+
+        write_package(left.path(), TOP, BOTTOM);
+        write_package(
+            right.path(),
+            TOP,
+            &BOTTOM.replace("X0Y1000000D01*", "X0Y2000000D01*"),
+        );
+
+        let diff = diff_packages(left.path(), right.path()).unwrap();
+
+        assert_eq!(diff.changed, vec!["Gerber_BottomLayer.GBL".to_string()]);
+        assert_eq!(diff.unchanged.len(), 2);
+        assert!(diff.only_in_left.is_empty());
+        assert!(diff.only_in_right.is_empty());
+        assert!(!diff.is_identical());
+    }
+}
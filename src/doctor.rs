@@ -0,0 +1,119 @@
+//! Environment self-test for `--doctor`
+//!
+//! Runs the pieces of a conversion that depend on the build and install
+//! (embedded assets, the colorful silkscreen key, Gerber processing) without
+//! touching any user files, so install problems show up before a real run.
+
+use crate::{colorful, converter, gerber::GerberProcessor};
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// Outcome of a single doctor check
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoctorCheck {
+    /// Short description of what was checked
+    pub name: String,
+    /// Failure message, or `None` when the check passed
+    pub error: Option<String>,
+}
+
+/// Results of every doctor check
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DoctorReport {
+    /// Checks in the order they ran
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Run the library checks
+    pub fn run() -> Self {
+        let mut report = Self::default();
+        report.check("Embedded assets load", converter::check_embedded_assets);
+        report.check(
+            "Colorful silkscreen RSA key parses",
+            colorful::check_embedded_key,
+        );
+        report.check(
+            "Gerber round-trips through the processor",
+            check_gerber_round_trip,
+        );
+        report
+    }
+
+    /// Record the result of `check` under `name`
+    pub fn check(&mut self, name: &str, check: impl FnOnce() -> Result<()>) {
+        self.checks.push(DoctorCheck {
+            name: name.to_string(),
+            error: check().err().map(|e| format!("{:#}", e)),
+        });
+    }
+
+    /// Whether every check passed
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+
+    /// Checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &DoctorCheck> {
+        self.checks.iter().filter(|check| check.error.is_some())
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.error {
+                None => writeln!(f, "[ OK ] {}", check.name)?,
+                Some(error) => writeln!(f, "[FAIL] {}: {}", check.name, error)?,
+            }
+        }
+        let failed = self.failures().count();
+        if failed == 0 {
+            writeln!(f, "All {} checks passed", self.checks.len())
+        } else {
+            writeln!(f, "{} of {} checks failed", failed, self.checks.len())
+        }
+    }
+}
+
+/// Process a minimal Gerber and make sure its commands survive
+fn check_gerber_round_trip() -> Result<()> {
+    const COMMANDS: &str = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nG54D10*\nX0Y0D03*\nM02*\n";
+
+    let processed = GerberProcessor::new()
+        .with_seed(Some(0))
+        .process_gerber_content(COMMANDS.to_string(), false)?;
+    for command in ["%FSLAX46Y46*%", "%MOMM*%", "X0Y0D03*", "M02*"] {
+        if !processed.contains(command) {
+            bail!("Processed Gerber lost the `{}` command", command);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_doctor_is_healthy() {
+        let report = DoctorReport::run();
+
+        assert_eq!(report.checks.len(), 3);
+        assert!(report.is_healthy(), "unhealthy environment:\n{}", report);
+        assert!(report.to_string().ends_with("All 3 checks passed\n"));
+    }
+
+    #[test]
+    fn test_failed_check_is_listed() {
+        let mut report = DoctorReport::run();
+        report.check("CJK font found", || Err(anyhow!("no font")));
+
+        assert!(!report.is_healthy());
+        assert_eq!(report.failures().count(), 1);
+        let output = report.to_string();
+        assert!(output.contains("[FAIL] CJK font found: no font"));
+        assert!(output.ends_with("1 of 4 checks failed\n"));
+    }
+}
@@ -0,0 +1,569 @@
+//! Excellon drill file processing
+//!
+//! Drill files are copied verbatim unless a normalization is requested. This
+//! module handles the optional rewrites, such as converting inch drill files
+//! to millimeters so they match the rest of a metric JLC job, and splitting
+//! combined drill files into plated and non-plated halves.
+
+use crate::coordinates::{CoordinateFormat, ZeroSuppression};
+use crate::error::Result;
+use anyhow::Context;
+use regex::Regex;
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// Millimeters per inch
+const MM_PER_INCH: f64 = 25.4;
+
+/// Drill file processor for Excellon-specific conversions
+#[derive(Debug, Default)]
+pub struct DrillProcessor {
+    /// Whether to convert inch drill files to millimeters
+    normalize_units: bool,
+    /// Whether to split combined drill files by plating
+    split_plating: bool,
+    /// Whether to rewrite the header into the form JLC expects
+    normalize_header: bool,
+}
+
+/// Plating of a drill tool, as declared by the comments in the drill header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Plating {
+    Plated,
+    NonPlated,
+}
+
+/// A combined drill file split into its plated and non-plated tools
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitDrill {
+    pub plated: String,
+    pub non_plated: String,
+}
+
+impl DrillProcessor {
+    /// Create a new drill processor with configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure conversion of inch drill files to millimeters
+    pub fn with_normalize_units(mut self, normalize: bool) -> Self {
+        self.normalize_units = normalize;
+        self
+    }
+
+    /// Configure splitting of drill files that mix plated and non-plated tools
+    pub fn with_split_plating(mut self, split: bool) -> Self {
+        self.split_plating = split;
+        self
+    }
+
+    /// Configure rewriting of the drill header (`M48`, `FMAT,2`, no `LZ` coordinates)
+    pub fn with_normalize_header(mut self, normalize: bool) -> Self {
+        self.normalize_header = normalize;
+        self
+    }
+
+    /// Split a drill file that contains both plated and non-plated tools
+    ///
+    /// Tools are classified by the comment preceding their definition, either
+    /// `;TYPE=PLATED` / `;TYPE=NON_PLATED` or KiCad's `TA.AperFunction,Plated` /
+    /// `NonPlated` attributes. Tools without such a comment count as plated.
+    /// Returns `None` when splitting is disabled or the file holds only one kind.
+    pub fn split_plating(&self, content: &str) -> Option<SplitDrill> {
+        if !self.split_plating {
+            return None;
+        }
+
+        let tools = tool_platings(content);
+        let has_plated = tools.values().any(|plating| *plating == Plating::Plated);
+        let has_non_plated = tools.values().any(|plating| *plating == Plating::NonPlated);
+        if !has_plated || !has_non_plated {
+            return None;
+        }
+
+        info!("Splitting combined drill file into plated and non-plated holes");
+
+        let mut plated = Vec::new();
+        let mut non_plated = Vec::new();
+        let mut in_header = true;
+        let mut current = None;
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+
+            let target = if in_header {
+                if trimmed == "%" || trimmed == "M95" {
+                    in_header = false;
+                    None
+                } else if trimmed.starts_with(';') {
+                    comment_plating(trimmed)
+                } else {
+                    tool_number(trimmed).and_then(|tool| tools.get(&tool).copied())
+                }
+            } else {
+                if let Some(tool) = tool_number(trimmed) {
+                    current = tools.get(&tool).copied();
+                }
+                if trimmed == "M30" {
+                    current = None;
+                }
+                current
+            };
+
+            match target {
+                Some(Plating::Plated) => plated.push(line),
+                Some(Plating::NonPlated) => non_plated.push(line),
+                None => {
+                    plated.push(line);
+                    non_plated.push(line);
+                }
+            }
+        }
+
+        let finish = |lines: Vec<&str>| {
+            let mut joined = lines.join("\n");
+            if content.ends_with('\n') {
+                joined.push('\n');
+            }
+            joined
+        };
+
+        Some(SplitDrill {
+            plated: finish(plated),
+            non_plated: finish(non_plated),
+        })
+    }
+
+    /// Process drill file content with all enabled transformations
+    pub fn process_drill_content(&self, content: String) -> Result<String> {
+        let mut content = content;
+        if self.normalize_header {
+            content = self.normalize_drill_header(content)?;
+        }
+        if self.normalize_units {
+            content = self.convert_inch_to_metric(content)?;
+        }
+        Ok(content)
+    }
+
+    /// Give the file an `M48 ... %` header with `FMAT,2` and decimal coordinates
+    ///
+    /// Files without `M48` get one, taking the leading comments, unit and tool
+    /// definitions as the header when there is no `%` terminator either.
+    /// Coordinates of `LZ` files are rewritten with a decimal point and the
+    /// unit line switched to `TZ`, which JLC reads reliably.
+    fn normalize_drill_header(&self, content: String) -> Result<String> {
+        let lines: Vec<&str> = content.lines().collect();
+        let is_terminator = |line: &&str| matches!(line.trim(), "%" | "M95");
+
+        let m48 = lines.iter().position(|line| line.trim() == "M48");
+        let (header, terminator, body) = if let Some(m48) = m48 {
+            // Anything before M48 can only be comments, which belong in the header
+            let end = lines[m48..]
+                .iter()
+                .position(is_terminator)
+                .map_or(lines.len(), |end| m48 + end);
+            (
+                lines[..m48]
+                    .iter()
+                    .chain(&lines[m48 + 1..end])
+                    .copied()
+                    .collect(),
+                lines.get(end).copied().unwrap_or("%"),
+                lines.get(end + 1..).unwrap_or_default().to_vec(),
+            )
+        } else {
+            info!("Adding missing M48 header to drill file");
+            let end = lines
+                .iter()
+                .position(is_terminator)
+                .map(|end| (end, end + 1))
+                .unwrap_or_else(|| {
+                    let end = lines
+                        .iter()
+                        .position(|line| !is_header_line(line))
+                        .unwrap_or(lines.len());
+                    (end, end)
+                });
+            (lines[..end.0].to_vec(), "%", lines[end.1..].to_vec())
+        };
+
+        let format = leading_zero_format(&header)?;
+        let mut result = vec!["M48".to_string()];
+        if !header
+            .iter()
+            .any(|line| line.trim().to_ascii_uppercase().starts_with("FMAT"))
+        {
+            result.push("FMAT,2".to_string());
+        }
+        for line in &header {
+            if format.is_some() && is_unit_line(line) {
+                result.push(line.trim().to_ascii_uppercase().replace(",LZ", ",TZ"));
+            } else {
+                result.push(line.to_string());
+            }
+        }
+        result.push(terminator.to_string());
+
+        let coordinate_regex =
+            Regex::new(r"([XYIJ])([+-]?[0-9.]+)").context("Failed to compile coordinate regex")?;
+        for line in body {
+            let Some(format) = format.filter(|_| !line.trim_start().starts_with(';')) else {
+                result.push(line.to_string());
+                continue;
+            };
+            let rewritten =
+                coordinate_regex.replace_all(line, |caps: &regex::Captures| {
+                    match format.parse(&caps[2]) {
+                        Some(value) => format!("{}{}", &caps[1], format_millimeters(value)),
+                        None => caps[0].to_string(),
+                    }
+                });
+            result.push(rewritten.into_owned());
+        }
+
+        let mut normalized = result.join("\n");
+        if content.ends_with('\n') {
+            normalized.push('\n');
+        }
+        Ok(normalized)
+    }
+
+    /// Rewrite an inch Excellon file in millimeters with explicit decimal coordinates
+    ///
+    /// Metric files are returned unchanged.
+    fn convert_inch_to_metric(&self, content: String) -> Result<String> {
+        let Some(format) = inch_coordinate_format(&content)? else {
+            debug!("Drill file is not in inches; leaving units unchanged");
+            return Ok(content);
+        };
+
+        info!("Converting inch drill file to millimeters");
+
+        let tool_regex = Regex::new(r"^(T\d+[A-Z0-9.]*?C)([0-9.]+)(.*)$")
+            .context("Failed to compile tool definition regex")?;
+        let coordinate_regex =
+            Regex::new(r"([XYIJ])([+-]?[0-9.]+)").context("Failed to compile coordinate regex")?;
+
+        let lines: Vec<String> = content
+            .lines()
+            .map(|line| {
+                let upper = line.trim().to_ascii_uppercase();
+
+                if upper.starts_with(';') || upper.starts_with('%') {
+                    return line.to_string();
+                }
+                if upper.starts_with("INCH") {
+                    return "METRIC".to_string();
+                }
+                if upper == "M72" {
+                    return "M71".to_string();
+                }
+
+                // Tool diameters are always written with a decimal point
+                if let Some(caps) = tool_regex.captures(line.trim()) {
+                    let diameter: f64 = caps[2].parse().unwrap_or(0.0);
+                    return format!(
+                        "{}{}{}",
+                        &caps[1],
+                        format_millimeters(diameter * MM_PER_INCH),
+                        &caps[3]
+                    );
+                }
+
+                coordinate_regex
+                    .replace_all(line, |caps: &regex::Captures| {
+                        match format.parse(&caps[2]) {
+                            Some(value) => {
+                                format!("{}{}", &caps[1], format_millimeters(value * MM_PER_INCH))
+                            }
+                            None => caps[0].to_string(),
+                        }
+                    })
+                    .into_owned()
+            })
+            .collect();
+
+        let mut converted = lines.join("\n");
+        if content.ends_with('\n') {
+            converted.push('\n');
+        }
+        Ok(converted)
+    }
+}
+
+/// Whether a line belongs in a drill header: comments, units, format and tool definitions
+fn is_header_line(line: &str) -> bool {
+    let upper = line.trim().to_ascii_uppercase();
+    upper.is_empty()
+        || upper.starts_with(';')
+        || upper.starts_with("FMAT")
+        || upper.starts_with("VER")
+        || upper.starts_with("ICI")
+        || is_unit_line(&upper)
+        || (tool_number(&upper).is_some() && upper.contains('C'))
+}
+
+/// Whether a line selects the units (`METRIC`, `INCH`, `M71`, `M72`)
+fn is_unit_line(line: &str) -> bool {
+    let upper = line.trim().to_ascii_uppercase();
+    upper.starts_with("METRIC") || upper.starts_with("INCH") || upper == "M71" || upper == "M72"
+}
+
+/// Coordinate format of a header whose unit line keeps leading zeros (`LZ`)
+///
+/// The digit split comes from a `;FILE_FORMAT=a:b` comment, defaulting to 2:4
+/// for inches and 3:3 for millimeters.
+fn leading_zero_format(header: &[&str]) -> Result<Option<CoordinateFormat>> {
+    let file_format_regex = Regex::new(r"(?i)FILE_FORMAT\s*=\s*(\d)\s*:\s*(\d)")
+        .context("Failed to compile file format regex")?;
+
+    let Some(unit_line) = header
+        .iter()
+        .map(|line| line.trim().to_ascii_uppercase())
+        .find(|line| is_unit_line(line))
+        .filter(|line| line.contains(",LZ"))
+    else {
+        return Ok(None);
+    };
+
+    let default_digits = if unit_line.starts_with("INCH") {
+        (2, 4)
+    } else {
+        (3, 3)
+    };
+    let (integer_digits, decimal_digits) = header
+        .iter()
+        .find_map(|line| file_format_regex.captures(line))
+        .map(|caps| (caps[1].parse().unwrap_or(2), caps[2].parse().unwrap_or(4)))
+        .unwrap_or(default_digits);
+
+    Ok(Some(CoordinateFormat {
+        zero_suppression: ZeroSuppression::Trailing,
+        integer_digits,
+        decimal_digits,
+    }))
+}
+
+/// Plating of every tool defined in the drill header
+fn tool_platings(content: &str) -> HashMap<u32, Plating> {
+    let mut tools = HashMap::new();
+    let mut plating = Plating::Plated;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == "%" || trimmed == "M95" {
+            break;
+        }
+
+        if trimmed.starts_with(';') {
+            if let Some(declared) = comment_plating(trimmed) {
+                plating = declared;
+            }
+        } else if let Some(tool) = tool_number(trimmed) {
+            tools.insert(tool, plating);
+        }
+    }
+
+    tools
+}
+
+/// Plating declared by a drill header comment, if any
+fn comment_plating(comment: &str) -> Option<Plating> {
+    let upper = comment.to_ascii_uppercase();
+    if upper.contains("NON_PLATED") || upper.contains("NONPLATED") || upper.contains("TYPE=NPTH") {
+        Some(Plating::NonPlated)
+    } else if upper.contains("TYPE=PLATED") || upper.contains(",PLATED") {
+        Some(Plating::Plated)
+    } else {
+        None
+    }
+}
+
+/// Tool number of a `Tn` definition or selection line; `T0` unloads the tool
+fn tool_number(line: &str) -> Option<u32> {
+    let digits: String = line
+        .strip_prefix('T')?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok().filter(|tool| *tool != 0)
+}
+
+/// Coordinate format of an inch Excellon file, or `None` for metric files
+///
+/// `LZ` keeps leading zeros (trailing ones are suppressed), `TZ` the reverse.
+/// The digit split comes from a `;FILE_FORMAT=a:b` comment, defaulting to 2:4.
+fn inch_coordinate_format(content: &str) -> Result<Option<CoordinateFormat>> {
+    let file_format_regex = Regex::new(r"(?i)FILE_FORMAT\s*=\s*(\d)\s*:\s*(\d)")
+        .context("Failed to compile file format regex")?;
+
+    let mut unit_line = None;
+    for line in content.lines() {
+        let upper = line.trim().to_ascii_uppercase();
+        if upper.starts_with("INCH") || upper == "M72" {
+            unit_line = Some(upper);
+            break;
+        }
+        if upper.starts_with("METRIC") || upper == "M71" {
+            return Ok(None);
+        }
+    }
+
+    let Some(unit_line) = unit_line else {
+        return Ok(None);
+    };
+
+    let (integer_digits, decimal_digits) = file_format_regex
+        .captures(content)
+        .map(|caps| (caps[1].parse().unwrap_or(2), caps[2].parse().unwrap_or(4)))
+        .unwrap_or((2, 4));
+
+    let zero_suppression = if unit_line.contains(",LZ") {
+        ZeroSuppression::Trailing
+    } else {
+        ZeroSuppression::Leading
+    };
+
+    Ok(Some(CoordinateFormat {
+        zero_suppression,
+        integer_digits,
+        decimal_digits,
+    }))
+}
+
+/// Format a millimeter value with an explicit decimal point and at most 4 decimals
+fn format_millimeters(value: f64) -> String {
+    let formatted = format!("{:.4}", value);
+    let trimmed = formatted.trim_end_matches('0');
+    let trimmed = if trimmed.ends_with('.') {
+        format!("{}0", trimmed)
+    } else {
+        trimmed.to_string()
+    };
+
+    if trimmed == "-0.0" {
+        "0.0".to_string()
+    } else {
+        trimmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INCH_DRILL: &str = "M48\n;FILE_FORMAT=2:4\nINCH,TZ\nT1C0.0320\nT2F00S00C0.1250\n%\nG90\nT1\nX012500Y-003000\nX1.5Y0.25\nT2\nX0Y0\nM30\n";
+
+    #[test]
+    fn test_inch_drill_is_converted_to_metric() {
+        let processor = DrillProcessor::new().with_normalize_units(true);
+        let converted = processor
+            .process_drill_content(INCH_DRILL.to_string())
+            .unwrap();
+
+        assert!(converted.contains("\nMETRIC\n"));
+        assert!(!converted.contains("INCH"));
+        assert!(converted.contains("T1C0.8128\n"));
+        assert!(converted.contains("T2F00S00C3.175\n"));
+        assert!(converted.contains("X31.75Y-7.62\n"));
+        assert!(converted.contains("X38.1Y6.35\n"));
+        assert!(converted.contains("X0.0Y0.0\n"));
+        assert!(converted.ends_with("M30\n"));
+    }
+
+    #[test]
+    fn test_leading_zero_inch_format() {
+        let content = "M48\nINCH,LZ\nT1C0.0320\n%\nT1\nX0125Y-0025\nM30\n";
+        let processor = DrillProcessor::new().with_normalize_units(true);
+        let converted = processor
+            .process_drill_content(content.to_string())
+            .unwrap();
+
+        // Trailing zeros are suppressed: X0125 -> 01.2500 in, Y-0025 -> -00.2500 in
+        assert!(converted.contains("X31.75Y-6.35\n"));
+    }
+
+    const COMBINED_DRILL: &str = "M48\n;TYPE=PLATED\nT1C0.800\nT2C1.000\n;TYPE=NON_PLATED\nT3C3.200\n%\nG90\nG05\nT1\nX10.0Y10.0\nX12.0Y10.0\nT2\nX20.0Y5.0\nT3\nX2.0Y2.0\nX48.0Y2.0\nT0\nM30\n";
+
+    #[test]
+    fn test_combined_drill_is_split_by_plating() {
+        let processor = DrillProcessor::new().with_split_plating(true);
+        let split = processor.split_plating(COMBINED_DRILL).unwrap();
+
+        assert!(split.plated.contains("T1C0.800\nT2C1.000\n"));
+        assert!(split.plated.contains("X10.0Y10.0\nX12.0Y10.0\n"));
+        assert!(split.plated.contains("X20.0Y5.0\n"));
+        assert!(!split.plated.contains("T3"));
+        assert!(!split.plated.contains("X2.0Y2.0"));
+
+        assert!(split.non_plated.contains(";TYPE=NON_PLATED\nT3C3.200\n"));
+        assert!(split.non_plated.contains("T3\nX2.0Y2.0\nX48.0Y2.0\n"));
+        assert!(!split.non_plated.contains("T1"));
+
+        for half in [&split.plated, &split.non_plated] {
+            assert!(half.starts_with("M48\n"));
+            assert!(half.contains("%\nG90\nG05\n"));
+            assert!(half.ends_with("M30\n"));
+        }
+    }
+
+    #[test]
+    fn test_single_plating_drill_is_not_split() {
+        let processor = DrillProcessor::new().with_split_plating(true);
+        let plated_only = "M48\n;TYPE=PLATED\nT1C0.800\n%\nT1\nX1.0Y1.0\nM30\n";
+
+        assert_eq!(processor.split_plating(plated_only), None);
+        assert_eq!(DrillProcessor::new().split_plating(COMBINED_DRILL), None);
+    }
+
+    #[test]
+    fn test_headerless_drill_gets_m48_block() {
+        let content = "METRIC,LZ\nT1C0.300\nT2C1.000\nT1\nX010000Y005000\nT2\nX-00250Y0\nM30\n";
+        let processor = DrillProcessor::new().with_normalize_header(true);
+
+        let normalized = processor
+            .process_drill_content(content.to_string())
+            .unwrap();
+
+        assert_eq!(
+            normalized,
+            "M48\nFMAT,2\nMETRIC,TZ\nT1C0.300\nT2C1.000\n%\nT1\nX10.0Y5.0\nT2\nX-2.5Y0.0\nM30\n"
+        );
+    }
+
+    #[test]
+    fn test_drill_header_normalization_keeps_complete_header() {
+        let content =
+            "M48\n;DRILL file\nFMAT,2\nMETRIC,TZ\nT1C0.800\n%\nG90\nT1\nX10.0Y10.0\nM30\n";
+        let processor = DrillProcessor::new().with_normalize_header(true);
+
+        assert_eq!(
+            processor
+                .process_drill_content(content.to_string())
+                .unwrap(),
+            content
+        );
+
+        // Only FMAT is missing here
+        let without_format = content.replace("FMAT,2\n", "");
+        assert_eq!(
+            processor.process_drill_content(without_format).unwrap(),
+            content.replace("M48\n;DRILL file\nFMAT,2\n", "M48\nFMAT,2\n;DRILL file\n")
+        );
+    }
+
+    #[test]
+    fn test_metric_drill_is_unchanged() {
+        let content = "M48\nMETRIC,TZ\nT1C0.800\n%\nT1\nX012500Y-003000\nM30\n";
+        let processor = DrillProcessor::new().with_normalize_units(true);
+
+        assert_eq!(
+            processor
+                .process_drill_content(content.to_string())
+                .unwrap(),
+            content
+        );
+    }
+}
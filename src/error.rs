@@ -46,9 +46,18 @@ pub enum TransJlcError {
     #[error("No matching EDA pattern found for files in directory")]
     NoMatchingPattern,
 
+    #[error("No recognizable Gerber/drill files found, got: {files}")]
+    NoRecognizableFiles { files: String },
+
+    #[error("Input archive {path} was empty")]
+    EmptyArchive { path: String },
+
     #[error("Unsupported EDA format: {format}")]
     UnsupportedEda { format: String },
 
+    #[error("Unsupported input format: {hint}")]
+    UnsupportedFormat { hint: String },
+
     #[error("Invalid Gerber file format: {reason}")]
     InvalidGerberFormat { reason: String },
 
@@ -60,4 +69,17 @@ pub enum TransJlcError {
 
     #[error("Hash aperture generation failed: {reason}")]
     HashApertureError { reason: String },
+
+    #[error("Invalid layer patterns in {matcher}: {details}")]
+    InvalidPatterns { matcher: String, details: String },
+
+    #[error("Conversion cancelled")]
+    Cancelled,
+
+    #[error("Insufficient disk space in {path}: {required} bytes required, {available} available")]
+    InsufficientDiskSpace {
+        path: String,
+        required: u64,
+        available: u64,
+    },
 }
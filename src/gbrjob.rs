@@ -0,0 +1,214 @@
+//! Gerber job file (`.gbrjob`) support
+//!
+//! KiCad writes a JSON job file next to its plots that lists every Gerber file
+//! together with its `FileFunction` attribute. When one is present in the input
+//! it describes the layer stackup exactly, so its mapping is used in preference
+//! to filename patterns. Files the job does not list (KiCad leaves out the
+//! drill files, for example) still go through the regular pattern matching.
+
+use crate::error::{Result, ResultExt};
+use crate::patterns::LayerType;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// File extension of Gerber job files
+const GBRJOB_EXTENSION: &str = "gbrjob";
+
+/// The parts of a job file TransJLC cares about
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JobFile {
+    #[serde(default)]
+    files_attributes: Vec<FileAttributes>,
+}
+
+/// One entry of the `FilesAttributes` list
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FileAttributes {
+    path: String,
+    file_function: String,
+}
+
+/// Filename to layer mapping taken from a Gerber job file
+#[derive(Debug, Clone, Default)]
+pub struct GerberJob {
+    /// Layer types keyed by lowercase file name
+    layers: HashMap<String, LayerType>,
+}
+
+impl GerberJob {
+    /// Find the first job file among the input files
+    pub fn find(files: &[PathBuf]) -> Option<&PathBuf> {
+        files.iter().find(|file| is_job_file(file))
+    }
+
+    /// Load and parse a job file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_path_context("read Gerber job", path)?;
+        let job = Self::parse(&content)
+            .with_context(|| format!("Invalid Gerber job file: {}", path.display()))?;
+        info!(
+            "Using layer stackup from {} ({} files)",
+            path.display(),
+            job.layers.len()
+        );
+        Ok(job)
+    }
+
+    /// Parse the JSON content of a job file
+    pub fn parse(content: &str) -> Result<Self> {
+        let job: JobFile = serde_json::from_str(content).context("Failed to parse JSON")?;
+        let mut layers = HashMap::new();
+
+        for file in job.files_attributes {
+            let name = Path::new(&file.path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(&file.path)
+                .to_lowercase();
+
+            match layer_from_file_function(&file.file_function) {
+                Some(layer) => {
+                    debug!("Job file: {} -> {:?}", name, layer);
+                    layers.insert(name, layer);
+                }
+                None => warn!(
+                    "Job file lists {} with unsupported function '{}'",
+                    file.path, file.file_function
+                ),
+            }
+        }
+
+        Ok(Self { layers })
+    }
+
+    /// Layer type the job assigns to a file name
+    pub fn layer_for(&self, filename: &str) -> Option<LayerType> {
+        self.layers.get(&filename.to_lowercase()).cloned()
+    }
+
+    /// Number of files with a known layer type
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Whether the job assigns no layer at all
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+/// Whether a path is a Gerber job file
+pub fn is_job_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(GBRJOB_EXTENSION))
+}
+
+/// Map a Gerber X2 `FileFunction` value such as `Copper,L2,Inr` to a layer type
+///
+/// Inner copper layers are numbered from the top, so `L2` is the first inner
+/// layer.
+pub fn layer_from_file_function(value: &str) -> Option<LayerType> {
+    let fields: Vec<String> = value
+        .split(',')
+        .map(|field| field.trim().to_lowercase())
+        .collect();
+    let kind = fields.first()?.as_str();
+    let field = |index: usize| fields.get(index).map(String::as_str);
+
+    let layer = match kind {
+        "copper" => match field(2)? {
+            "top" => LayerType::TopCopper,
+            "bot" => LayerType::BottomCopper,
+            "inr" => {
+                let number: u32 = field(1)?.strip_prefix('l')?.parse().ok()?;
+                LayerType::InnerLayer(number.checked_sub(1).filter(|n| *n > 0)?)
+            }
+            _ => return None,
+        },
+        "soldermask" => side(
+            field(1)?,
+            LayerType::TopSoldermask,
+            LayerType::BottomSoldermask,
+        )?,
+        "legend" => side(
+            field(1)?,
+            LayerType::TopSilkscreen,
+            LayerType::BottomSilkscreen,
+        )?,
+        "paste" | "solderpaste" => side(
+            field(1)?,
+            LayerType::TopPasteMask,
+            LayerType::BottomPasteMask,
+        )?,
+        "profile" => LayerType::BoardOutline,
+        "plated" => LayerType::PthThrough,
+        "nonplated" => LayerType::NpthThrough,
+        "assemblydrawing" => side(field(1)?, LayerType::TopFab, LayerType::BottomFab)?,
+        _ => return None,
+    };
+
+    Some(layer)
+}
+
+/// Pick the top or bottom variant of a layer from a `Top`/`Bot` field
+fn side(value: &str, top: LayerType, bottom: LayerType) -> Option<LayerType> {
+    match value {
+        "top" => Some(top),
+        "bot" => Some(bottom),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_functions_map_to_layers() {
+        let cases = [
+            ("Copper,L1,Top", Some(LayerType::TopCopper)),
+            ("Copper,L2,Inr", Some(LayerType::InnerLayer(1))),
+            ("Copper,L5,Inr", Some(LayerType::InnerLayer(4))),
+            ("Copper,L6,Bot", Some(LayerType::BottomCopper)),
+            ("SolderMask,Bot", Some(LayerType::BottomSoldermask)),
+            ("Legend,Top", Some(LayerType::TopSilkscreen)),
+            ("SolderPaste,Top", Some(LayerType::TopPasteMask)),
+            ("Paste,Bot", Some(LayerType::BottomPasteMask)),
+            ("Profile,NP", Some(LayerType::BoardOutline)),
+            ("Plated,1,4,PTH", Some(LayerType::PthThrough)),
+            ("NonPlated,1,4,NPTH", Some(LayerType::NpthThrough)),
+            ("Copper,L1,Inr", None),
+            ("Keep-out,Top", None),
+            ("", None),
+        ];
+
+        for (function, expected) in cases {
+            assert_eq!(layer_from_file_function(function), expected, "{}", function);
+        }
+    }
+
+    #[test]
+    fn test_parse_job_file() {
+        let job = GerberJob::parse(
+            r#"{
+                "Header": { "GenerationSoftware": { "Vendor": "KiCad" } },
+                "FilesAttributes": [
+                    { "Path": "plots/Board-Top.gbr", "FileFunction": "Copper,L1,Top", "FilePolarity": "Positive" },
+                    { "Path": "Board-User.gbr", "FileFunction": "Other,Comment", "FilePolarity": "Positive" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(job.len(), 1);
+        assert_eq!(job.layer_for("board-top.GBR"), Some(LayerType::TopCopper));
+        assert_eq!(job.layer_for("Board-User.gbr"), None);
+        assert!(GerberJob::parse("not json").is_err());
+    }
+}
@@ -3,13 +3,23 @@
 //! This module handles Gerber file format-specific operations including
 //! aperture prefix normalization and hash aperture generation.
 
-use crate::error::Result;
+use crate::error::{Result, TransJlcError};
+use crate::gbrjob::layer_from_file_function;
+use crate::patterns::LayerType;
 use anyhow::Context;
+use indicatif::ProgressBar;
 use md5::{Digest, Md5};
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use regex::Regex;
+use std::fmt;
 use tracing::{debug, info, warn};
 
+/// Number of progress steps reported while processing one Gerber file
+const GERBER_STAGES: u64 = 3;
+
+/// Highest aperture number, which renumbering for the hash aperture never shifts
+pub(crate) const MAX_APERTURE_NUMBER: u32 = 9999;
+
 /// Gerber file processor for format-specific conversions
 pub struct GerberProcessor {
     /// Whether to ignore hash aperture generation
@@ -20,6 +30,18 @@ pub struct GerberProcessor {
 
     /// Maximum file size for hash processing (bytes)
     max_hash_file_size: usize,
+
+    /// Whether output must be reproducible (fixed header time, content-seeded hash)
+    deterministic: bool,
+
+    /// Whether to remove X2 attribute commands (`%TF`, `%TA`, `%TO`, `%TD`)
+    strip_attributes: bool,
+
+    /// Whether to remove comments identifying the source tool, author or date
+    scrub_comments: bool,
+
+    /// Seed for the hash aperture random source; also fixes the header time
+    seed: Option<u64>,
 }
 
 impl Default for GerberProcessor {
@@ -28,6 +50,10 @@ impl Default for GerberProcessor {
             ignore_hash: false,
             is_imported_pcb_doc: false,
             max_hash_file_size: 30_000_000, // 30MB
+            deterministic: false,
+            strip_attributes: false,
+            scrub_comments: false,
+            seed: None,
         }
     }
 }
@@ -56,43 +82,218 @@ impl GerberProcessor {
         self
     }
 
+    /// Configure reproducible output for identical inputs
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Seed hash aperture generation and fix the header time, for reproducible output
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Configure whether X2 attribute commands are removed
+    pub fn with_strip_attributes(mut self, strip: bool) -> Self {
+        self.strip_attributes = strip;
+        self
+    }
+
+    /// Configure whether source tool, author and date comments are removed
+    pub fn with_scrub_comments(mut self, scrub: bool) -> Self {
+        self.scrub_comments = scrub;
+        self
+    }
+
     /// Process a Gerber file content with all necessary transformations
     pub fn process_gerber_content(
         &self,
         content: String,
         needs_g54_aperture_prefix: bool,
     ) -> Result<String> {
+        self.process_gerber_content_with_hash(content, needs_g54_aperture_prefix)
+            .map(|(processed, _)| processed)
+    }
+
+    /// Process Gerber content and also return the inserted hash aperture, if any
+    pub fn process_gerber_content_with_hash(
+        &self,
+        content: String,
+        needs_g54_aperture_prefix: bool,
+    ) -> Result<(String, Option<HashAperture>)> {
+        self.process_gerber_content_with_progress(content, needs_g54_aperture_prefix, None)
+    }
+
+    /// Process Gerber content, advancing `progress` by one step per stage
+    ///
+    /// The bar's length is set to the number of stages and its message names
+    /// the stage being run, so a large file does not look stuck.
+    pub fn process_gerber_content_with_progress(
+        &self,
+        content: String,
+        needs_g54_aperture_prefix: bool,
+        progress: Option<&ProgressBar>,
+    ) -> Result<(String, Option<HashAperture>)> {
         info!("Processing Gerber files...");
 
-        let mut processed_content = content;
+        let stage = |position: u64, message: &str| {
+            if let Some(progress) = progress {
+                progress.set_length(GERBER_STAGES);
+                progress.set_position(position);
+                progress.set_message(message.to_string());
+            }
+        };
+
+        stage(0, "Normalizing");
+        // The header must be the first thing in the file, ahead of any BOM
+        let content = strip_bom(content);
+        // Every later pass splits on '\n', so unify CRLF and lone CR first
+        let mut processed_content = normalize_line_endings(content);
+
+        // EasyEDA import rejects the deprecated image name/polarity statements
+        processed_content = self.strip_deprecated_image_statements(processed_content)?;
+
+        if self.strip_attributes {
+            processed_content = self.strip_attribute_commands(processed_content)?;
+        }
+
+        if self.scrub_comments {
+            processed_content = self.scrub_metadata_comments(processed_content)?;
+        }
 
         // Add header information
         processed_content = self.add_gerber_header(processed_content);
 
+        stage(1, "Adding G54 prefixes");
         // Apply aperture prefix normalization when required
         if needs_g54_aperture_prefix {
             debug!("Ensuring G54 aperture prefixes are present");
             processed_content = self.add_missing_g54_aperture_prefix(processed_content)?;
         }
 
+        stage(2, "Renumbering apertures");
         // Add hash aperture for file fingerprinting
-        processed_content = self.add_hash_aperture_to_gerber(processed_content)?;
+        let (processed_content, hash_aperture) =
+            self.add_hash_aperture_to_gerber(processed_content)?;
+
+        stage(GERBER_STAGES, "Done");
 
         info!("Gerber file processing completed");
-        Ok(processed_content)
+        Ok((processed_content, hash_aperture))
     }
 
     /// Add standard header to Gerber file
     fn add_gerber_header(&self, content: String) -> String {
-        let now = chrono::Local::now();
+        // A seed promises repeatable output, which a wall-clock time would break
+        let timestamp = if self.deterministic || self.seed.is_some() {
+            // Matches the fixed modification time used for deterministic archives
+            "1980-01-01 00:00:00".to_string()
+        } else {
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+        };
         let header = format!(
             "G04 EasyEDA Pro v2.2.42.2, {}*\nG04 Gerber Generator version 0.3*\n",
-            now.format("%Y-%m-%d %H:%M:%S")
+            timestamp
         );
 
-        // Normalize line endings and add header
-        let normalized = content.replace("\r\n", "\n");
-        format!("{}{}", header, normalized)
+        format!("{}{}", header, content)
+    }
+
+    /// Remove deprecated `%IN` (image name) and `%IPPOS` (image polarity) statements
+    ///
+    /// `%IPPOS*%` is the default and carries no geometry. `%IPNEG*%` inverts
+    /// the whole image, which cannot be expressed without the statement, so it
+    /// is rejected instead of silently turning the layer positive.
+    fn strip_deprecated_image_statements(&self, content: String) -> Result<String> {
+        if Regex::new(r"%IPNEG\*%")
+            .context("Failed to compile image polarity regex")?
+            .is_match(&content)
+        {
+            return Err(TransJlcError::InvalidGerberFormat {
+                reason: "negative image polarity (%IPNEG) is not supported; export the layer with positive polarity".to_string(),
+            }
+            .into());
+        }
+
+        let statement_regex = Regex::new(r"%(IN[^%]*|IPPOS)\*%")
+            .context("Failed to compile image statement regex")?;
+
+        if !statement_regex.is_match(&content) {
+            return Ok(content);
+        }
+
+        let mut result_lines = Vec::new();
+        for line in content.split('\n') {
+            if !statement_regex.is_match(line) {
+                result_lines.push(line.to_string());
+                continue;
+            }
+
+            for statement in statement_regex.find_iter(line) {
+                debug!("Removing deprecated statement {}", statement.as_str());
+            }
+
+            let stripped = statement_regex.replace_all(line, "");
+            if !stripped.trim().is_empty() {
+                result_lines.push(stripped.into_owned());
+            }
+        }
+
+        Ok(result_lines.join("\n"))
+    }
+
+    /// Remove X2 attribute commands (`%TF`, `%TA`, `%TO`, `%TD`)
+    ///
+    /// Whole extended-code blocks are matched up to their closing `*%`, so
+    /// values containing commas or spanning lines are removed completely. Lines
+    /// left empty by the removal are dropped. Attribute comments (`G04 #@! TF`)
+    /// are ordinary comments and stay.
+    fn strip_attribute_commands(&self, content: String) -> Result<String> {
+        let line_regex = Regex::new(r"(?m)^[ \t]*%T[FAOD][^%]*\*%[ \t]*(\n|$)")
+            .context("Failed to compile attribute line regex")?;
+        let inline_regex =
+            Regex::new(r"%T[FAOD][^%]*\*%").context("Failed to compile attribute regex")?;
+
+        let removed =
+            line_regex.find_iter(&content).count() + inline_regex.find_iter(&content).count();
+        if removed == 0 {
+            return Ok(content);
+        }
+
+        let stripped = line_regex.replace_all(&content, "");
+        let stripped = inline_regex.replace_all(&stripped, "");
+        debug!("Removed {} X2 attribute commands", removed);
+        Ok(stripped.into_owned())
+    }
+
+    /// Remove comments and file attributes naming the source tool, author or date
+    ///
+    /// Only whole-line `G04` comments are considered. Comments that describe
+    /// the image (`TF.FileFunction`, aperture functions, aperture macro
+    /// parameters, section markers) are kept.
+    fn scrub_metadata_comments(&self, content: String) -> Result<String> {
+        let attribute_regex =
+            Regex::new(r"^\s*(G04 #@! |%)TF\.(GenerationSoftware|CreationDate|ProjectId)\b")
+                .context("Failed to compile metadata attribute regex")?;
+        let comment_regex = Regex::new(
+            r"(?i)^\s*G04\b(.*\b(created|generated|generator|exported|author|date|version|kicad|pcbnew|eagle|altium|protel|easyeda|orcad|allegro|cadence|mentor|diptrace|fritzing)\b|.*\d{4}[-/.]\d{1,2}[-/.]\d{1,2})",
+        )
+        .context("Failed to compile metadata comment regex")?;
+
+        let mut removed = 0;
+        let result_lines: Vec<&str> = content
+            .split('\n')
+            .filter(|line| {
+                let is_metadata = attribute_regex.is_match(line)
+                    || (comment_regex.is_match(line) && !line.contains("#@!"));
+                removed += usize::from(is_metadata);
+                !is_metadata
+            })
+            .collect();
+
+        debug!("Removed {} metadata comments", removed);
+        Ok(result_lines.join("\n"))
     }
 
     /// Convert aperture format from Dx* to G54Dx* when missing
@@ -147,25 +348,29 @@ impl GerberProcessor {
     }
 
     /// Add hash aperture to Gerber file for fingerprinting
-    fn add_hash_aperture_to_gerber(&self, content: String) -> Result<String> {
+    fn add_hash_aperture_to_gerber(
+        &self,
+        content: String,
+    ) -> Result<(String, Option<HashAperture>)> {
         if self.ignore_hash || content.len() > self.max_hash_file_size {
             if content.len() > self.max_hash_file_size {
                 warn!(
-                    "File too large for hash processing ({} bytes), skipping",
-                    content.len()
+                    "File too large for hash processing ({} bytes, limit {} bytes), skipping",
+                    content.len(),
+                    self.max_hash_file_size
                 );
             }
-            return Ok(content);
+            return Ok((content, None));
         }
 
         info!("Adding hash aperture to Gerber file");
 
         let aperture_info = self.analyze_apertures(&content)?;
         let hash_aperture = self.generate_hash_aperture(&content, &aperture_info)?;
-        let result = self.insert_hash_aperture(content, hash_aperture, &aperture_info)?;
+        let result = self.insert_hash_aperture(content, &hash_aperture, &aperture_info)?;
 
         debug!("Hash aperture added successfully");
-        Ok(result)
+        Ok((result, Some(hash_aperture)))
     }
 
     /// Analyze existing apertures in the Gerber file
@@ -179,7 +384,7 @@ impl GerberProcessor {
         let mut aperture_definitions = Vec::new();
         let mut aperture_numbers = Vec::new();
         let mut found_aperture = false;
-        let number_max = 9999u32;
+        let number_max = MAX_APERTURE_NUMBER;
 
         // Scan for aperture definitions (limit to first 200 lines or until non-aperture content)
         for (index, line) in lines.iter().enumerate() {
@@ -215,7 +420,7 @@ impl GerberProcessor {
         content: &str,
         aperture_info: &ApertureInfo,
     ) -> Result<HashAperture> {
-        let mut rng = rand::thread_rng();
+        let mut rng = self.hash_rng(content);
 
         // Select insertion position
         let selection_index = std::cmp::min(
@@ -289,15 +494,32 @@ impl GerberProcessor {
         Ok(HashAperture {
             definition: aperture_definition,
             target_number,
+            size: final_size,
             hash: hash_hex,
         })
     }
 
+    /// Random source for hash aperture generation
+    ///
+    /// In deterministic mode, or with an explicit seed, the generator is
+    /// seeded from the content itself (mixed with the seed), so identical
+    /// inputs produce identical hash apertures.
+    fn hash_rng(&self, content: &str) -> StdRng {
+        if !self.deterministic && self.seed.is_none() {
+            return StdRng::from_entropy();
+        }
+
+        let digest = Md5::digest(content.as_bytes());
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&digest[..8]);
+        StdRng::seed_from_u64(u64::from_le_bytes(seed) ^ self.seed.unwrap_or(0))
+    }
+
     /// Insert hash aperture into Gerber content
     fn insert_hash_aperture(
         &self,
         content: String,
-        hash_aperture: HashAperture,
+        hash_aperture: &HashAperture,
         aperture_info: &ApertureInfo,
     ) -> Result<String> {
         // First, renumber existing apertures to make room
@@ -308,7 +530,34 @@ impl GerberProcessor {
         )?;
 
         // Then insert the hash aperture
-        self.insert_aperture_definition(renumbered_content, hash_aperture)
+        let result = self.insert_aperture_definition(renumbered_content, hash_aperture)?;
+        self.check_unique_apertures(&result)?;
+        Ok(result)
+    }
+
+    /// Make sure renumbering left every aperture number defined only once
+    ///
+    /// Apertures at `max_number` are never shifted, so a file that already
+    /// uses the numbers around the maximum can end up with two definitions
+    /// of the same D code.
+    fn check_unique_apertures(&self, content: &str) -> Result<()> {
+        let definition_regex =
+            Regex::new(r"(?m)^%ADD(\d+)").context("Failed to compile aperture definition regex")?;
+
+        let mut seen = std::collections::HashSet::new();
+        for caps in definition_regex.captures_iter(content) {
+            let number = caps.get(1).map_or("", |m| m.as_str());
+            if !seen.insert(number) {
+                return Err(TransJlcError::HashApertureError {
+                    reason: format!(
+                        "aperture D{} is defined more than once after renumbering",
+                        number
+                    ),
+                }
+                .into());
+            }
+        }
+        Ok(())
     }
 
     /// Renumber existing apertures to make room for hash aperture
@@ -342,7 +591,7 @@ impl GerberProcessor {
     fn insert_aperture_definition(
         &self,
         content: String,
-        hash_aperture: HashAperture,
+        hash_aperture: &HashAperture,
     ) -> Result<String> {
         // Try to insert before the next aperture definition
         let next_aperture_pattern = format!(r"(?m)^%ADD{}(\D)", hash_aperture.target_number + 1);
@@ -387,6 +636,44 @@ impl GerberProcessor {
     }
 }
 
+/// Layer type declared by the X2 `FileFunction` attribute of a Gerber file
+///
+/// Both the `%TF.FileFunction,...*%` command and the `G04 #@! TF.FileFunction`
+/// comment form written by older KiCad versions are recognised.
+pub fn file_function_layer(content: &str) -> Option<LayerType> {
+    const ATTRIBUTE: &str = "TF.FileFunction,";
+
+    content.lines().find_map(|line| {
+        let line = line.trim();
+        let value = line
+            .strip_prefix('%')
+            .or_else(|| line.strip_prefix("G04 #@! "))?
+            .strip_prefix(ATTRIBUTE)?;
+        let value = value.trim_end_matches('%').trim_end_matches('*');
+        layer_from_file_function(value)
+    })
+}
+
+/// Remove a leading UTF-8 byte order mark
+fn strip_bom(content: String) -> String {
+    match content.strip_prefix('\u{feff}') {
+        Some(rest) => {
+            debug!("Removing UTF-8 byte order mark");
+            rest.to_string()
+        }
+        None => content,
+    }
+}
+
+/// Convert CRLF and lone CR line endings to LF
+fn normalize_line_endings(content: String) -> String {
+    if !content.contains('\r') {
+        return content;
+    }
+
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
 /// Information about existing apertures in a Gerber file
 #[derive(Debug)]
 struct ApertureInfo {
@@ -396,12 +683,26 @@ struct ApertureInfo {
 }
 
 /// Hash-based aperture information
-#[derive(Debug)]
-#[allow(dead_code)]
-struct HashAperture {
-    definition: String,
-    target_number: u32,
-    hash: String,
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashAperture {
+    /// Aperture definition line inserted into the file
+    pub definition: String,
+    /// D-code number assigned to the hash aperture
+    pub target_number: u32,
+    /// Aperture size carrying the hash suffix
+    pub size: String,
+    /// MD5 hash of the content the fingerprint was computed from
+    pub hash: String,
+}
+
+impl fmt::Display for HashAperture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "md5 {} -> D{} size {}",
+            self.hash, self.target_number, self.size
+        )
+    }
 }
 
 #[cfg(test)]
@@ -451,6 +752,267 @@ mod tests {
         assert!(result.contains("G04 Original content*"));
     }
 
+    #[test]
+    fn test_deterministic_output_is_reproducible() {
+        let processor = GerberProcessor::new().with_deterministic(true);
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\n%ADD11C,0.200000*%\nD10*\nX0Y0D02*\nM02*\n";
+
+        let first = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+        let second = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.contains("1980-01-01 00:00:00"));
+    }
+
+    #[test]
+    fn test_seeded_hash_aperture_is_reproducible() {
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\n%ADD11C,0.200000*%\n%ADD12R,1.0X1.0*%\nD10*\nX0Y0D02*\nD11*\nX100Y100D03*\nM02*\n";
+        let process = |seed: u64| {
+            let (processed, hash) = GerberProcessor::new()
+                .with_seed(Some(seed))
+                .process_gerber_content_with_hash(content.to_string(), false)
+                .unwrap();
+            (processed, hash.unwrap())
+        };
+
+        // The header time is fixed too, so the whole file is repeatable
+        let (first, first_hash) = process(42);
+        let (second, second_hash) = process(42);
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        assert_eq!(first_hash, second_hash);
+        assert!(first.starts_with("G04 EasyEDA Pro v2.2.42.2, 1980-01-01 00:00:00*\n"));
+
+        // Another seed still gives a valid, but generally different, aperture
+        let others: Vec<HashAperture> = (0..8).map(|seed| process(seed).1).collect();
+        assert!(others.iter().any(|hash| *hash != first_hash));
+    }
+
+    #[test]
+    fn test_hash_aperture_reports_content_md5() {
+        let processor = GerberProcessor::new().with_deterministic(true);
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\nD10*\nX0Y0D02*\nM02*\n";
+
+        let (processed, hash_aperture) = processor
+            .process_gerber_content_with_hash(content.to_string(), false)
+            .unwrap();
+        let hash_aperture = hash_aperture.expect("hash aperture should be inserted");
+
+        // The fingerprint covers the content after the header is added
+        let pre_hash = format!(
+            "G04 EasyEDA Pro v2.2.42.2, 1980-01-01 00:00:00*\nG04 Gerber Generator version 0.3*\n{}",
+            content
+        );
+        let expected = format!("{:x}", Md5::digest(pre_hash.as_bytes()));
+
+        assert_eq!(hash_aperture.hash, expected);
+        assert!(hash_aperture.to_string().contains(&expected));
+        assert!(processed.contains(&hash_aperture.definition));
+    }
+
+    #[test]
+    fn test_imported_pcb_doc_changes_hash() {
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\nD10*\nX0Y0D02*\nM02*\n";
+        let hash_for = |imported: bool| {
+            GerberProcessor::new()
+                .with_deterministic(true)
+                .with_imported_pcb_doc(imported)
+                .process_gerber_content_with_hash(content.to_string(), false)
+                .unwrap()
+                .1
+                .expect("hash aperture should be inserted")
+                .hash
+        };
+
+        assert_ne!(hash_for(true), hash_for(false));
+    }
+
+    #[test]
+    fn test_cr_only_line_endings() {
+        let processor = GerberProcessor::new().with_deterministic(true);
+        let content = "%FSLAX46Y46*%\r%MOMM*%\r%ADD10C,0.100000*%\r%ADD11C,0.200000*%\rD10*\rX0Y0D02*\rM02*\r";
+
+        let (processed, hash_aperture) = processor
+            .process_gerber_content_with_hash(content.to_string(), false)
+            .unwrap();
+
+        assert!(!processed.contains('\r'));
+        assert!(processed.contains("\n%MOMM*%\n"));
+        // Apertures were found, so the hash aperture reuses one of their definitions
+        let hash_aperture = hash_aperture.expect("hash aperture should be inserted");
+        assert!((10..=12).contains(&hash_aperture.target_number));
+        assert_eq!(
+            processed
+                .lines()
+                .filter(|line| line.starts_with("%ADD"))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_strips_image_name_and_polarity() {
+        let processor = GerberProcessor::new().with_ignore_hash(true);
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%IPPOS*%\n%INTOP_COPPER*%\n%ADD10C,0.100000*%\nD10*\nX0Y0D02*\nX1000000Y0D01*\nM02*\n";
+
+        let processed = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+
+        assert!(!processed.contains("%IP"));
+        assert!(!processed.contains("%IN"));
+        assert!(processed.contains("%MOMM*%\n%ADD10C,0.100000*%"));
+        assert!(processed.contains("X1000000Y0D01*"));
+    }
+
+    #[test]
+    fn test_negative_image_polarity_fails() {
+        let processor = GerberProcessor::new().with_ignore_hash(true);
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%IPNEG*%\n%ADD10C,0.100000*%\nD10*\nX0Y0D02*\nX1000000Y0D01*\nM02*\n";
+
+        let err = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::InvalidGerberFormat { .. })
+        ));
+        assert!(err.to_string().contains("%IPNEG"));
+    }
+
+    #[test]
+    fn test_strips_x2_attributes() {
+        let processor = GerberProcessor::new()
+            .with_ignore_hash(true)
+            .with_strip_attributes(true);
+        let content = "%TF.GenerationSoftware,KiCad,Pcbnew,7.0.1*%\n%TF.FileFunction,Copper,L1,Top*%\n%TF.FilePolarity,Positive*%\n%FSLAX46Y46*%\nG04 #@! TF.SameCoordinates,Original*\n%MOMM*%\n%TA.AperFunction,SMDPad,CuDef*%\n%ADD10R,1.000000X1.000000*%\n%TD*%\n%TO.N,GND*%D10*\nX0Y0D03*\n%TD*%\nM02*\n";
+
+        let processed = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+
+        assert!(!processed.contains("%T"));
+        assert!(processed.contains("G04 #@! TF.SameCoordinates,Original*"));
+        assert!(processed.contains("%FSLAX46Y46*%\nG04 #@!"));
+        assert!(processed.contains("%MOMM*%\n%ADD10R,1.000000X1.000000*%\nD10*\nX0Y0D03*\nM02*"));
+
+        // Attributes are kept unless stripping is requested
+        let kept = GerberProcessor::new()
+            .with_ignore_hash(true)
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+        assert!(kept.contains("%TF.FileFunction,Copper,L1,Top*%"));
+    }
+
+    #[test]
+    fn test_aperture_collision_at_max_number_fails() {
+        let processor = GerberProcessor::new().with_seed(Some(1));
+        // The hash aperture targets D9999, which renumbering never shifts
+        let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\n%ADD9999C,0.200000*%\nG54D10*\nX0Y0D03*\nG54D9999*\nX1000000Y0D03*\nM02*\n";
+
+        let err = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::HashApertureError { .. })
+        ));
+        assert!(err.to_string().contains("D9999 is defined more than once"));
+    }
+
+    #[test]
+    fn test_strips_leading_bom() {
+        let processor = GerberProcessor::new()
+            .with_ignore_hash(true)
+            .with_deterministic(true);
+        let content = "\u{feff}%FSLAX46Y46*%\r\n%MOMM*%\r\nM02*\r\n";
+
+        let processed = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+
+        assert!(!processed.contains('\u{feff}'));
+        assert_eq!(
+            processed,
+            "G04 EasyEDA Pro v2.2.42.2, 1980-01-01 00:00:00*\nG04 Gerber Generator version 0.3*\n%FSLAX46Y46*%\n%MOMM*%\nM02*\n"
+        );
+    }
+
+    #[test]
+    fn test_scrubs_tool_comments() {
+        let processor = GerberProcessor::new()
+            .with_ignore_hash(true)
+            .with_deterministic(true)
+            .with_scrub_comments(true);
+        let content = "G04 #@! TF.GenerationSoftware,KiCad,Pcbnew,7.0.1*\nG04 #@! TF.CreationDate,2023-05-01T10:00:00+02:00*\nG04 #@! TF.ProjectId,board,626f617264,rev?*\nG04 #@! TF.FileFunction,Copper,L1,Top*\n%TF.GenerationSoftware,KiCad,Pcbnew,7.0.1*%\nG04 Created by KiCad (PCBNEW 7.0.1) date 2023-05-01 10:00:00*\n%FSLAX46Y46*%\nG04 Gerber Fmt 4.6, Leading zero omitted, Abs format (unit mm)*\n%MOMM*%\nG04 APERTURE LIST*\n%ADD10C,0.100000*%\nG04 APERTURE END LIST*\nD10*\nX0Y0D03*\nM02*\n";
+
+        let processed = processor
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+
+        for metadata in [
+            "GenerationSoftware",
+            "CreationDate",
+            "ProjectId",
+            "KiCad",
+            "2023",
+        ] {
+            assert!(!processed.contains(metadata), "{} kept", metadata);
+        }
+        for kept in [
+            "G04 #@! TF.FileFunction,Copper,L1,Top*",
+            "G04 Gerber Fmt 4.6, Leading zero omitted, Abs format (unit mm)*",
+            "G04 APERTURE LIST*\n%ADD10C,0.100000*%\nG04 APERTURE END LIST*",
+        ] {
+            assert!(processed.contains(kept), "{} removed", kept);
+        }
+        assert!(processed.starts_with("G04 EasyEDA Pro v2.2.42.2, 1980-01-01 00:00:00*\n"));
+
+        // Comments stay unless scrubbing is requested
+        let kept = GerberProcessor::new()
+            .with_ignore_hash(true)
+            .process_gerber_content(content.to_string(), false)
+            .unwrap();
+        assert!(kept.contains("G04 Created by KiCad"));
+    }
+
+    #[test]
+    fn test_file_function_layer() {
+        assert_eq!(
+            file_function_layer("%FSLAX46Y46*%\n%TF.FileFunction,Copper,L2,Bot*%\n%MOMM*%\n"),
+            Some(LayerType::BottomCopper)
+        );
+        assert_eq!(
+            file_function_layer("G04 #@! TF.FileFunction,Legend,Top*\n"),
+            Some(LayerType::TopSilkscreen)
+        );
+        assert_eq!(file_function_layer("%TF.FileFunction,Other,User*%\n"), None);
+        assert_eq!(file_function_layer("%FSLAX46Y46*%\nM02*\n"), None);
+    }
+
+    #[test]
+    fn test_progress_reaches_last_stage() {
+        let processor = GerberProcessor::new();
+        let progress = ProgressBar::hidden();
+
+        processor
+            .process_gerber_content_with_progress(
+                "%ADD10C,0.1*%\nD10*\nM02*\n".to_string(),
+                false,
+                Some(&progress),
+            )
+            .unwrap();
+
+        assert_eq!(progress.length(), Some(GERBER_STAGES));
+        assert_eq!(progress.position(), GERBER_STAGES);
+        assert_eq!(progress.message(), "Done");
+    }
+
     #[test]
     fn test_aperture_analysis() {
         let processor = GerberProcessor::new();
@@ -468,12 +1030,13 @@ mod tests {
         let processor = GerberProcessor::new().with_max_hash_file_size(100);
         let large_content = "x".repeat(200); // Exceeds max size
 
-        let result = processor
+        let (result, hash_aperture) = processor
             .add_hash_aperture_to_gerber(large_content.clone())
             .unwrap();
 
         // Should return original content unchanged for large files
         assert_eq!(result, large_content);
+        assert!(hash_aperture.is_none());
     }
 
     #[test]
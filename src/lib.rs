@@ -7,15 +7,25 @@
 
 // Public API modules
 pub mod archive;
+pub mod boards;
 pub mod colorful;
 pub mod config;
 pub mod converter;
+pub mod coordinates;
+pub mod diff;
+pub mod doctor;
+pub mod drill;
 pub mod error;
+pub mod gbrjob;
 pub mod gerber;
+pub mod logging;
+pub mod memory;
 pub mod patterns;
 pub mod progress;
+pub mod report;
 
 // Re-export main types for convenience
 pub use config::{Config, EdaType};
-pub use converter::{ConversionStats, Converter};
+pub use converter::{ConversionEvent, ConversionStats, Converter};
 pub use error::{Result, ResultExt, TransJlcError};
+pub use memory::convert_layers;
@@ -0,0 +1,88 @@
+//! Logging setup for the command line tool
+//!
+//! Console output follows `RUST_LOG` and is off by default. With `--log-file`
+//! a second layer writes the full debug trace to a file as well, independent
+//! of the console filter, so a complete log can be attached to bug reports.
+
+use crate::error::{Result, ResultExt};
+use std::{fs::File, path::Path, sync::Arc};
+use tracing::Subscriber;
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+/// Install the global subscriber, optionally also logging to `log_file`
+pub fn init(log_file: Option<&Path>) -> Result<()> {
+    // RUST_LOG takes precedence over the default of no console output
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("off"));
+    let console = fmt::layer().with_filter(env_filter);
+
+    let file = log_file.map(file_layer).transpose()?;
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(file)
+        .try_init()
+        .map_err(|e| anyhow::anyhow!("Failed to initialize logging: {}", e))
+}
+
+/// Layer writing debug-level logs without colors to a newly created file
+///
+/// Every event is written straight to the file, so nothing is lost when the
+/// process exits early.
+pub fn file_layer<S>(path: &Path) -> Result<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let file = File::create(path).with_path_context("create log", path)?;
+
+    Ok(fmt::layer()
+        .with_writer(Arc::new(file))
+        .with_ansi(false)
+        .with_filter(LevelFilter::DEBUG))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, converter::Converter};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_log_file_captures_conversion() {
+        let input_dir = tempdir().expect("Failed to create input dir");
+        let output_dir = tempdir().expect("Failed to create output dir");
+        for name in [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-Edge_Cuts.gbr",
+        ] {
+            fs::write(input_dir.path().join(name), "G04 test*\nD10*\nM02*\n")
+                .expect("Failed to write input file");
+        }
+        let log_path = output_dir.path().join("transjlc.log");
+
+        let config = Config {
+            eda: "kicad".to_string(),
+            path: input_dir.path().to_path_buf(),
+            output_path: output_dir.path().join("out"),
+            no_progress: true,
+            ..Default::default()
+        };
+        let subscriber = tracing_subscriber::registry().with(file_layer(&log_path).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            Converter::new(config)
+                .run()
+                .expect("Conversion should succeed");
+        });
+
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains("Starting conversion process..."));
+        assert!(log.contains("Matched project-F_Cu.gbr to layer type: TopCopper"));
+        // Debug lines are kept even though no console filter is set
+        assert!(log.contains("DEBUG"));
+        assert!(!log.contains("\u{1b}["), "log file contains ANSI escapes");
+    }
+}
@@ -6,7 +6,16 @@
 #![allow(non_snake_case)]
 
 use tracing::{error, info};
-use TransJLC::{config::Config, converter::Converter, error::Result};
+use TransJLC::{
+    boards,
+    config::{Command, Config},
+    converter::Converter,
+    diff,
+    doctor::DoctorReport,
+    error::Result,
+    patterns,
+    report::{BoundsReport, ConversionReport},
+};
 
 fn main() -> Result<()> {
     // Parse configuration and initialize logging
@@ -15,12 +24,45 @@ fn main() -> Result<()> {
         std::process::exit(1);
     });
 
+    if config.list_layers {
+        print!("{}", patterns::layer_listing());
+        return Ok(());
+    }
+
+    if config.doctor {
+        let report = DoctorReport::run();
+        print!("{}", report);
+        if !report.is_healthy() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Diff { left, right }) = &config.command {
+        return run_diff(left, right);
+    }
+
+    if config.batch {
+        let jobs = boards::batch_jobs(&config).unwrap_or_else(|e| {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        });
+        return run_boards(&config, jobs);
+    }
+
+    if !config.boards.is_empty() {
+        return run_boards(&config, boards::board_jobs(&config));
+    }
+
     info!("Starting conversion process...");
     if config.verbose {
         info!("Configuration: {:?}", config);
     }
 
     // Create and run converter
+    let explain_hash = config.explain_hash;
+    let bounds_report = config.bounds_report;
+    let quiet = config.quiet;
     let mut converter = Converter::new(config);
 
     match converter.run() {
@@ -29,7 +71,30 @@ fn main() -> Result<()> {
             info!("Conversion completed successfully");
             info!("Processed {} files", stats.total_files_processed);
 
-            println!("Conversion completed successfully");
+            if !quiet && !stats.unmatched_files.is_empty() {
+                eprintln!(
+                    "Warning: {} file(s) did not match any layer pattern and were skipped:",
+                    stats.unmatched_files.len()
+                );
+                for name in &stats.unmatched_files {
+                    eprintln!("  {}", name);
+                }
+            }
+
+            if explain_hash {
+                for (layer, hash_aperture) in &stats.hash_apertures {
+                    println!("{}: {}", layer.to_jlc_filename(), hash_aperture);
+                }
+            }
+
+            if bounds_report {
+                print!("{}", BoundsReport::from_stats(&stats));
+            }
+
+            if !quiet {
+                print!("{}", ConversionReport::from_stats(&stats));
+                println!("Conversion completed successfully");
+            }
             Ok(())
         }
         Err(e) => {
@@ -39,3 +104,68 @@ fn main() -> Result<()> {
         }
     }
 }
+
+/// Convert every board concurrently and exit non-zero if any failed
+fn run_boards(config: &Config, jobs: Vec<boards::BoardJob>) -> Result<()> {
+    let threads = config.threads.unwrap_or_else(boards::default_thread_count);
+    let results = boards::convert_boards(jobs, threads);
+
+    let mut failed = 0;
+    for board in &results {
+        match &board.result {
+            Ok(_) if config.quiet => {}
+            Ok(stats) => {
+                println!(
+                    "[{}] Converted {} files into {}",
+                    board.name,
+                    stats.total_files_processed,
+                    board.output_path.display()
+                );
+                print!("{}", ConversionReport::from_stats(stats));
+            }
+            Err(e) => {
+                failed += 1;
+                error!("Board {} failed: {:#}", board.name, e);
+                eprintln!("[{}] Error: {:#}", board.name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} boards failed", failed, results.len());
+        std::process::exit(1);
+    }
+
+    if !config.quiet {
+        let total_files: usize = results
+            .iter()
+            .filter_map(|board| board.result.as_ref().ok())
+            .map(|stats| stats.total_files_processed)
+            .sum();
+        println!(
+            "Converted {} boards ({} files in total)",
+            results.len(),
+            total_files
+        );
+        println!("Conversion completed successfully");
+    }
+    Ok(())
+}
+
+/// Compare two converted packages and exit non-zero when they differ
+fn run_diff(left: &std::path::Path, right: &std::path::Path) -> Result<()> {
+    match diff::diff_packages(left, right) {
+        Ok(report) => {
+            print!("{}", report);
+            if !report.is_identical() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("Diff failed: {:#}", e);
+            eprintln!("Error: {:#}", e);
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! In-memory conversion of layer buffers
+//!
+//! [`convert_layers`] runs the same layer matching and Gerber processing as the
+//! [`Converter`](crate::converter::Converter), but on byte buffers keyed by file
+//! name instead of files on disk. It suits WebAssembly or server use, where
+//! the input arrives as an upload and the result is streamed back.
+
+use crate::{
+    config::EdaType,
+    converter::{decode_input_content, is_drill_layer, is_gerber_layer},
+    drill::DrillProcessor,
+    error::Result,
+    gerber::{file_function_layer, GerberProcessor},
+    patterns::{LayerType, PatternMatcher},
+};
+use anyhow::Context;
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+/// Convert named layer files to JLC layer files without touching the filesystem
+///
+/// Input names are matched with the naming patterns of `eda` (auto-detected for
+/// [`EdaType::Auto`]); files that match no layer are skipped. The result maps
+/// JLC file names to converted content. Custom pattern files are not supported
+/// here since they have to be read from disk.
+pub fn convert_layers(
+    files: HashMap<String, Vec<u8>>,
+    eda: EdaType,
+) -> Result<HashMap<String, Vec<u8>>> {
+    let mut names: Vec<&String> = files.keys().collect();
+    names.sort();
+
+    let patterns = match eda {
+        EdaType::Auto => PatternMatcher::auto_detect_eda(&names)?,
+        eda => PatternMatcher::builtin(&eda)?,
+    };
+    info!(
+        "Converting {} buffers with {} naming patterns",
+        files.len(),
+        patterns.name
+    );
+
+    let mut layers: Vec<(LayerType, &str, String)> = Vec::new();
+    for name in names {
+        let layer = patterns.match_filename(name).or_else(|| {
+            decode_input_content(files[name].clone())
+                .ok()
+                .and_then(|content| file_function_layer(&content))
+        });
+        let Some(layer) = layer else {
+            debug!("No pattern match for buffer: {}", name);
+            continue;
+        };
+        if layer.is_extra() {
+            debug!("Skipping extra layer {}", name);
+            continue;
+        }
+
+        let content = decode_input_content(files[name].clone())
+            .with_context(|| format!("Failed to decode {}", name))?;
+        layers.push((layer, name, content));
+    }
+
+    let gerber_processor = GerberProcessor::new();
+    let mut needs_g54_aperture_prefix = false;
+    for (layer, _, content) in &layers {
+        if is_gerber_layer(layer) && gerber_processor.has_missing_g54_aperture_prefix(content)? {
+            needs_g54_aperture_prefix = true;
+            break;
+        }
+    }
+
+    let drill_processor = DrillProcessor::new();
+    let mut outputs = HashMap::new();
+    for (layer, name, content) in layers {
+        let processed = if is_gerber_layer(&layer) {
+            gerber_processor
+                .process_gerber_content(content, needs_g54_aperture_prefix)
+                .with_context(|| format!("Failed to process {}", name))?
+        } else if is_drill_layer(&layer) {
+            drill_processor
+                .process_drill_content(content)
+                .with_context(|| format!("Failed to process {}", name))?
+        } else {
+            content
+        };
+
+        let output = layer.to_jlc_filename();
+        if outputs
+            .insert(output.clone(), processed.into_bytes())
+            .is_some()
+        {
+            warn!("{} replaces an earlier file converted to {}", name, output);
+        }
+    }
+
+    Ok(outputs)
+}
@@ -3,10 +3,12 @@
 //! This module provides pattern matching capabilities for identifying
 //! and mapping files from different EDA software to JLC format.
 
-use crate::error::{Result, TransJlcError};
+use crate::config::EdaType;
+use crate::error::{Result, ResultExt, TransJlcError};
+use anyhow::{anyhow, bail, Context};
 use regex::Regex;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
 /// Represents a layer type in PCB files
@@ -38,6 +40,15 @@ pub enum LayerType {
     ColorfulBottomSilkscreen,
     ColorfulBoardOutline,
     ColorfulBoardOutlineMark,
+
+    // Electrical test netlist (IPC-D-356)
+    IpcNetlist,
+
+    // Layers JLC does not use, only kept with `--keep-extra`
+    TopFab,
+    BottomFab,
+    Extra(String), // Original filename, kept as-is
+
     Other,
 }
 
@@ -60,6 +71,8 @@ impl LayerType {
             LayerType::TopCopper => "Gerber_TopLayer.GTL".to_string(),
 
             LayerType::BoardOutline => "Gerber_BoardOutlineLayer.GKO".to_string(),
+            // EasyEDA Pro numbers inner layers without padding, from `.G1` up to
+            // `.G32`; this is also Protel's mid-layer scheme, not a plane (`.GP<n>`)
             LayerType::InnerLayer(num) => format!("Gerber_InnerLayer{}.G{}", num, num),
 
             LayerType::ColorfulTopSilkscreen => {
@@ -74,9 +87,116 @@ impl LayerType {
             LayerType::ColorfulBoardOutlineMark => {
                 "Fabrication_ColorfulBoardOutlineMark.FCBM".to_string()
             }
+            LayerType::IpcNetlist => "Netlist_IPC-D-356.ipc".to_string(),
+            LayerType::TopFab => "Fabrication_TopFabLayer.GTF".to_string(),
+            LayerType::BottomFab => "Fabrication_BottomFabLayer.GBF".to_string(),
+            LayerType::Extra(name) => name.clone(),
             LayerType::Other => "Unknown".to_string(),
         }
     }
+
+    /// Whether this layer is only carried through on request (`--keep-extra`)
+    pub fn is_extra(&self) -> bool {
+        matches!(
+            self,
+            LayerType::TopFab | LayerType::BottomFab | LayerType::Extra(_)
+        )
+    }
+
+    /// All output layer types, with inner layers 1-4 as examples
+    pub fn supported_layers() -> Vec<LayerType> {
+        let mut layers = vec![
+            LayerType::TopCopper,
+            LayerType::TopSilkscreen,
+            LayerType::TopSoldermask,
+            LayerType::TopPasteMask,
+            LayerType::BottomCopper,
+            LayerType::BottomSilkscreen,
+            LayerType::BottomSoldermask,
+            LayerType::BottomPasteMask,
+            LayerType::BoardOutline,
+        ];
+        layers.extend((1..=4).map(LayerType::InnerLayer));
+        layers.extend([
+            LayerType::NpthThrough,
+            LayerType::PthThrough,
+            LayerType::PthThroughVia,
+            LayerType::ColorfulTopSilkscreen,
+            LayerType::ColorfulBottomSilkscreen,
+            LayerType::ColorfulBoardOutline,
+            LayerType::ColorfulBoardOutlineMark,
+            LayerType::IpcNetlist,
+            LayerType::TopFab,
+            LayerType::BottomFab,
+        ]);
+        layers
+    }
+
+    /// Parse a layer type name as printed by `--list-layers` (case-insensitive)
+    ///
+    /// `InnerLayer` takes its number from the matched filename and `Extra`
+    /// passes files through under their own name.
+    pub fn from_name(name: &str) -> Option<LayerType> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("InnerLayer") {
+            return Some(LayerType::InnerLayer(0));
+        }
+        if name.eq_ignore_ascii_case("Extra") {
+            return Some(LayerType::Extra(String::new()));
+        }
+
+        Self::supported_layers()
+            .into_iter()
+            .filter(|layer| !matches!(layer, LayerType::InnerLayer(_)))
+            .find(|layer| format!("{:?}", layer).eq_ignore_ascii_case(name))
+    }
+}
+
+/// Format the layer type to JLC filename mapping, one layer per line
+pub fn layer_listing() -> String {
+    LayerType::supported_layers()
+        .iter()
+        .map(|layer| {
+            format!(
+                "{:<28} {}\n",
+                format!("{:?}", layer),
+                layer.to_jlc_filename()
+            )
+        })
+        .collect()
+}
+
+/// Distinct layer types a matcher must recognize before it is considered viable
+///
+/// More flexible than requiring a specific layer, yet enough to tell naming
+/// conventions apart.
+pub const DEFAULT_MIN_LAYER_TYPES: usize = 3;
+
+/// Highest inner layer number accepted before a match is treated as bogus
+pub const DEFAULT_MAX_LAYERS: u32 = 32;
+
+/// Strip a trailing `.gz` extension from a filename, if present
+pub fn strip_gzip_suffix(filename: &str) -> &str {
+    let len = filename.len();
+    if len > 3
+        && filename.is_char_boundary(len - 3)
+        && filename[len - 3..].eq_ignore_ascii_case(".gz")
+    {
+        &filename[..len - 3]
+    } else {
+        filename
+    }
+}
+
+/// Check whether a file is an IPC-D-356 netlist (`.d356` / `.ipc`)
+///
+/// Netlists are named the same way regardless of the EDA tool, so they are
+/// recognized independently of the per-EDA patterns.
+pub fn is_netlist_file(filename: &str) -> bool {
+    Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("d356") || ext.eq_ignore_ascii_case("ipc"))
 }
 
 /// Pattern matcher for a specific EDA software
@@ -103,46 +223,90 @@ impl EdaPatterns {
             .push(pattern);
     }
 
+    /// Append user-provided patterns and validate the resulting matcher
+    pub fn add_patterns(&mut self, patterns: &[(LayerType, String)]) -> Result<()> {
+        for (layer_type, pattern) in patterns {
+            debug!(
+                "Adding {:?} pattern '{}' to {}",
+                layer_type, pattern, self.name
+            );
+            self.add_pattern(layer_type.clone(), pattern.clone());
+        }
+        self.validate()
+    }
+
+    /// Compile every stored pattern, reporting all invalid ones at once
+    ///
+    /// `match_filename` skips patterns that fail to compile, so a typo would
+    /// otherwise just match nothing.
+    pub fn validate(&self) -> Result<()> {
+        let mut invalid: Vec<String> = self
+            .patterns
+            .iter()
+            .flat_map(|(layer_type, patterns)| {
+                patterns.iter().filter_map(move |pattern| {
+                    Regex::new(pattern).err().map(|e| {
+                        let reason = e.to_string();
+                        let reason = reason.lines().last().unwrap_or_default().trim().to_string();
+                        format!("{:?} pattern '{}' ({})", layer_type, pattern, reason)
+                    })
+                })
+            })
+            .collect();
+
+        if invalid.is_empty() {
+            return Ok(());
+        }
+
+        invalid.sort();
+        Err(TransJlcError::InvalidPatterns {
+            matcher: self.name.clone(),
+            details: invalid.join("; "),
+        }
+        .into())
+    }
+
     /// Match a filename against all patterns and return the layer type
     /// Special handling for drill files to ensure NPTH takes precedence over PTH
     pub fn match_filename(&self, filename: &str) -> Option<LayerType> {
-        // Special handling for drill files: check NPTH first, then PTH
-        if filename.to_lowercase().ends_with(".drl") {
-            // Check NPTH patterns first
-            if let Some(npth_patterns) = self.patterns.get(&LayerType::NpthThrough) {
-                for pattern in npth_patterns {
-                    if let Ok(regex) = Regex::new(pattern) {
-                        if regex.is_match(filename) {
-                            debug!("Matched '{}' to NPTH using pattern '{}'", filename, pattern);
-                            return Some(LayerType::NpthThrough);
-                        }
+        // Gzipped layers (e.g. `project-F_Cu.gbr.gz`) match by their inner name
+        let filename = strip_gzip_suffix(filename);
+
+        // Special handling for drill (and NC route) files: check NPTH first, then PTH.
+        // Not gated on `.drl`/`.rou` since Proteus also writes drills as `.txt`
+        if let Some(npth_patterns) = self.patterns.get(&LayerType::NpthThrough) {
+            for pattern in npth_patterns {
+                if let Ok(regex) = Regex::new(pattern) {
+                    if regex.is_match(filename) {
+                        debug!("Matched '{}' to NPTH using pattern '{}'", filename, pattern);
+                        return Some(LayerType::NpthThrough);
                     }
                 }
             }
+        }
 
-            // Then check PTH patterns
-            if let Some(pth_patterns) = self.patterns.get(&LayerType::PthThrough) {
-                for pattern in pth_patterns {
-                    if let Ok(regex) = Regex::new(pattern) {
-                        if regex.is_match(filename) {
-                            debug!("Matched '{}' to PTH using pattern '{}'", filename, pattern);
-                            return Some(LayerType::PthThrough);
-                        }
+        // Then check PTH patterns
+        if let Some(pth_patterns) = self.patterns.get(&LayerType::PthThrough) {
+            for pattern in pth_patterns {
+                if let Ok(regex) = Regex::new(pattern) {
+                    if regex.is_match(filename) {
+                        debug!("Matched '{}' to PTH using pattern '{}'", filename, pattern);
+                        return Some(LayerType::PthThrough);
                     }
                 }
             }
+        }
 
-            // Check PTH via patterns
-            if let Some(pth_via_patterns) = self.patterns.get(&LayerType::PthThroughVia) {
-                for pattern in pth_via_patterns {
-                    if let Ok(regex) = Regex::new(pattern) {
-                        if regex.is_match(filename) {
-                            debug!(
-                                "Matched '{}' to PTH Via using pattern '{}'",
-                                filename, pattern
-                            );
-                            return Some(LayerType::PthThroughVia);
-                        }
+        // Check PTH via patterns
+        if let Some(pth_via_patterns) = self.patterns.get(&LayerType::PthThroughVia) {
+            for pattern in pth_via_patterns {
+                if let Ok(regex) = Regex::new(pattern) {
+                    if regex.is_match(filename) {
+                        debug!(
+                            "Matched '{}' to PTH Via using pattern '{}'",
+                            filename, pattern
+                        );
+                        return Some(LayerType::PthThroughVia);
                     }
                 }
             }
@@ -171,6 +335,11 @@ impl EdaPatterns {
                             return self.extract_inner_layer_number(filename, &regex);
                         }
 
+                        // Passthrough layers keep their original name
+                        if matches!(layer_type, LayerType::Extra(_)) {
+                            return Some(LayerType::Extra(filename.to_string()));
+                        }
+
                         return Some(layer_type.clone());
                     }
                 } else {
@@ -209,21 +378,24 @@ impl EdaPatterns {
         None
     }
 
-    /// Check if this pattern set can handle the given files
-    pub fn can_handle_files(&self, filenames: &[String]) -> bool {
+    /// Count how many of the given files this pattern set recognizes
+    pub fn count_matches(&self, filenames: &[String]) -> usize {
+        filenames
+            .iter()
+            .filter(|filename| self.match_filename(filename).is_some())
+            .count()
+    }
+
+    /// Count how many different layer types this pattern set recognizes
+    pub fn matched_layer_types(&self, filenames: &[String]) -> usize {
         let mut matched_types = std::collections::HashSet::new();
 
-        // Count how many different layer types we can match
         for filename in filenames {
             if let Some(layer_type) = self.match_filename(filename) {
                 matched_types.insert(std::mem::discriminant(&layer_type));
             }
         }
 
-        // We need at least 3 different layer types to consider it a viable match
-        // This is more flexible than requiring a specific layer
-        let min_layer_types = 3;
-
         debug!(
             "Pattern '{}' matched {} different layer types from {} files",
             self.name,
@@ -231,7 +403,21 @@ impl EdaPatterns {
             filenames.len()
         );
 
-        matched_types.len() >= min_layer_types
+        matched_types.len()
+    }
+
+    /// Check if this pattern set can handle the given files
+    pub fn can_handle_files(&self, filenames: &[String]) -> bool {
+        self.can_handle_files_with_threshold(filenames, DEFAULT_MIN_LAYER_TYPES)
+    }
+
+    /// Check if this pattern set recognizes at least `min_layer_types` layer types
+    pub fn can_handle_files_with_threshold(
+        &self,
+        filenames: &[String],
+        min_layer_types: usize,
+    ) -> bool {
+        self.matched_layer_types(filenames) >= min_layer_types
     }
 }
 
@@ -276,6 +462,14 @@ impl PatternMatcher {
         // Board outline
         patterns.add_pattern(LayerType::BoardOutline, r"-Edge_Cuts\.gbr$".to_string());
 
+        // Fabrication and courtyard layers (only kept with --keep-extra)
+        patterns.add_pattern(LayerType::TopFab, r"-F_Fab\.gbr$".to_string());
+        patterns.add_pattern(LayerType::BottomFab, r"-B_Fab\.gbr$".to_string());
+        patterns.add_pattern(
+            LayerType::Extra(String::new()),
+            r"-[FB]_Courtyard\.gbr$".to_string(),
+        );
+
         patterns
     }
 
@@ -307,7 +501,6 @@ impl PatternMatcher {
 
         // Drill files - more patterns
         patterns.add_pattern(LayerType::PthThrough, r"(?i)\.drl$".to_string());
-        patterns.add_pattern(LayerType::PthThrough, r"(?i)\.txt$".to_string()); // Drill file as txt
         patterns.add_pattern(LayerType::NpthThrough, r"(?i)npth\.drl$".to_string());
         patterns.add_pattern(LayerType::NpthThrough, r"(?i)-npth\.drl$".to_string());
 
@@ -380,8 +573,338 @@ impl PatternMatcher {
         patterns
     }
 
+    /// Create patterns for EasyEDA Standard exports
+    pub fn create_easyeda_std_patterns() -> EdaPatterns {
+        let mut patterns = EdaPatterns::new("EasyEDA Std".to_string());
+
+        // Drill files use Gerber_Drill_* names instead of Drill_*_Through
+        patterns.add_pattern(
+            LayerType::NpthThrough,
+            r"(?i)^Gerber_Drill_NPTH\.DRL$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::PthThrough,
+            r"(?i)^Gerber_Drill_PTH\.DRL$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::BottomSilkscreen,
+            r"(?i)^Gerber_BottomSilk(?:screen)?Layer\.GBO$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSoldermask,
+            r"(?i)^Gerber_BottomSolderMaskLayer\.GBS$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomPasteMask,
+            r"(?i)^Gerber_BottomPasteMaskLayer\.GBP$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomCopper,
+            r"(?i)^Gerber_BottomLayer\.GBL$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::TopSilkscreen,
+            r"(?i)^Gerber_TopSilk(?:screen)?Layer\.GTO$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopSoldermask,
+            r"(?i)^Gerber_TopSolderMaskLayer\.GTS$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopPasteMask,
+            r"(?i)^Gerber_TopPasteMaskLayer\.GTP$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopCopper,
+            r"(?i)^Gerber_TopLayer\.GTL$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::BoardOutline,
+            r"(?i)^Gerber_BoardOutline(?:Layer)?\.GKO$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::InnerLayer(0),
+            r"(?i)^Gerber_Inner(?:Layer)?(\d+)\.G(\d+)$".to_string(),
+        );
+
+        patterns
+    }
+
+    /// Create patterns for PADS CAM output (`.pho` photoplot files)
+    ///
+    /// Layer names may carry a project prefix separated by `-` or `.`, so that
+    /// e.g. `board-sm_top.pho` is never mistaken for top copper.
+    pub fn create_pads_patterns() -> EdaPatterns {
+        let mut patterns = EdaPatterns::new("PADS".to_string());
+
+        patterns.add_pattern(
+            LayerType::NpthThrough,
+            r"(?i)(?:npth|non_?plated).*\.drl$".to_string(),
+        );
+        patterns.add_pattern(LayerType::PthThrough, r"(?i)\.drl$".to_string());
+
+        patterns.add_pattern(
+            LayerType::TopCopper,
+            r"(?i)^(?:.+[-.])?(?:top|art0*1|routing_top)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomCopper,
+            r"(?i)^(?:.+[-.])?(?:bot|bottom|routing_bottom)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::InnerLayer(0),
+            r"(?i)^(?:.+[-.])?(?:inner|layer)_?(\d+)\.pho$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::TopSoldermask,
+            r"(?i)^(?:.+[-.])?(?:smt|sm_top|solder_?mask_top)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSoldermask,
+            r"(?i)^(?:.+[-.])?(?:smb|sm_bot(?:tom)?|solder_?mask_bot(?:tom)?)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopPasteMask,
+            r"(?i)^(?:.+[-.])?(?:pmt|pm_top|paste_?mask_top)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomPasteMask,
+            r"(?i)^(?:.+[-.])?(?:pmb|pm_bot(?:tom)?|paste_?mask_bot(?:tom)?)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopSilkscreen,
+            r"(?i)^(?:.+[-.])?(?:sst|ss_top|silk(?:screen)?_top)\.pho$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSilkscreen,
+            r"(?i)^(?:.+[-.])?(?:ssb|ss_bot(?:tom)?|silk(?:screen)?_bot(?:tom)?)\.pho$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::BoardOutline,
+            r"(?i)^(?:.+[-.])?(?:outline|board_?outline|board)\.pho$".to_string(),
+        );
+
+        patterns
+    }
+
+    /// Create patterns for OrCAD/Allegro artwork output (`.art` films)
+    pub fn create_allegro_patterns() -> EdaPatterns {
+        let mut patterns = EdaPatterns::new("Allegro".to_string());
+
+        // NC route files carry routed slots and cutouts, which are non-plated
+        patterns.add_pattern(LayerType::NpthThrough, r"(?i)\.rou$".to_string());
+        patterns.add_pattern(
+            LayerType::NpthThrough,
+            r"(?i)(?:npth|non_?plated).*\.drl$".to_string(),
+        );
+        patterns.add_pattern(LayerType::PthThrough, r"(?i)\.drl$".to_string());
+
+        patterns.add_pattern(
+            LayerType::TopCopper,
+            r"(?i)^(?:.+[-.])?(?:top|etch_top)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomCopper,
+            r"(?i)^(?:.+[-.])?(?:bot|bottom|etch_bottom)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::InnerLayer(0),
+            r"(?i)^(?:.+[-.])?(?:inner|layer|l)_?(\d+)\.art$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::TopSoldermask,
+            r"(?i)^(?:.+[-.])?(?:smt|soldermask_top|mask_top)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSoldermask,
+            r"(?i)^(?:.+[-.])?(?:smb|soldermask_bot(?:tom)?|mask_bot(?:tom)?)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopPasteMask,
+            r"(?i)^(?:.+[-.])?(?:pmt|pastemask_top|paste_top)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomPasteMask,
+            r"(?i)^(?:.+[-.])?(?:pmb|pastemask_bot(?:tom)?|paste_bot(?:tom)?)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopSilkscreen,
+            r"(?i)^(?:.+[-.])?(?:sst|silkscreen_top|silk_top)\.art$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSilkscreen,
+            r"(?i)^(?:.+[-.])?(?:ssb|silkscreen_bot(?:tom)?|silk_bot(?:tom)?)\.art$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::BoardOutline,
+            r"(?i)^(?:.+[-.])?(?:outline|board_?outline|dim(?:ension)?)\.art$".to_string(),
+        );
+
+        patterns
+    }
+
+    /// Create patterns for DipTrace Gerber output, which encodes layers in the extension
+    pub fn create_diptrace_patterns() -> EdaPatterns {
+        let mut patterns = EdaPatterns::new("DipTrace".to_string());
+
+        patterns.add_pattern(
+            LayerType::NpthThrough,
+            r"(?i)(?:npth|non[ _-]?plated).*\.drl$".to_string(),
+        );
+        patterns.add_pattern(LayerType::PthThrough, r"(?i)\.drl$".to_string());
+
+        patterns.add_pattern(LayerType::TopCopper, r"(?i)\.top$".to_string());
+        patterns.add_pattern(LayerType::BottomCopper, r"(?i)\.bot$".to_string());
+        patterns.add_pattern(
+            LayerType::InnerLayer(0),
+            r"(?i)\.in(?:ner)?(\d+)$".to_string(),
+        );
+
+        patterns.add_pattern(LayerType::TopSoldermask, r"(?i)\.smt$".to_string());
+        patterns.add_pattern(LayerType::BottomSoldermask, r"(?i)\.smb$".to_string());
+        patterns.add_pattern(LayerType::TopPasteMask, r"(?i)\.spt$".to_string());
+        patterns.add_pattern(LayerType::BottomPasteMask, r"(?i)\.spb$".to_string());
+        patterns.add_pattern(LayerType::TopSilkscreen, r"(?i)\.slk$".to_string());
+        patterns.add_pattern(LayerType::BottomSilkscreen, r"(?i)\.bsk$".to_string());
+
+        patterns.add_pattern(LayerType::BoardOutline, r"(?i)\.brd$".to_string());
+
+        patterns
+    }
+
+    /// Create patterns for Proteus CADCAM output
+    ///
+    /// Proteus spells layer names out with spaces, e.g.
+    /// `Board - CADCAM Top Copper.GBR`, so names are matched on whitespace
+    /// boundaries rather than on `-` separators.
+    pub fn create_proteus_patterns() -> EdaPatterns {
+        let mut patterns = EdaPatterns::new("Proteus".to_string());
+
+        patterns.add_pattern(
+            LayerType::NpthThrough,
+            r"(?i)(?:^|\s)(?:npth|non[\s_-]?plated)[^.]*\.(?:drl|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::PthThrough,
+            r"(?i)(?:^|\s)(?:pth\s+)?drill\.(?:drl|txt)$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::TopCopper,
+            r"(?i)(?:^|\s)top\s+copper\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomCopper,
+            r"(?i)(?:^|\s)bottom\s+copper\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::InnerLayer(0),
+            r"(?i)(?:^|\s)inner\s*(\d+)\.(?:gbr|txt)$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::TopSoldermask,
+            r"(?i)(?:^|\s)top\s+solder\s+(?:resist|mask)\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSoldermask,
+            r"(?i)(?:^|\s)bottom\s+solder\s+(?:resist|mask)\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopPasteMask,
+            r"(?i)(?:^|\s)top\s+(?:smt\s+)?paste(?:\s+mask)?\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomPasteMask,
+            r"(?i)(?:^|\s)bottom\s+(?:smt\s+)?paste(?:\s+mask)?\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::TopSilkscreen,
+            r"(?i)(?:^|\s)top\s+silk(?:\s*screen)?\.(?:gbr|txt)$".to_string(),
+        );
+        patterns.add_pattern(
+            LayerType::BottomSilkscreen,
+            r"(?i)(?:^|\s)bottom\s+silk(?:\s*screen)?\.(?:gbr|txt)$".to_string(),
+        );
+
+        patterns.add_pattern(
+            LayerType::BoardOutline,
+            r"(?i)(?:^|\s)board\s+(?:outline|edge)\.(?:gbr|txt)$".to_string(),
+        );
+
+        patterns
+    }
+
+    /// Naming patterns of a built-in EDA type
+    ///
+    /// Auto-detection and custom matchers need more than the type to build their
+    /// patterns and are rejected.
+    pub fn builtin(eda: &EdaType) -> Result<EdaPatterns> {
+        let patterns = match eda {
+            EdaType::KiCad => Self::create_kicad_patterns(),
+            EdaType::Protel => Self::create_protel_patterns(),
+            EdaType::Jlc => Self::create_jlc_patterns(),
+            EdaType::EasyEdaStd => Self::create_easyeda_std_patterns(),
+            EdaType::Pads => Self::create_pads_patterns(),
+            EdaType::Allegro => Self::create_allegro_patterns(),
+            EdaType::DipTrace => Self::create_diptrace_patterns(),
+            EdaType::Proteus => Self::create_proteus_patterns(),
+            EdaType::Auto | EdaType::Custom(_) => {
+                return Err(TransJlcError::UnsupportedEda {
+                    format: eda.as_str().to_string(),
+                }
+                .into())
+            }
+        };
+        Ok(patterns)
+    }
+
     /// Auto-detect the EDA type from a list of files
     pub fn auto_detect_eda<P: AsRef<Path>>(files: &[P]) -> Result<EdaPatterns> {
+        Self::auto_detect_eda_with_threshold(files, DEFAULT_MIN_LAYER_TYPES)
+    }
+
+    /// Auto-detect the EDA type, picking the matcher with the best coverage
+    ///
+    /// Matchers recognizing fewer than `min_layer_types` distinct layer types are
+    /// discarded; among the rest the one covering the most layer types (then the
+    /// most files) wins.
+    pub fn auto_detect_eda_with_threshold<P: AsRef<Path>>(
+        files: &[P],
+        min_layer_types: usize,
+    ) -> Result<EdaPatterns> {
+        Self::auto_detect_eda_among(files, min_layer_types, Self::detection_candidates())
+    }
+
+    /// The built-in matchers considered by auto-detection, in tie-breaking order
+    pub fn detection_candidates() -> Vec<EdaPatterns> {
+        // Most specific naming conventions first, so they win coverage ties
+        // against generic extension-based matchers such as Protel
+        vec![
+            Self::create_jlc_patterns(),
+            Self::create_easyeda_std_patterns(),
+            Self::create_kicad_patterns(),
+            Self::create_pads_patterns(),
+            Self::create_allegro_patterns(),
+            Self::create_diptrace_patterns(),
+            Self::create_proteus_patterns(),
+            Self::create_protel_patterns(),
+        ]
+    }
+
+    /// Auto-detect among the given matchers; earlier candidates win ties
+    pub fn auto_detect_eda_among<P: AsRef<Path>>(
+        files: &[P],
+        min_layer_types: usize,
+        candidates: Vec<EdaPatterns>,
+    ) -> Result<EdaPatterns> {
         let filenames: Vec<String> = files
             .iter()
             .filter_map(|p| {
@@ -400,47 +923,130 @@ impl PatternMatcher {
             debug!("File {}: {}", i + 1, filename);
         }
 
-        let patterns_to_test = vec![
-            Self::create_kicad_patterns(),
-            Self::create_protel_patterns(),
-            Self::create_jlc_patterns(),
-        ];
-
-        for pattern in patterns_to_test {
-            info!("Testing pattern matcher: {}", pattern.name);
-
-            // Check individual file matches for debugging
-            let mut matches = 0;
-            for filename in &filenames {
-                if let Some(layer_type) = pattern.match_filename(filename) {
-                    debug!(
-                        "Pattern '{}' matched '{}' -> {:?}",
-                        pattern.name, filename, layer_type
-                    );
-                    matches += 1;
-                }
-            }
-            debug!("Pattern '{}' matched {} files", pattern.name, matches);
+        let mut best: Option<((usize, usize), EdaPatterns)> = None;
+        for pattern in candidates {
+            let layer_types = pattern.matched_layer_types(&filenames);
+            let matches = pattern.count_matches(&filenames);
+            debug!(
+                "Pattern '{}' matched {} files covering {} layer types",
+                pattern.name, matches, layer_types
+            );
 
-            if pattern.can_handle_files(&filenames) {
-                info!("Detected pattern: {}", &pattern.name);
-                return Ok(pattern);
-            } else {
+            if layer_types < min_layer_types {
                 debug!(
-                    "Pattern '{}' cannot handle files (missing board outline)",
-                    pattern.name
+                    "Pattern '{}' cannot handle files (fewer than {} layer types)",
+                    pattern.name, min_layer_types
                 );
+                continue;
+            }
+
+            let score = (layer_types, matches);
+            let is_better = match &best {
+                Some((best_score, _)) => score > *best_score,
+                None => true,
+            };
+            if is_better {
+                best = Some((score, pattern));
             }
         }
 
+        if let Some((_, pattern)) = best {
+            info!("Detected pattern: {}", &pattern.name);
+            return Ok(pattern);
+        }
+
         warn!("No known EDA pattern detected");
         Err(TransJlcError::NoMatchingPattern.into())
     }
 
+    /// Group files by the EDA naming convention they follow
+    ///
+    /// Each file is assigned to the most specific known matcher that recognizes it
+    /// (JLC names first, then KiCad, then Protel). Groups are returned in that order
+    /// and only include matchers that recognized at least one file.
+    pub fn group_files_by_eda<P: AsRef<Path>>(files: &[P]) -> Vec<(EdaPatterns, Vec<PathBuf>)> {
+        let mut groups: Vec<(EdaPatterns, Vec<PathBuf>)> = vec![
+            (Self::create_jlc_patterns(), Vec::new()),
+            (Self::create_kicad_patterns(), Vec::new()),
+            (Self::create_protel_patterns(), Vec::new()),
+        ];
+
+        for file in files {
+            let path = file.as_ref();
+            let Some(filename) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            if let Some((patterns, group)) = groups
+                .iter_mut()
+                .find(|(patterns, _)| patterns.match_filename(filename).is_some())
+            {
+                debug!("Grouped '{}' under {}", filename, patterns.name);
+                group.push(path.to_path_buf());
+            }
+        }
+
+        groups.retain(|(_, group)| !group.is_empty());
+        groups
+    }
+
+    /// Load a custom pattern matcher from a TOML file
+    ///
+    /// Each key is a layer type name and each value a regex or list of regexes:
+    ///
+    /// ```toml
+    /// TopCopper = '(?i)\.top$'
+    /// InnerLayer = ['(?i)\.in(\d+)$']
+    /// ```
+    pub fn load_custom_patterns(name: String, path: &Path) -> Result<EdaPatterns> {
+        info!("Loading custom patterns from {}", path.display());
+        let content =
+            std::fs::read_to_string(path).with_path_context("read custom pattern", path)?;
+        Self::parse_custom_patterns(name, &content)
+            .with_context(|| format!("Invalid custom pattern file: {}", path.display()))
+    }
+
+    /// Parse custom pattern definitions, see [`PatternMatcher::load_custom_patterns`]
+    pub fn parse_custom_patterns(name: String, content: &str) -> Result<EdaPatterns> {
+        let table: toml::Table = content.parse().context("Failed to parse TOML")?;
+        let mut patterns = EdaPatterns::new(name);
+
+        for (key, value) in &table {
+            let layer_type = LayerType::from_name(key)
+                .ok_or_else(|| anyhow!("Unknown layer type '{}' (see --list-layers)", key))?;
+
+            let regexes = match value {
+                toml::Value::String(pattern) => vec![pattern.as_str()],
+                toml::Value::Array(items) => items
+                    .iter()
+                    .map(|item| {
+                        item.as_str().ok_or_else(|| {
+                            anyhow!("Patterns for '{}' must be strings, got {}", key, item)
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                other => bail!(
+                    "Patterns for '{}' must be a string or list of strings, got {}",
+                    key,
+                    other
+                ),
+            };
+
+            for regex in regexes {
+                patterns.add_pattern(layer_type.clone(), regex.to_string());
+            }
+        }
+
+        patterns.validate()?;
+        Ok(patterns)
+    }
+
     /// Create patterns for a custom EDA type (placeholder)
-    pub fn create_custom_patterns(name: String) -> EdaPatterns {
+    pub fn create_custom_patterns(name: String) -> Result<EdaPatterns> {
         warn!("Creating custom pattern matcher for: {}", name);
-        EdaPatterns::new(name)
+        let patterns = EdaPatterns::new(name);
+        patterns.validate()?;
+        Ok(patterns)
     }
 }
 
@@ -492,6 +1098,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_easyeda_std_drill_precedence() {
+        let patterns = PatternMatcher::create_easyeda_std_patterns();
+
+        assert_eq!(
+            patterns.match_filename("Gerber_Drill_NPTH.DRL"),
+            Some(LayerType::NpthThrough)
+        );
+        assert_eq!(
+            patterns.match_filename("Gerber_Drill_PTH.DRL"),
+            Some(LayerType::PthThrough)
+        );
+        assert_eq!(
+            patterns.match_filename("Gerber_TopSilkLayer.GTO"),
+            Some(LayerType::TopSilkscreen)
+        );
+    }
+
+    #[test]
+    fn test_auto_detect_easyeda_std() {
+        let files = vec![
+            PathBuf::from("Gerber_TopLayer.GTL"),
+            PathBuf::from("Gerber_BottomLayer.GBL"),
+            PathBuf::from("Gerber_TopSilkLayer.GTO"),
+            PathBuf::from("Gerber_BoardOutline.GKO"),
+            PathBuf::from("Gerber_Drill_PTH.DRL"),
+            PathBuf::from("Gerber_Drill_NPTH.DRL"),
+        ];
+
+        let patterns = PatternMatcher::auto_detect_eda(&files).unwrap();
+        assert_eq!(patterns.name, "EasyEDA Std");
+
+        // JLC exports keep being recognized by the JLC-compatible matchers
+        let jlc_files = vec![
+            PathBuf::from("Gerber_TopLayer.GTL"),
+            PathBuf::from("Gerber_BottomLayer.GBL"),
+            PathBuf::from("Gerber_BoardOutlineLayer.GKO"),
+            PathBuf::from("Drill_PTH_Through.DRL"),
+        ];
+        let patterns = PatternMatcher::auto_detect_eda(&jlc_files).unwrap();
+        assert_ne!(patterns.name, "EasyEDA Std");
+    }
+
+    #[test]
+    fn test_auto_detect_prefers_higher_coverage() {
+        // JLC recognizes three layers, Protel recognizes all five
+        let files = vec![
+            "Gerber_TopLayer.GTL",
+            "Gerber_BottomLayer.GBL",
+            "Gerber_BoardOutlineLayer.GKO",
+            "board.GTS",
+            "board.GTO",
+        ];
+        assert!(PatternMatcher::create_jlc_patterns()
+            .can_handle_files(&files.iter().map(|f| f.to_string()).collect::<Vec<_>>()));
+
+        let patterns = PatternMatcher::auto_detect_eda(&files).unwrap();
+        assert_eq!(patterns.name, "Protel");
+    }
+
+    #[test]
+    fn test_configurable_layer_threshold() {
+        let files = vec!["project-F_Cu.gbr", "project-B_Cu.gbr"];
+
+        assert!(PatternMatcher::auto_detect_eda(&files).is_err());
+        assert_eq!(
+            PatternMatcher::auto_detect_eda_with_threshold(&files, 2)
+                .unwrap()
+                .name,
+            "KiCad"
+        );
+    }
+
+    #[test]
+    fn test_kicad_extra_layers() {
+        let patterns = PatternMatcher::create_kicad_patterns();
+
+        assert_eq!(
+            patterns.match_filename("project-F_Fab.gbr"),
+            Some(LayerType::TopFab)
+        );
+        assert_eq!(
+            patterns.match_filename("project-B_Fab.gbr"),
+            Some(LayerType::BottomFab)
+        );
+        let courtyard = patterns.match_filename("project-F_Courtyard.gbr").unwrap();
+        assert_eq!(courtyard.to_jlc_filename(), "project-F_Courtyard.gbr");
+        assert!(courtyard.is_extra());
+    }
+
     #[test]
     fn test_layer_type_to_jlc_filename() {
         assert_eq!(
@@ -510,6 +1206,245 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_layer_listing() {
+        let listing = layer_listing();
+
+        assert_eq!(listing.lines().count(), LayerType::supported_layers().len());
+        assert!(listing.contains("TopCopper"));
+        assert!(listing.contains("Gerber_TopLayer.GTL"));
+        assert!(listing.contains("Gerber_InnerLayer4.G4"));
+        assert!(!listing.contains("Unknown"));
+    }
+
+    #[test]
+    fn test_gzipped_filename_matching() {
+        let patterns = PatternMatcher::create_kicad_patterns();
+
+        assert_eq!(strip_gzip_suffix("project-F_Cu.gbr.GZ"), "project-F_Cu.gbr");
+        assert_eq!(strip_gzip_suffix("project-F_Cu.gbr"), "project-F_Cu.gbr");
+        assert_eq!(
+            patterns.match_filename("project-F_Cu.gbr.gz"),
+            Some(LayerType::TopCopper)
+        );
+    }
+
+    fn pads_fileset() -> Vec<String> {
+        [
+            "art001.pho",
+            "board-bottom.pho",
+            "board-sm_top.pho",
+            "board-smb.pho",
+            "board-sst.pho",
+            "board-outline.pho",
+            "drl001.drl",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_pads_pattern_matching() {
+        let patterns = PatternMatcher::create_pads_patterns();
+
+        assert_eq!(
+            patterns.match_filename("art001.pho"),
+            Some(LayerType::TopCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("board-sm_top.pho"),
+            Some(LayerType::TopSoldermask)
+        );
+        assert_eq!(
+            patterns.match_filename("board-outline.pho"),
+            Some(LayerType::BoardOutline)
+        );
+        assert_eq!(
+            patterns.match_filename("layer3.pho"),
+            Some(LayerType::InnerLayer(3))
+        );
+        assert_eq!(
+            patterns.match_filename("drl001.drl"),
+            Some(LayerType::PthThrough)
+        );
+
+        let detected = PatternMatcher::auto_detect_eda(&pads_fileset()).unwrap();
+        assert_eq!(detected.name, "PADS");
+    }
+
+    #[test]
+    fn test_allegro_pattern_matching() {
+        let patterns = PatternMatcher::create_allegro_patterns();
+
+        assert_eq!(
+            patterns.match_filename("TOP.art"),
+            Some(LayerType::TopCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("soldermask_bottom.art"),
+            Some(LayerType::BottomSoldermask)
+        );
+        assert_eq!(
+            patterns.match_filename("L2.art"),
+            Some(LayerType::InnerLayer(2))
+        );
+        assert_eq!(
+            patterns.match_filename("ncroute.rou"),
+            Some(LayerType::NpthThrough)
+        );
+
+        let files = [
+            "top.art",
+            "bottom.art",
+            "smt.art",
+            "outline.art",
+            "board.drl",
+        ];
+        let detected = PatternMatcher::auto_detect_eda(&files).unwrap();
+        assert_eq!(detected.name, "Allegro");
+    }
+
+    #[test]
+    fn test_diptrace_pattern_matching() {
+        let patterns = PatternMatcher::create_diptrace_patterns();
+
+        assert_eq!(
+            patterns.match_filename("Board.top"),
+            Some(LayerType::TopCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("Board.SMB"),
+            Some(LayerType::BottomSoldermask)
+        );
+        assert_eq!(
+            patterns.match_filename("Board.bsk"),
+            Some(LayerType::BottomSilkscreen)
+        );
+        assert_eq!(
+            patterns.match_filename("Board.in2"),
+            Some(LayerType::InnerLayer(2))
+        );
+        assert_eq!(
+            patterns.match_filename("Board.brd"),
+            Some(LayerType::BoardOutline)
+        );
+
+        let files = [
+            "Board.top",
+            "Board.bot",
+            "Board.smt",
+            "Board.slk",
+            "Board.brd",
+            "Board.drl",
+        ];
+        let detected = PatternMatcher::auto_detect_eda(&files).unwrap();
+        assert_eq!(detected.name, "DipTrace");
+    }
+
+    #[test]
+    fn test_proteus_pattern_matching() {
+        let patterns = PatternMatcher::create_proteus_patterns();
+
+        assert_eq!(
+            patterns.match_filename("Top Copper.GBR"),
+            Some(LayerType::TopCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Bottom Copper.GBR"),
+            Some(LayerType::BottomCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Top Solder Resist.GBR"),
+            Some(LayerType::TopSoldermask)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Top SMT Paste Mask.GBR"),
+            Some(LayerType::TopPasteMask)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Inner 1.GBR"),
+            Some(LayerType::InnerLayer(1))
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Drill.DRL"),
+            Some(LayerType::PthThrough)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Drill.TXT"),
+            Some(LayerType::PthThrough)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM NPTH Drill.TXT"),
+            Some(LayerType::NpthThrough)
+        );
+        assert_eq!(
+            patterns.match_filename("My Board - CADCAM Non-Plated Drill.DRL"),
+            Some(LayerType::NpthThrough)
+        );
+        // Names run together without a space are a different layer
+        assert_eq!(patterns.match_filename("DesktopCopper.GBR"), None);
+
+        let files = [
+            "My Board - CADCAM Top Copper.GBR",
+            "My Board - CADCAM Bottom Copper.GBR",
+            "My Board - CADCAM Top Silk Screen.GBR",
+            "My Board - CADCAM Board Outline.GBR",
+            "My Board - CADCAM Drill.DRL",
+        ];
+        let detected = PatternMatcher::auto_detect_eda(&files).unwrap();
+        assert_eq!(detected.name, "Proteus");
+    }
+
+    #[test]
+    fn test_pads_and_kicad_do_not_cross_match() {
+        let kicad_files: Vec<String> = [
+            "project-F_Cu.gbr",
+            "project-B_Cu.gbr",
+            "project-F_Mask.gbr",
+            "project-Edge_Cuts.gbr",
+            "project-NPTH.drl",
+            "project-PTH.drl",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+        // Drills alone only give PADS/Allegro two layer types
+        assert!(!PatternMatcher::create_pads_patterns().can_handle_files(&kicad_files));
+        assert!(!PatternMatcher::create_allegro_patterns().can_handle_files(&kicad_files));
+        assert!(!PatternMatcher::create_kicad_patterns().can_handle_files(&pads_fileset()));
+        assert_eq!(
+            PatternMatcher::auto_detect_eda(&kicad_files).unwrap().name,
+            "KiCad"
+        );
+    }
+
+    #[test]
+    fn test_is_netlist_file() {
+        assert!(is_netlist_file("board.d356"));
+        assert!(is_netlist_file("board.IPC"));
+        assert!(!is_netlist_file("board.gbr"));
+    }
+
+    #[test]
+    fn test_group_files_by_eda() {
+        let files = vec![
+            PathBuf::from("project-F_Cu.gbr"),
+            PathBuf::from("project-B_Cu.gbr"),
+            PathBuf::from("legacy.GTL"),
+            PathBuf::from("legacy.GBL"),
+            PathBuf::from("notes.xyz"),
+        ];
+
+        let groups = PatternMatcher::group_files_by_eda(&files);
+        let names: Vec<&str> = groups.iter().map(|(p, _)| p.name.as_str()).collect();
+
+        assert_eq!(names, vec!["KiCad", "Protel"]);
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(groups[1].1.len(), 2);
+    }
+
     #[test]
     fn test_can_handle_files() {
         let patterns = PatternMatcher::create_kicad_patterns();
@@ -526,4 +1461,118 @@ mod tests {
         assert!(patterns.can_handle_files(&files_with_multiple_layers));
         assert!(!patterns.can_handle_files(&files_with_few_layers));
     }
+
+    #[test]
+    fn test_validate_reports_invalid_patterns() {
+        let mut patterns = EdaPatterns::new("Typo".to_string());
+        patterns.add_pattern(LayerType::TopCopper, r"(?i)\.gtl$".to_string());
+        patterns.add_pattern(LayerType::BottomCopper, r"(?i)\.gbl($".to_string());
+        patterns.add_pattern(LayerType::BoardOutline, r"[edge".to_string());
+
+        let err = patterns.validate().unwrap_err();
+        let message = err.to_string();
+        assert!(matches!(
+            err.downcast_ref::<TransJlcError>(),
+            Some(TransJlcError::InvalidPatterns { .. })
+        ));
+        assert!(message.contains("Typo"));
+        assert!(message.contains(r"BottomCopper pattern '(?i)\.gbl($'"));
+        assert!(message.contains("BoardOutline pattern '[edge'"));
+        assert!(!message.contains("gtl"));
+    }
+
+    #[test]
+    fn test_builtin_patterns_are_valid() {
+        for patterns in [
+            PatternMatcher::create_kicad_patterns(),
+            PatternMatcher::create_protel_patterns(),
+            PatternMatcher::create_jlc_patterns(),
+            PatternMatcher::create_easyeda_std_patterns(),
+            PatternMatcher::create_pads_patterns(),
+            PatternMatcher::create_allegro_patterns(),
+            PatternMatcher::create_diptrace_patterns(),
+            PatternMatcher::create_proteus_patterns(),
+        ] {
+            patterns.validate().unwrap();
+        }
+        assert!(PatternMatcher::create_custom_patterns("custom".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_custom_patterns() {
+        let content = r#"
+TopCopper = '(?i)\.top$'
+BottomCopper = ['(?i)\.bot$', '(?i)_bottom\.gbr$']
+innerlayer = '(?i)\.in(\d+)$'
+BoardOutline = '(?i)\.outline$'
+"#;
+        let patterns = PatternMatcher::parse_custom_patterns("mycad".to_string(), content).unwrap();
+
+        assert_eq!(patterns.name, "mycad");
+        assert_eq!(
+            patterns.match_filename("board.TOP"),
+            Some(LayerType::TopCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("board_bottom.gbr"),
+            Some(LayerType::BottomCopper)
+        );
+        assert_eq!(
+            patterns.match_filename("board.in3"),
+            Some(LayerType::InnerLayer(3))
+        );
+        assert_eq!(patterns.match_filename("board.gtl"), None);
+    }
+
+    #[test]
+    fn test_parse_custom_patterns_rejects_bad_input() {
+        for content in [
+            "NotALayer = 'x'",
+            "TopCopper = 3",
+            "TopCopper = ['(']",
+            "TopCopper = [",
+        ] {
+            assert!(
+                PatternMatcher::parse_custom_patterns("bad".to_string(), content).is_err(),
+                "{} should be rejected",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_inner_layer_filenames_up_to_twelve() {
+        let expected = [
+            "Gerber_InnerLayer1.G1",
+            "Gerber_InnerLayer2.G2",
+            "Gerber_InnerLayer3.G3",
+            "Gerber_InnerLayer4.G4",
+            "Gerber_InnerLayer5.G5",
+            "Gerber_InnerLayer6.G6",
+            "Gerber_InnerLayer7.G7",
+            "Gerber_InnerLayer8.G8",
+            "Gerber_InnerLayer9.G9",
+            "Gerber_InnerLayer10.G10",
+            "Gerber_InnerLayer11.G11",
+            "Gerber_InnerLayer12.G12",
+        ];
+        let jlc = PatternMatcher::create_jlc_patterns();
+        let kicad = PatternMatcher::create_kicad_patterns();
+        let protel = PatternMatcher::create_protel_patterns();
+
+        for (num, name) in (1..=12).zip(expected) {
+            assert_eq!(LayerType::InnerLayer(num).to_jlc_filename(), name);
+
+            // Two-digit layers keep their number through every matcher
+            assert_eq!(jlc.match_filename(name), Some(LayerType::InnerLayer(num)));
+            assert_eq!(
+                kicad.match_filename(&format!("board-In{}_Cu.gbr", num)),
+                Some(LayerType::InnerLayer(num))
+            );
+            assert_eq!(
+                protel.match_filename(&format!("board.G{}", num)),
+                Some(LayerType::InnerLayer(num))
+            );
+        }
+    }
 }
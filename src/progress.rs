@@ -3,44 +3,134 @@
 //! This module provides unified progress bar functionality
 //! for various operations throughout the application.
 
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
 use std::time::Duration;
 use tracing::info;
 
+/// Template for item-count progress bars, including throughput and ETA
+pub(crate) const FILE_PROGRESS_TEMPLATE: &str = "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {per_sec} ETA {eta} {msg}";
+
+/// Template for byte-weighted progress bars, including throughput and ETA
+pub(crate) const BYTE_PROGRESS_TEMPLATE: &str = "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}) ETA {eta} {msg}";
+
+/// Template for the per-file stage bar shown below the conversion progress
+pub(crate) const STAGE_PROGRESS_TEMPLATE: &str = "  {prefix:.bold} [{bar:20.cyan/blue}] {msg}";
+
+/// Conversion progress: one bar counting files and one for the current file
+pub struct ConversionProgress {
+    multi: MultiProgress,
+    /// Advances once per converted file
+    pub files: ProgressBar,
+    /// Shows the current file name and its processing stage
+    pub current: ProgressBar,
+}
+
+impl ConversionProgress {
+    /// Start reporting the stages of a new file
+    pub fn start_file(&self, name: &str) {
+        self.current.set_prefix(name.to_string());
+        self.current.set_length(1);
+        self.current.set_position(0);
+        self.current.set_message("Reading");
+    }
+
+    /// Remove the stage bar and finish the file bar
+    pub fn finish(self, success_message: &str) {
+        self.current.finish_and_clear();
+        self.multi.remove(&self.current);
+        ProgressTracker::finish_progress(Some(self.files), success_message);
+    }
+}
+
 /// Progress tracker for TransJLC operations
 pub struct ProgressTracker {
     enabled: bool,
+    /// Whether progress is drawn on a terminal; otherwise bars are replaced
+    /// by plain percentage lines so CI logs are not flooded with redraws
+    terminal: bool,
 }
 
 impl ProgressTracker {
-    /// Create a new progress tracker
+    /// Create a new progress tracker drawing on a terminal
     pub fn new(enabled: bool) -> Self {
-        Self { enabled }
+        Self {
+            enabled,
+            terminal: true,
+        }
+    }
+
+    /// Create a progress tracker that only animates when stderr is a terminal
+    pub fn for_stderr(enabled: bool) -> Self {
+        Self::new(enabled).with_terminal(std::io::stderr().is_terminal())
+    }
+
+    /// Configure whether progress is drawn on a terminal
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    /// Whether animated progress bars should be created
+    fn animated(&self) -> bool {
+        self.enabled && self.terminal
+    }
+
+    /// Print a plain progress line instead of a bar when not on a terminal
+    ///
+    /// Lines are only printed every 10% (and on completion) to keep logs short.
+    /// Returns whether a line was printed.
+    pub fn report_line(&self, done: usize, total: usize, operation: &str) -> bool {
+        if !self.enabled || self.terminal || total == 0 || done == 0 {
+            return false;
+        }
+
+        let percent = done * 100 / total;
+        let previous = (done - 1) * 100 / total;
+        if done < total && percent / 10 == previous / 10 {
+            return false;
+        }
+
+        eprintln!("{}: {}/{} ({}%)", operation, done, total, percent);
+        true
     }
 
     /// Create a progress bar for file operations
     pub fn create_file_progress(&self, total: usize, operation: &str) -> Option<ProgressBar> {
-        if !self.enabled || total == 0 {
+        if !self.animated() || total == 0 {
+            return None;
+        }
+
+        let pb = file_progress_bar(total, operation);
+        pb.enable_steady_tick(Duration::from_millis(100));
+
+        info!("Started progress tracking for: {}", operation);
+        Some(pb)
+    }
+
+    /// Create a progress bar weighted by bytes rather than item count
+    pub fn create_byte_progress(&self, total_bytes: u64, operation: &str) -> Option<ProgressBar> {
+        if !self.animated() || total_bytes == 0 {
             return None;
         }
 
-        let pb = ProgressBar::new(total as u64);
+        let pb = ProgressBar::new(total_bytes);
         pb.set_style(
             ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")
+                .template(BYTE_PROGRESS_TEMPLATE)
                 .unwrap_or_else(|_| ProgressStyle::default_bar())
-                .progress_chars("█▉▊▋▌▍▎▏ ")
+                .progress_chars("█▉▊▋▌▍▎▏ "),
         );
         pb.set_message(format!("{}...", operation));
         pb.enable_steady_tick(Duration::from_millis(100));
 
-        info!("Started progress tracking for: {}", operation);
+        info!("Started byte progress tracking for: {}", operation);
         Some(pb)
     }
 
     /// Create a spinner for indeterminate operations
     pub fn create_spinner(&self, message: &str) -> Option<ProgressBar> {
-        if !self.enabled {
+        if !self.animated() {
             return None;
         }
 
@@ -62,6 +152,32 @@ impl ProgressTracker {
         self.create_file_progress(total, "Converting files")
     }
 
+    /// Create a file progress bar with a second bar for the current file's stages
+    pub fn create_detailed_conversion_progress(&self, total: usize) -> Option<ConversionProgress> {
+        if total == 0 {
+            return None;
+        }
+        let multi = self.create_multi_progress()?;
+
+        let files = multi.add(file_progress_bar(total, "Converting files"));
+        files.enable_steady_tick(Duration::from_millis(100));
+
+        let current = multi.add(ProgressBar::new(1));
+        current.set_style(
+            ProgressStyle::default_bar()
+                .template(STAGE_PROGRESS_TEMPLATE)
+                .unwrap_or_else(|_| ProgressStyle::default_bar())
+                .progress_chars("█▉▊▋▌▍▎▏ "),
+        );
+
+        info!("Started detailed progress tracking for {} files", total);
+        Some(ConversionProgress {
+            multi,
+            files,
+            current,
+        })
+    }
+
     /// Create a progress bar for archive operations
     pub fn create_archive_progress(
         &self,
@@ -102,38 +218,71 @@ impl ProgressTracker {
     }
 
     /// Create a multi-progress for complex operations
-    pub fn create_multi_progress(&self) -> Option<indicatif::MultiProgress> {
-        if !self.enabled {
+    pub fn create_multi_progress(&self) -> Option<MultiProgress> {
+        if !self.animated() {
             return None;
         }
 
-        Some(indicatif::MultiProgress::new())
+        Some(MultiProgress::new())
     }
 }
 
-/// Utility trait for easy progress tracking integration
-pub trait WithProgress<T> {
-    /// Execute operation with progress tracking
-    fn with_progress<F>(self, tracker: &ProgressTracker, operation: &str, f: F) -> T
-    where
-        F: FnOnce(&Option<ProgressBar>) -> T;
+/// Item-count progress bar in the standard style
+fn file_progress_bar(total: usize, operation: &str) -> ProgressBar {
+    let pb = ProgressBar::new(total as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(FILE_PROGRESS_TEMPLATE)
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("█▉▊▋▌▍▎▏ "),
+    );
+    pb.set_message(format!("{}...", operation));
+    pb
 }
 
-impl<I, T> WithProgress<Vec<T>> for I
-where
-    I: IntoIterator,
-    I::Item: Clone,
-{
-    fn with_progress<F>(self, tracker: &ProgressTracker, operation: &str, f: F) -> Vec<T>
+/// Utility trait for easy progress tracking integration
+///
+/// The progress bar advances once per item, so callers only provide the work
+/// to do for each item.
+pub trait WithProgress: IntoIterator + Sized {
+    /// Map every item through `f`, advancing a progress bar after each one
+    fn map_with_progress<F, R>(self, tracker: &ProgressTracker, operation: &str, f: F) -> Vec<R>
     where
-        F: FnOnce(&Option<ProgressBar>) -> Vec<T>,
+        F: FnMut(Self::Item) -> R,
     {
         let items: Vec<_> = self.into_iter().collect();
         let pb = tracker.create_file_progress(items.len(), operation);
-        let result = f(&pb);
+        let result = map_with_bar(items, pb.as_ref(), f);
         ProgressTracker::finish_progress(pb, &format!("{} completed successfully", operation));
         result
     }
+
+    /// Run `f` for every item, advancing a progress bar after each one
+    fn with_progress<F>(self, tracker: &ProgressTracker, operation: &str, f: F)
+    where
+        F: FnMut(Self::Item),
+    {
+        self.map_with_progress(tracker, operation, f);
+    }
+}
+
+impl<I: IntoIterator> WithProgress for I {}
+
+/// Map items through `f`, incrementing `pb` once per item
+fn map_with_bar<T, F, R>(items: Vec<T>, pb: Option<&ProgressBar>, mut f: F) -> Vec<R>
+where
+    F: FnMut(T) -> R,
+{
+    items
+        .into_iter()
+        .map(|item| {
+            let result = f(item);
+            if let Some(pb) = pb {
+                pb.inc(1);
+            }
+            result
+        })
+        .collect()
 }
 
 /// Progress-aware operation wrapper
@@ -215,6 +364,96 @@ mod tests {
         assert_eq!(op.progress_fraction(), 1.0);
     }
 
+    #[test]
+    fn test_progress_templates_include_eta_and_rate() {
+        assert!(FILE_PROGRESS_TEMPLATE.contains("{eta}"));
+        assert!(FILE_PROGRESS_TEMPLATE.contains("{per_sec}"));
+        assert!(BYTE_PROGRESS_TEMPLATE.contains("{eta}"));
+        assert!(BYTE_PROGRESS_TEMPLATE.contains("{bytes_per_sec}"));
+
+        // Templates must be accepted by indicatif
+        assert!(ProgressStyle::default_bar()
+            .template(FILE_PROGRESS_TEMPLATE)
+            .is_ok());
+        assert!(ProgressStyle::default_bar()
+            .template(BYTE_PROGRESS_TEMPLATE)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_byte_progress_creation() {
+        let tracker = ProgressTracker::new(true);
+
+        assert!(tracker.create_byte_progress(1024, "bytes").is_some());
+        assert!(tracker.create_byte_progress(0, "bytes").is_none());
+        assert!(ProgressTracker::new(false)
+            .create_byte_progress(1024, "bytes")
+            .is_none());
+    }
+
+    #[test]
+    fn test_detailed_conversion_progress_uses_multi_progress() {
+        let progress = ProgressTracker::new(true)
+            .create_detailed_conversion_progress(3)
+            .expect("Detailed progress should be created when enabled");
+
+        assert_eq!(progress.files.length(), Some(3));
+        progress.start_file("board-F_Cu.gbr");
+        assert_eq!(progress.current.prefix(), "board-F_Cu.gbr");
+        assert!(ProgressStyle::default_bar()
+            .template(STAGE_PROGRESS_TEMPLATE)
+            .is_ok());
+        progress.finish("done");
+
+        assert!(ProgressTracker::new(false)
+            .create_detailed_conversion_progress(3)
+            .is_none());
+        assert!(ProgressTracker::new(true)
+            .create_detailed_conversion_progress(0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_no_bars_without_terminal() {
+        let tracker = ProgressTracker::new(true).with_terminal(false);
+
+        assert!(tracker.create_file_progress(10, "test operation").is_none());
+        assert!(tracker.create_byte_progress(1024, "bytes").is_none());
+        assert!(tracker.create_spinner("Processing...").is_none());
+        assert!(tracker.create_detailed_conversion_progress(3).is_none());
+
+        // Plain lines every 10% and on completion instead
+        let printed: Vec<usize> = (1..=20)
+            .filter(|&done| tracker.report_line(done, 20, "Converting files"))
+            .collect();
+        assert_eq!(printed, vec![2, 4, 6, 8, 10, 12, 14, 16, 18, 20]);
+        assert!(!ProgressTracker::new(true).report_line(20, 20, "Converting files"));
+        assert!(!ProgressTracker::new(false)
+            .with_terminal(false)
+            .report_line(20, 20, "Converting files"));
+    }
+
+    #[test]
+    fn test_map_with_progress_advances_per_item() {
+        let items = vec![1, 2, 3, 4];
+        let pb = ProgressBar::hidden();
+        pb.set_length(items.len() as u64);
+
+        let doubled = map_with_bar(items, Some(&pb), |item| item * 2);
+
+        assert_eq!(doubled, vec![2, 4, 6, 8]);
+        assert_eq!(pb.position(), pb.length().unwrap());
+
+        let tracker = ProgressTracker::new(false);
+        assert_eq!(
+            ["a", "b"].map_with_progress(&tracker, "Upper-casing", str::to_uppercase),
+            vec!["A", "B"]
+        );
+        let mut seen = Vec::new();
+        vec![1, 2].with_progress(&tracker, "Collecting", |item| seen.push(item));
+        assert_eq!(seen, vec![1, 2]);
+    }
+
     #[test]
     fn test_zero_total_progress() {
         let tracker = ProgressTracker::new(true);
@@ -0,0 +1,298 @@
+//! End-of-run conversion summary
+//!
+//! Turns the `ConversionStats` of a finished run into the table printed by the
+//! CLI: which EDA format was detected, how many copper layers were found, where
+//! every matched input went and where the output ended up.
+
+use crate::{
+    converter::{ConversionStats, LayerMapping},
+    patterns::LayerType,
+};
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+/// Smallest board edge JLC manufactures, in millimeters
+pub const DEFAULT_MIN_BOARD_SIZE_MM: f64 = 5.0;
+
+/// Board size measured from the outline layer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardDimensions {
+    /// Width in millimeters
+    pub width: f64,
+    /// Height in millimeters
+    pub height: f64,
+}
+
+impl BoardDimensions {
+    /// Whether either edge is shorter than `min` millimeters
+    pub fn is_smaller_than(&self, min: f64) -> bool {
+        self.width < min || self.height < min
+    }
+}
+
+impl fmt::Display for BoardDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} x {:.2} mm", self.width, self.height)
+    }
+}
+
+/// How far a layer may reach past the outline before it is flagged, in millimeters
+pub const BOUNDS_TOLERANCE_MM: f64 = 1.0;
+
+/// Coordinate extents of one converted Gerber layer, in millimeters
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerBounds {
+    pub layer: LayerType,
+    /// Output file name of the layer
+    pub output: String,
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+}
+
+impl LayerBounds {
+    /// Whether this layer reaches more than `tolerance` millimeters past `outline`
+    pub fn exceeds(&self, outline: &LayerBounds, tolerance: f64) -> bool {
+        self.min_x < outline.min_x - tolerance
+            || self.min_y < outline.min_y - tolerance
+            || self.max_x > outline.max_x + tolerance
+            || self.max_y > outline.max_y + tolerance
+    }
+}
+
+/// Per-layer extents printed by `--bounds-report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundsReport {
+    /// Measured layers, ordered by output file name
+    pub layers: Vec<LayerBounds>,
+}
+
+impl BoundsReport {
+    /// Build a report from the layer extents of a conversion
+    pub fn from_stats(stats: &ConversionStats) -> Self {
+        let mut layers = stats.layer_bounds.clone();
+        layers.sort_by(|a, b| a.output.cmp(&b.output));
+        Self { layers }
+    }
+
+    /// Layers reaching past the board outline, if there is one
+    pub fn outliers(&self) -> Vec<&LayerBounds> {
+        let Some(outline) = self
+            .layers
+            .iter()
+            .find(|bounds| bounds.layer == LayerType::BoardOutline)
+        else {
+            return Vec::new();
+        };
+        self.layers
+            .iter()
+            .filter(|bounds| bounds.exceeds(outline, BOUNDS_TOLERANCE_MM))
+            .collect()
+    }
+}
+
+impl fmt::Display for BoundsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Layer bounds (mm):")?;
+
+        let outliers = self.outliers();
+        let width = self
+            .layers
+            .iter()
+            .map(|bounds| bounds.output.chars().count())
+            .max()
+            .unwrap_or(0);
+        for bounds in &self.layers {
+            write!(
+                f,
+                "  {:<width$}  X {:.3} .. {:.3}  Y {:.3} .. {:.3}",
+                bounds.output,
+                bounds.min_x,
+                bounds.max_x,
+                bounds.min_y,
+                bounds.max_y,
+                width = width
+            )?;
+            if outliers.contains(&bounds) {
+                write!(f, "  (extends past the board outline)")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summary of a finished conversion, ready for display
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionReport {
+    /// Detected (or selected) EDA format names
+    pub eda: Vec<String>,
+    /// Number of distinct copper layers, outer and inner
+    pub copper_layers: usize,
+    /// Board size, when an outline layer could be measured
+    pub board_size: Option<BoardDimensions>,
+    /// Converted layers, ordered by output file name
+    pub layers: Vec<LayerMapping>,
+    /// Output directory or archive
+    pub output: PathBuf,
+}
+
+impl ConversionReport {
+    /// Build a report from the statistics of a conversion
+    pub fn from_stats(stats: &ConversionStats) -> Self {
+        let copper_layers = stats
+            .layer_types_found
+            .iter()
+            .filter(|layer| {
+                matches!(
+                    layer,
+                    LayerType::TopCopper | LayerType::BottomCopper | LayerType::InnerLayer(_)
+                )
+            })
+            .collect::<HashSet<_>>()
+            .len();
+
+        let mut layers = stats.layer_mappings.clone();
+        layers.sort_by(|a, b| a.output.cmp(&b.output).then(a.source.cmp(&b.source)));
+
+        Self {
+            eda: stats.detected_eda.clone(),
+            copper_layers,
+            board_size: stats.board_size,
+            layers,
+            output: stats.output_location.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ConversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let eda = if self.eda.is_empty() {
+            "unknown".to_string()
+        } else {
+            self.eda.join(", ")
+        };
+
+        writeln!(f, "EDA format:    {}", eda)?;
+        writeln!(f, "Copper layers: {}", self.copper_layers)?;
+        if let Some(size) = &self.board_size {
+            writeln!(f, "Board size:    {}", size)?;
+        }
+        writeln!(f, "Layers:")?;
+
+        let width = self
+            .layers
+            .iter()
+            .map(|mapping| mapping.source.chars().count())
+            .max()
+            .unwrap_or(0);
+        for mapping in &self.layers {
+            writeln!(
+                f,
+                "  {:<width$} -> {}",
+                mapping.source,
+                mapping.output,
+                width = width
+            )?;
+        }
+
+        writeln!(f, "Output:        {}", self.output.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(layer: LayerType, source: &str) -> LayerMapping {
+        LayerMapping {
+            output: layer.to_jlc_filename(),
+            layer,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_report_formatting() {
+        let stats = ConversionStats {
+            total_files_processed: 4,
+            layer_types_found: vec![
+                LayerType::TopCopper,
+                LayerType::BottomCopper,
+                LayerType::InnerLayer(1),
+                LayerType::BoardOutline,
+            ],
+            output_format: "Files".to_string(),
+            detected_eda: vec!["KiCad".to_string()],
+            layer_mappings: vec![
+                mapping(LayerType::TopCopper, "board-F_Cu.gbr"),
+                mapping(LayerType::BoardOutline, "board-Edge_Cuts.gbr"),
+                mapping(LayerType::InnerLayer(1), "board-In1_Cu.gbr"),
+                mapping(LayerType::BottomCopper, "board-B_Cu.gbr"),
+            ],
+            output_location: PathBuf::from("/tmp/out"),
+            ..Default::default()
+        };
+
+        let report = ConversionReport::from_stats(&stats);
+        assert_eq!(report.copper_layers, 3);
+        assert_eq!(
+            report.to_string(),
+            "EDA format:    KiCad\n\
+             Copper layers: 3\n\
+             Layers:\n\
+             \x20 board-Edge_Cuts.gbr -> Gerber_BoardOutlineLayer.GKO\n\
+             \x20 board-B_Cu.gbr      -> Gerber_BottomLayer.GBL\n\
+             \x20 board-In1_Cu.gbr    -> Gerber_InnerLayer1.G1\n\
+             \x20 board-F_Cu.gbr      -> Gerber_TopLayer.GTL\n\
+             Output:        /tmp/out\n"
+        );
+    }
+
+    #[test]
+    fn test_report_shows_board_size() {
+        let stats = ConversionStats {
+            board_size: Some(BoardDimensions {
+                width: 50.8,
+                height: 25.4,
+            }),
+            ..Default::default()
+        };
+
+        let report = ConversionReport::from_stats(&stats).to_string();
+        assert!(report.contains("Copper layers: 0\nBoard size:    50.80 x 25.40 mm\n"));
+    }
+
+    fn bounds(layer: LayerType, max_x: f64, max_y: f64) -> LayerBounds {
+        LayerBounds {
+            output: layer.to_jlc_filename(),
+            layer,
+            min_x: 0.0,
+            max_x,
+            min_y: 0.0,
+            max_y,
+        }
+    }
+
+    #[test]
+    fn test_bounds_report_flags_layers_past_the_outline() {
+        let stats = ConversionStats {
+            layer_bounds: vec![
+                bounds(LayerType::TopCopper, 50.5, 20.0),
+                bounds(LayerType::BoardOutline, 50.0, 20.0),
+                bounds(LayerType::TopSilkscreen, 80.0, 20.0),
+            ],
+            ..Default::default()
+        };
+
+        let report = BoundsReport::from_stats(&stats);
+        let outliers = report.outliers();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].layer, LayerType::TopSilkscreen);
+
+        let text = report.to_string();
+        assert!(text.starts_with("Layer bounds (mm):\n  Gerber_BoardOutlineLayer.GKO"));
+        assert!(text.contains("X 0.000 .. 80.000  Y 0.000 .. 20.000  (extends past"));
+        assert_eq!(text.matches("extends past").count(), 1);
+    }
+}
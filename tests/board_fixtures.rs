@@ -0,0 +1,249 @@
+//! Full-pipeline tests against realistic board fixtures
+
+mod common;
+
+use clap::Parser;
+use common::{assert_jlc_file_set, convert_fixture, copy_fixture, list_files};
+use std::{collections::BTreeSet, fs, io::Write, path::Path};
+use TransJLC::{boards, config::Config, patterns::LayerType};
+
+/// JLC files produced from the four-layer KiCad fixture
+const KICAD_4LAYER_OUTPUT: &[&str] = &[
+    "Drill_NPTH_Through.DRL",
+    "Drill_PTH_Through.DRL",
+    "Gerber_BoardOutlineLayer.GKO",
+    "Gerber_BottomLayer.GBL",
+    "Gerber_BottomSilkscreenLayer.GBO",
+    "Gerber_BottomSolderMaskLayer.GBS",
+    "Gerber_InnerLayer1.G1",
+    "Gerber_InnerLayer2.G2",
+    "Gerber_TopLayer.GTL",
+    "Gerber_TopPasteMaskLayer.GTP",
+    "Gerber_TopSilkscreenLayer.GTO",
+    "Gerber_TopSolderMaskLayer.GTS",
+    "PCB下单必读.txt",
+];
+
+#[test]
+fn test_kicad_four_layer_board_converts_to_jlc_file_set() {
+    let conversion = convert_fixture("kicad_4layer", |_| {});
+
+    assert_jlc_file_set(&conversion, KICAD_4LAYER_OUTPUT);
+    assert!(conversion.stats.unmatched_files.is_empty());
+    assert_eq!(conversion.stats.detected_eda, vec!["KiCad".to_string()]);
+    assert_eq!(conversion.stats.layer_mappings.len(), 12);
+    let size = conversion
+        .stats
+        .board_size
+        .expect("Outline should be measured");
+    assert!((size.width - 50.0).abs() < 1e-6 && (size.height - 30.0).abs() < 1e-6);
+    assert!(conversion
+        .stats
+        .layer_types_found
+        .contains(&LayerType::InnerLayer(2)));
+
+    // Gerber layers gain the EasyEDA header, drills are passed through untouched
+    let top = conversion.read("Gerber_TopLayer.GTL");
+    assert!(top.starts_with("G04 EasyEDA Pro"));
+    assert!(top.contains("X127540000Y-105000000D03*"));
+    let npth = conversion.read("Drill_NPTH_Through.DRL");
+    assert!(npth.starts_with("M48"));
+    assert!(npth.contains("T1C3.200"));
+}
+
+#[test]
+fn test_colorful_flags_produce_colorful_layers() {
+    let colorful = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/colorful");
+    let top_mask = common::fixture_path("kicad_4layer").join("sensor_board-F_Mask.gbr");
+    let args = Config::parse_from([
+        "transjlc".as_ref(),
+        "--colorful-top".as_ref(),
+        colorful.join("test_top.svg").as_os_str(),
+        "--colorful-bottom".as_ref(),
+        colorful.join("test_bottom.svg").as_os_str(),
+        "--colorful-top-mask".as_ref(),
+        top_mask.as_os_str(),
+    ]);
+    assert_eq!(args.colorful_top_mask.as_deref(), Some(top_mask.as_path()));
+
+    let conversion = convert_fixture("kicad_4layer", |config| {
+        config.top_color_image = args.top_color_image.clone();
+        config.bottom_color_image = args.bottom_color_image.clone();
+        config.colorful_top_mask = args.colorful_top_mask.clone();
+    });
+
+    let produced = conversion.produced_files();
+    for name in [
+        "Fabrication_ColorfulTopSilkscreen.FCTS",
+        "Fabrication_ColorfulBottomSilkscreen.FCBS",
+        "Fabrication_ColorfulBoardOutlineLayer.FCBO",
+        "Fabrication_ColorfulBoardOutlineMark.FCBM",
+    ] {
+        assert!(produced.contains(name), "{} missing", name);
+    }
+    assert!(conversion
+        .stats
+        .layer_types_found
+        .contains(&LayerType::ColorfulTopSilkscreen));
+    assert!(conversion
+        .read("Fabrication_ColorfulBoardOutlineMark.FCBM")
+        .starts_with("G04 Fabrication_ColorfulBoardOutlineMark*"));
+}
+
+#[test]
+fn test_three_boards_convert_concurrently() {
+    let input_root = tempfile::TempDir::new().expect("Failed to create input root");
+    let output_root = tempfile::TempDir::new().expect("Failed to create output root");
+
+    let names = ["alpha", "beta", "gamma"];
+    let mut board_paths = Vec::new();
+    for name in names {
+        let board = input_root.path().join(name);
+        copy_fixture("kicad_4layer", &board);
+        board_paths.push(board);
+    }
+
+    let config = Config {
+        output_path: output_root.path().to_path_buf(),
+        boards: board_paths,
+        ..Default::default()
+    };
+    let results = boards::convert_boards(boards::board_jobs(&config), 3);
+
+    assert_eq!(results.len(), 3);
+    for (result, name) in results.iter().zip(names) {
+        assert_eq!(result.name, name);
+        assert!(result.result.is_ok(), "{} failed", name);
+
+        let produced = list_files(&output_root.path().join(name));
+        let expected: BTreeSet<String> = KICAD_4LAYER_OUTPUT
+            .iter()
+            .map(|file| file.to_string())
+            .collect();
+        assert_eq!(produced, expected);
+    }
+}
+
+/// Names of the entries in a ZIP archive
+fn zip_entries(archive: &Path) -> BTreeSet<String> {
+    let file = fs::File::open(archive).expect("Failed to open archive");
+    let mut zip = zip::ZipArchive::new(file).expect("Failed to read archive");
+    (0..zip.len())
+        .map(|index| {
+            zip.by_index(index)
+                .expect("Failed to read entry")
+                .name()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_boards_with_zip_path_write_one_archive_each() {
+    let input_root = tempfile::TempDir::new().expect("Failed to create input root");
+    let output_root = tempfile::TempDir::new().expect("Failed to create output root");
+
+    let mut board_paths = Vec::new();
+    for name in ["alpha", "beta"] {
+        let board = input_root.path().join(name);
+        copy_fixture("kicad_4layer", &board);
+        board_paths.push(board);
+    }
+
+    let config = Config {
+        output_path: output_root.path().to_path_buf(),
+        zip_path: Some(output_root.path().join("gerbers.zip")),
+        boards: board_paths,
+        ..Default::default()
+    };
+    let results = boards::convert_boards(boards::board_jobs(&config), 2);
+
+    let expected: BTreeSet<String> = KICAD_4LAYER_OUTPUT
+        .iter()
+        .map(|file| file.to_string())
+        .collect();
+    for result in &results {
+        assert!(result.result.is_ok(), "{} failed", result.name);
+    }
+    assert!(!output_root.path().join("gerbers.zip").exists());
+    for name in ["alpha", "beta"] {
+        let archive = output_root.path().join(format!("gerbers-{}.zip", name));
+        assert_eq!(zip_entries(&archive), expected, "{}", archive.display());
+    }
+}
+
+/// Pack a fixture's files into a ZIP archive
+fn zip_fixture(name: &str, archive: &Path) {
+    let file = fs::File::create(archive).expect("Failed to create archive");
+    let mut writer = zip::ZipWriter::new(file);
+    for entry in list_files(&common::fixture_path(name)) {
+        let content =
+            fs::read(common::fixture_path(name).join(&entry)).expect("Failed to read fixture");
+        writer
+            .start_file(entry, zip::write::SimpleFileOptions::default())
+            .expect("Failed to start archive entry");
+        writer.write_all(&content).expect("Failed to write entry");
+    }
+    writer.finish().expect("Failed to finish archive");
+}
+
+#[test]
+fn test_batch_converts_each_zip_independently() {
+    let input_root = tempfile::TempDir::new().expect("Failed to create input root");
+    let output_root = tempfile::TempDir::new().expect("Failed to create output root");
+    zip_fixture("kicad_4layer", &input_root.path().join("boardA.zip"));
+    zip_fixture("kicad_4layer", &input_root.path().join("boardB.zip"));
+
+    let config = Config {
+        path: input_root.path().to_path_buf(),
+        output_path: output_root.path().to_path_buf(),
+        batch: true,
+        ..Default::default()
+    };
+    let jobs = boards::batch_jobs(&config).expect("Batch input should be found");
+    let results = boards::convert_boards(jobs, 2);
+
+    let expected: BTreeSet<String> = KICAD_4LAYER_OUTPUT
+        .iter()
+        .map(|file| file.to_string())
+        .collect();
+    assert_eq!(results.len(), 2);
+    for (result, name) in results.iter().zip(["boardA", "boardB"]) {
+        assert_eq!(result.name, name);
+        let stats = result.result.as_ref().expect("Board should convert");
+        assert_eq!(stats.layer_mappings.len(), 12);
+        assert_eq!(list_files(&output_root.path().join(name)), expected);
+    }
+}
+
+#[test]
+fn test_batch_with_zip_path_writes_one_archive_per_board() {
+    let input_root = tempfile::TempDir::new().expect("Failed to create input root");
+    let output_root = tempfile::TempDir::new().expect("Failed to create output root");
+    zip_fixture("kicad_4layer", &input_root.path().join("boardA.zip"));
+    zip_fixture("kicad_4layer", &input_root.path().join("boardB.zip"));
+
+    let config = Config {
+        path: input_root.path().to_path_buf(),
+        output_path: output_root.path().to_path_buf(),
+        zip_path: Some(output_root.path().join("gerbers.zip")),
+        batch: true,
+        ..Default::default()
+    };
+    let jobs = boards::batch_jobs(&config).expect("Batch input should be found");
+    let results = boards::convert_boards(jobs, 2);
+
+    let expected: BTreeSet<String> = KICAD_4LAYER_OUTPUT
+        .iter()
+        .map(|file| file.to_string())
+        .collect();
+    assert_eq!(results.len(), 2);
+    for result in &results {
+        assert!(result.result.is_ok(), "{} failed", result.name);
+    }
+    assert!(!output_root.path().join("gerbers.zip").exists());
+    for name in ["boardA", "boardB"] {
+        let archive = output_root.path().join(format!("gerbers-{}.zip", name));
+        assert_eq!(zip_entries(&archive), expected, "{}", archive.display());
+    }
+}
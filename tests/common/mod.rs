@@ -0,0 +1,92 @@
+//! Shared helpers for running full conversions against the board fixtures
+//!
+//! Fixtures live in `tests/data/boards/<name>/` and contain a realistic export
+//! from one EDA tool. Test crates pull this module in with `mod common;`.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+use TransJLC::{
+    config::Config,
+    converter::{ConversionStats, Converter},
+};
+
+/// Directory of a board fixture under `tests/data/boards`
+pub fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/boards")
+        .join(name)
+}
+
+/// Copy a fixture's files into a new directory, e.g. to get several boards
+pub fn copy_fixture(name: &str, destination: &Path) {
+    fs::create_dir_all(destination).expect("Failed to create fixture copy");
+    for file in list_files(&fixture_path(name)) {
+        fs::copy(fixture_path(name).join(&file), destination.join(&file))
+            .expect("Failed to copy fixture file");
+    }
+}
+
+/// Result of converting a fixture into a fresh output directory
+pub struct FixtureConversion {
+    /// Output directory, removed when the conversion is dropped
+    pub output_dir: TempDir,
+    pub stats: ConversionStats,
+}
+
+impl FixtureConversion {
+    /// Names of the files written to the output directory
+    pub fn produced_files(&self) -> BTreeSet<String> {
+        list_files(self.output_dir.path())
+    }
+
+    /// Content of a produced file
+    pub fn read(&self, name: &str) -> String {
+        fs::read_to_string(self.output_dir.path().join(name))
+            .unwrap_or_else(|e| panic!("Failed to read produced file {}: {}", name, e))
+    }
+}
+
+/// Run a full conversion of a fixture, letting the caller adjust the configuration
+pub fn convert_fixture(name: &str, configure: impl FnOnce(&mut Config)) -> FixtureConversion {
+    let output_dir = TempDir::new().expect("Failed to create output dir");
+
+    let mut config = Config {
+        path: fixture_path(name),
+        output_path: output_dir.path().to_path_buf(),
+        no_progress: true,
+        ..Default::default()
+    };
+    configure(&mut config);
+
+    let mut converter = Converter::new(config);
+    converter
+        .run()
+        .unwrap_or_else(|e| panic!("Conversion of fixture {} failed: {:#}", name, e));
+
+    FixtureConversion {
+        stats: converter.get_conversion_stats(),
+        output_dir,
+    }
+}
+
+/// Assert that exactly the expected JLC files were produced
+pub fn assert_jlc_file_set(conversion: &FixtureConversion, expected: &[&str]) {
+    let expected: BTreeSet<String> = expected.iter().map(|name| name.to_string()).collect();
+    assert_eq!(conversion.produced_files(), expected);
+}
+
+/// Names of the regular files directly inside a directory
+pub fn list_files(dir: &Path) -> BTreeSet<String> {
+    fs::read_dir(dir)
+        .expect("Failed to read directory")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect()
+}
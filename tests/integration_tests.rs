@@ -3,12 +3,18 @@
 //! This module contains comprehensive tests for the entire conversion pipeline
 //! and individual component functionality.
 
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 use tempfile::TempDir;
 use TransJLC::{
     archive::ArchiveExtractor,
     config::{Config, EdaType},
-    converter::Converter,
+    convert_layers,
+    converter::{ConversionEvent, Converter},
     gerber::GerberProcessor,
     patterns::{LayerType, PatternMatcher},
 };
@@ -55,6 +61,19 @@ fn create_test_files(files: &[(&str, &str)]) -> TempDir {
     temp_dir
 }
 
+/// Read a single entry from a ZIP archive as text
+fn read_zip_entry(zip_path: &Path, name: &str) -> String {
+    let zip_file = fs::File::open(zip_path).expect("Failed to open ZIP");
+    let mut archive = zip::ZipArchive::new(zip_file).expect("Failed to read ZIP");
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .expect("Entry should be archived")
+        .read_to_string(&mut content)
+        .expect("Failed to read ZIP entry");
+    content
+}
+
 /// Create a test configuration
 fn create_test_config(input_path: PathBuf, output_path: PathBuf, eda: EdaType) -> Config {
     Config {
@@ -67,6 +86,7 @@ fn create_test_config(input_path: PathBuf, output_path: PathBuf, eda: EdaType) -
         no_progress: true, // Disable progress bars in tests
         top_color_image: None,
         bottom_color_image: None,
+        ..Default::default()
     }
 }
 
@@ -164,6 +184,315 @@ fn test_auto_detection_protel() {
     assert_eq!(patterns.name, "Protel");
 }
 
+#[test]
+fn test_multi_eda_converts_each_group() {
+    let mut mixed_files: Vec<(&str, &str)> = KICAD_TEST_FILES.to_vec();
+    mixed_files.extend_from_slice(PROTEL_TEST_FILES);
+
+    let temp_input = create_test_files(&mixed_files);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().to_path_buf(),
+        EdaType::Auto,
+    );
+    config.multi_eda = true;
+
+    let mut converter = Converter::new(config);
+    converter
+        .run()
+        .expect("Multi-EDA conversion should succeed");
+
+    for group in ["KiCad", "Protel"] {
+        let group_dir = temp_output.path().join(group);
+        assert!(
+            group_dir.join("Gerber_TopLayer.GTL").exists(),
+            "{} group should produce a top copper layer",
+            group
+        );
+        assert!(group_dir.join("Gerber_BoardOutlineLayer.GKO").exists());
+    }
+
+    let stats = converter.get_conversion_stats();
+    assert!(stats.total_files_processed >= 8);
+}
+
+#[test]
+fn test_multi_eda_groups_report_to_the_listener() {
+    let mut mixed_files: Vec<(&str, &str)> = KICAD_TEST_FILES.to_vec();
+    mixed_files.extend_from_slice(PROTEL_TEST_FILES);
+
+    let temp_input = create_test_files(&mixed_files);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().to_path_buf(),
+        EdaType::Auto,
+    );
+    config.multi_eda = true;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut converter = Converter::new(config).with_event_sender(tx);
+    converter
+        .run()
+        .expect("Multi-EDA conversion should succeed");
+    drop(converter);
+
+    let events: Vec<ConversionEvent> = rx.iter().collect();
+    let written: BTreeSet<PathBuf> = events
+        .iter()
+        .filter_map(|event| match event {
+            ConversionEvent::LayerWritten { path, .. } => path.parent().map(Path::to_path_buf),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        written,
+        BTreeSet::from([
+            temp_output.path().join("KiCad"),
+            temp_output.path().join("Protel"),
+        ])
+    );
+    let started = events
+        .iter()
+        .filter(|event| matches!(event, ConversionEvent::Started))
+        .count();
+    assert_eq!(started, 1);
+    assert!(matches!(events.last(), Some(ConversionEvent::Finished(_))));
+}
+
+#[test]
+fn test_multi_eda_groups_honour_the_cancel_flag() {
+    let mut mixed_files: Vec<(&str, &str)> = KICAD_TEST_FILES.to_vec();
+    mixed_files.extend_from_slice(PROTEL_TEST_FILES);
+
+    let temp_input = create_test_files(&mixed_files);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().to_path_buf(),
+        EdaType::Auto,
+    );
+    config.multi_eda = true;
+
+    let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let mut converter = Converter::new(config).with_cancel_flag(cancel);
+    let err = converter
+        .run()
+        .expect_err("Cancelled multi-EDA conversion should fail");
+
+    assert!(matches!(
+        err.downcast_ref::<TransJLC::TransJlcError>(),
+        Some(TransJLC::TransJlcError::Cancelled)
+    ));
+    for group in ["KiCad", "Protel"] {
+        assert!(!temp_output
+            .path()
+            .join(group)
+            .join("Gerber_TopLayer.GTL")
+            .exists());
+    }
+}
+
+#[test]
+fn test_multi_eda_with_zip_path_writes_one_archive_per_group() {
+    let mut mixed_files: Vec<(&str, &str)> = KICAD_TEST_FILES.to_vec();
+    mixed_files.extend_from_slice(PROTEL_TEST_FILES);
+
+    let temp_input = create_test_files(&mixed_files);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().to_path_buf(),
+        EdaType::Auto,
+    );
+    config.multi_eda = true;
+    config.zip_path = Some(temp_output.path().join("gerbers.zip"));
+
+    let mut converter = Converter::new(config);
+    converter
+        .run()
+        .expect("Multi-EDA conversion should succeed");
+
+    assert!(!temp_output.path().join("gerbers.zip").exists());
+    for group in ["KiCad", "Protel"] {
+        let archive = temp_output.path().join(format!("gerbers-{}.zip", group));
+        let file = fs::File::open(&archive).expect("Group archive should exist");
+        let mut zip = zip::ZipArchive::new(file).expect("Group archive should be a ZIP");
+        assert!(
+            zip.by_name("Gerber_TopLayer.GTL").is_ok(),
+            "{} archive should contain the top copper layer",
+            group
+        );
+    }
+}
+
+#[test]
+fn test_zip_path_override() {
+    let temp_input = create_test_files(KICAD_TEST_FILES);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+    let zip_path = temp_output.path().join("release").join("board-rev-b.zip");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().join("output"),
+        EdaType::KiCad,
+    );
+    config.zip_path = Some(zip_path.clone());
+
+    let mut converter = Converter::new(config);
+    converter.run().expect("Conversion should succeed");
+
+    assert!(
+        zip_path.exists(),
+        "ZIP should be written to the explicit path"
+    );
+    assert!(!temp_output.path().join("output").join("test.zip").exists());
+    assert_eq!(converter.get_conversion_stats().output_format, "ZIP");
+}
+
+#[test]
+fn test_deterministic_zip_is_byte_identical() {
+    let temp_input = create_test_files(KICAD_TEST_FILES);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+    let mut archives = Vec::new();
+    for run in ["first", "second"] {
+        let zip_path = temp_output.path().join(format!("{}.zip", run));
+        let mut config = create_test_config(
+            temp_input.path().to_path_buf(),
+            temp_output.path().join(run),
+            EdaType::KiCad,
+        );
+        config.zip_path = Some(zip_path.clone());
+        config.deterministic = true;
+
+        Converter::new(config)
+            .run()
+            .expect("Conversion should succeed");
+        archives.push(fs::read(&zip_path).expect("Failed to read ZIP"));
+
+        // Cross a second boundary so non-deterministic timestamps would differ
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+    }
+
+    assert_eq!(archives[0], archives[1]);
+}
+
+#[test]
+fn test_no_hash_keeps_aperture_count() {
+    let top =
+        "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\n%ADD11C,0.200000*%\nD10*\nX0Y0D02*\nM02*\n";
+    let files = [
+        ("project-F_Cu.gbr", top),
+        ("project-B_Cu.gbr", top),
+        ("project-Edge_Cuts.gbr", top),
+    ];
+    let count_apertures = |content: &str| {
+        content
+            .lines()
+            .filter(|line| line.starts_with("%ADD"))
+            .count()
+    };
+
+    for (no_hash, extra) in [(true, 0), (false, 1)] {
+        let temp_input = create_test_files(&files);
+        let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+        let mut config = create_test_config(
+            temp_input.path().to_path_buf(),
+            temp_output.path().to_path_buf(),
+            EdaType::KiCad,
+        );
+        config.no_hash = no_hash;
+        config.zip = true;
+
+        Converter::new(config)
+            .run()
+            .expect("Conversion should succeed");
+
+        let output = read_zip_entry(&temp_output.path().join("test.zip"), "Gerber_TopLayer.GTL");
+        assert_eq!(count_apertures(&output), count_apertures(top) + extra);
+    }
+}
+
+#[test]
+fn test_zip_input_skips_macos_junk() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let input_zip = temp_dir.path().join("gerbers.zip");
+
+    let mut writer = zip::ZipWriter::new(fs::File::create(&input_zip).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, content) in KICAD_TEST_FILES {
+        writer.start_file(*name, options).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        writer
+            .start_file(format!("__MACOSX/._{}", name), options)
+            .unwrap();
+        writer.write_all(b"\0\x05\x16\x07").unwrap();
+    }
+    writer.start_file(".DS_Store", options).unwrap();
+    writer.write_all(b"Bud1").unwrap();
+    writer.finish().unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let config = create_test_config(input_zip, output_dir.clone(), EdaType::Auto);
+    let mut converter = Converter::new(config);
+    converter.run().expect("Conversion should succeed");
+
+    assert!(output_dir.join("Gerber_TopLayer.GTL").exists());
+    for entry in fs::read_dir(&output_dir).unwrap() {
+        let name = entry.unwrap().file_name();
+        let name = name.to_string_lossy();
+        assert!(
+            !name.starts_with('.') && name != "__MACOSX",
+            "junk entry {} leaked into the output",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_max_hash_size_skips_large_files() {
+    let small = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.100000*%\nD10*\nX0Y0D02*\nM02*\n";
+    let large = small.replace("M02*", &"X1000Y1000D01*\n".repeat(200)) + "M02*\n";
+    let temp_input = create_test_files(&[
+        ("project-F_Cu.gbr", large.as_str()),
+        ("project-B_Cu.gbr", small),
+        ("project-Edge_Cuts.gbr", small),
+    ]);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().to_path_buf(),
+        EdaType::KiCad,
+    );
+    config.zip = true;
+    config.max_hash_size = 1024;
+
+    Converter::new(config)
+        .run()
+        .expect("Conversion should succeed");
+
+    let zip_path = temp_output.path().join("test.zip");
+    let count_apertures = |content: &str| {
+        content
+            .lines()
+            .filter(|line| line.starts_with("%ADD"))
+            .count()
+    };
+
+    let top = read_zip_entry(&zip_path, "Gerber_TopLayer.GTL");
+    let bottom = read_zip_entry(&zip_path, "Gerber_BottomLayer.GBL");
+    assert_eq!(count_apertures(&top), 1, "large file should not be hashed");
+    assert_eq!(count_apertures(&bottom), 2, "small file should be hashed");
+}
+
 #[test]
 fn test_gerber_processor_g54_prefix_conversion() {
     let processor = GerberProcessor::new().with_ignore_hash(true);
@@ -307,12 +636,86 @@ fn test_config_eda_type_parsing() {
             no_progress: false,
             top_color_image: None,
             bottom_color_image: None,
+            ..Default::default()
         };
 
         assert_eq!(config.get_eda_type(), expected);
     }
 }
 
+#[test]
+fn test_convert_layers_in_memory() {
+    let buffers: HashMap<String, Vec<u8>> = KICAD_TEST_FILES
+        .iter()
+        .map(|(name, content)| (name.to_string(), content.as_bytes().to_vec()))
+        .chain([("README.md".to_string(), b"not a layer".to_vec())])
+        .collect();
+
+    let outputs = convert_layers(buffers, EdaType::Auto).expect("In-memory conversion failed");
+
+    let names: BTreeSet<&str> = outputs.keys().map(String::as_str).collect();
+    assert_eq!(
+        names,
+        BTreeSet::from([
+            "Drill_PTH_Through.DRL",
+            "Gerber_BoardOutlineLayer.GKO",
+            "Gerber_BottomLayer.GBL",
+            "Gerber_TopLayer.GTL",
+            "Gerber_TopSolderMaskLayer.GTS",
+        ])
+    );
+
+    let top = String::from_utf8(outputs["Gerber_TopLayer.GTL"].clone()).unwrap();
+    assert!(top.starts_with("G04 EasyEDA Pro"));
+    assert!(top.contains("G04 KiCad test*"));
+    assert_eq!(
+        outputs["Drill_PTH_Through.DRL"],
+        b"T1C0.8\nX100Y100\nT0\nM30\n".to_vec()
+    );
+
+    // Custom matchers need a pattern file and are not available in memory
+    assert!(convert_layers(HashMap::new(), EdaType::Custom("mine".to_string())).is_err());
+}
+
+#[test]
+fn test_colorful_silkscreen_lands_in_zip() {
+    let temp_input = create_test_files(&[
+        (
+            "project-F_Cu.gbr",
+            "G04 KiCad test*\nG01*\nD10*\nG04 End*\n",
+        ),
+        (
+            "project-Edge_Cuts.gbr",
+            "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX40000000Y0D01*\n\
+             X40000000Y20000000D01*\nX0Y20000000D01*\nX0Y0D01*\nM02*\n",
+        ),
+    ]);
+    let temp_output = TempDir::new().expect("Failed to create output temp dir");
+    let image_path = temp_input.path().join("logo.png");
+    image::RgbImage::from_pixel(8, 4, image::Rgb([220, 40, 40]))
+        .save(&image_path)
+        .expect("Failed to write image");
+
+    let mut config = create_test_config(
+        temp_input.path().to_path_buf(),
+        temp_output.path().to_path_buf(),
+        EdaType::KiCad,
+    );
+    config.zip = true;
+    config.top_color_image = Some(image_path);
+
+    let mut converter = Converter::new(config);
+    converter.run().expect("Conversion should succeed");
+
+    let zip_file = fs::File::open(temp_output.path().join("test.zip")).expect("ZIP should exist");
+    let archive = zip::ZipArchive::new(zip_file).expect("Failed to read ZIP");
+    let names: BTreeSet<&str> = archive.file_names().collect();
+    assert!(names.contains("Fabrication_ColorfulTopSilkscreen.FCTS"));
+    assert!(names.contains("Fabrication_ColorfulBoardOutlineLayer.FCBO"));
+    assert!(names.contains("Fabrication_ColorfulBoardOutlineMark.FCBM"));
+    assert!(!names.contains("Fabrication_ColorfulBottomSilkscreen.FCBS"));
+}
+
 // Performance benchmark test (optional)
 #[test]
 fn test_large_file_processing_performance() {
@@ -1,7 +1,10 @@
 use std::fs;
 use std::path::Path;
 
-use TransJLC::colorful::parse_solder_mask;
+use TransJLC::colorful::{
+    mask::parse_solder_mask_with_arc_segments, parse_solder_mask, ColorfulOptions,
+    ColorfulSilkscreenGenerator,
+};
 
 #[test]
 fn parse_sample_solder_mask_generates_paths() {
@@ -10,3 +13,226 @@ fn parse_sample_solder_mask_generates_paths() {
     let paths = parse_solder_mask(&txt).expect("parse mask");
     assert!(!paths.is_empty(), "expected parsed solder mask paths");
 }
+
+/// Size of the top colorful silkscreen generated for a 10 mm board, with the
+/// given solder mask content carving its openings
+fn top_silkscreen_len(dir: &Path, mask: Option<&str>) -> u64 {
+    let outline = dir.join("outline.gbr");
+    fs::write(
+        &outline,
+        "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX10000000Y0D01*\n\
+         X10000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n",
+    )
+    .expect("write outline");
+    let image = dir.join("top.svg");
+    fs::write(
+        &image,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"/>"#,
+    )
+    .expect("write image");
+    let top_solder_mask = mask.map(|content| {
+        let path = dir.join("mask.gbr");
+        fs::write(&path, content).expect("write mask");
+        path
+    });
+
+    let output_dir = dir.join("output");
+    ColorfulSilkscreenGenerator::new(ColorfulOptions {
+        top_image: Some(image),
+        top_solder_mask,
+        ..Default::default()
+    })
+    .generate(&outline, &output_dir)
+    .expect("generate colorful silkscreen");
+    fs::metadata(output_dir.join("Fabrication_ColorfulTopSilkscreen.FCTS"))
+        .expect("top silkscreen written")
+        .len()
+}
+
+#[test]
+fn exported_generator_cuts_solder_mask_openings() {
+    // The colorful module is the single implementation, so its generator
+    // honours the solder mask option
+    let plain = top_silkscreen_len(tempfile::tempdir().expect("temp dir").path(), None);
+    let masked = top_silkscreen_len(
+        tempfile::tempdir().expect("temp dir").path(),
+        Some(&region_mask("C")),
+    );
+    let mask_paths: usize = parse_solder_mask(&region_mask("C"))
+        .expect("parse mask")
+        .iter()
+        .map(String::len)
+        .sum();
+    assert!(mask_paths > 0);
+    assert_eq!(masked, plain + mask_paths as u64);
+}
+
+/// Parse the points of a `M x y L x y ... Z` path
+fn path_points(path: &str) -> Vec<(String, String)> {
+    let numbers: Vec<String> = path
+        .split_whitespace()
+        .filter(|token| !matches!(*token, "M" | "L" | "Z"))
+        .map(|token| token.trim_end_matches('Z').to_string())
+        .collect();
+    numbers
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect()
+}
+
+fn region_mask(polarity: &str) -> String {
+    format!(
+        "%FSLAX46Y46*%\n%MOMM*%\n%LPD*%\nG36*\nX0Y0D02*\nG01*\nX10000000Y0D01*\nX10000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nG37*\n%LP{}*%\nG36*\nX2000000Y2000000D02*\nX8000000Y2000000D01*\nX8000000Y8000000D01*\nX2000000Y8000000D01*\nX2000000Y2000000D01*\nG37*\nM02*\n",
+        polarity
+    )
+}
+
+#[test]
+fn clear_polarity_shapes_do_not_open_the_mask() {
+    let dark = parse_solder_mask(&region_mask("D")).expect("parse dark mask");
+    let clear = parse_solder_mask(&region_mask("C")).expect("parse clear mask");
+    assert_eq!(dark.len(), 2);
+    assert_eq!(clear, dark[..1], "clear shapes are dropped");
+
+    // The clear square renders exactly like a mask without it, instead of
+    // toggling the evenodd clip inside the dark opening
+    let outer_only = region_mask("C")
+        .split_once("%LPC*%")
+        .map(|(dark, _)| format!("{dark}M02*\n"))
+        .expect("split mask");
+    let render =
+        |mask: &str| top_silkscreen_len(tempfile::tempdir().expect("temp dir").path(), Some(mask));
+    assert_eq!(render(&region_mask("C")), render(&outer_only));
+    assert_ne!(render(&region_mask("C")), render(&region_mask("D")));
+}
+
+#[test]
+fn polygon_aperture_flash_generates_path() {
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10P,1.0X6*%\n%ADD11C,0.5*%\nD10*\nX5000000Y5000000D03*\nD11*\nX8000000Y5000000D03*\nM02*\n";
+    let paths = parse_solder_mask(content).expect("parse mask");
+
+    assert_eq!(paths.len(), 2, "polygon flash must not be skipped");
+
+    // A hexagon of diameter 1 mm spans 1 mm across its vertices
+    let xs: Vec<f64> = path_points(&paths[0])
+        .iter()
+        .map(|(x, _)| x.parse().expect("numeric x"))
+        .collect();
+    let width = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+        - xs.iter().cloned().fold(f64::INFINITY, f64::min);
+    assert!((width - 1.0 / 0.254).abs() < 1e-6);
+}
+
+fn single_flash(aperture: &str) -> String {
+    let content = format!(
+        "%FSLAX46Y46*%\n%MOMM*%\n%ADD10{}*%\nD10*\nX5000000Y5000000D03*\nM02*\n",
+        aperture
+    );
+    let paths = parse_solder_mask(&content).expect("parse mask");
+    assert_eq!(paths.len(), 1);
+    paths[0].clone()
+}
+
+fn commands(path: &str) -> Vec<&str> {
+    path.split_whitespace()
+        .filter(|token| token.chars().all(|c| c.is_ascii_alphabetic()))
+        .collect()
+}
+
+#[test]
+fn circle_flash_is_drawn_with_arcs() {
+    let circle = single_flash("C,1.0");
+    let rectangle = single_flash("R,1.0X1.0");
+
+    assert_eq!(commands(&circle), vec!["M", "A", "A", "Z"]);
+    assert_eq!(commands(&rectangle), vec!["M", "L"]);
+}
+
+#[test]
+fn obround_flash_is_a_stadium() {
+    let obround = single_flash("O,2.0X1.0");
+    assert_eq!(commands(&obround), vec!["M", "L", "A", "L", "A", "Z"]);
+}
+
+/// Region of a 10 mm wide half disc: a straight base and a CCW arc back over the top
+const HALF_DISC_MASK: &str = "%FSLAX46Y46*%\n%MOMM*%\nG75*\nG36*\nX0Y0D02*\nG01*\nX10000000Y0D01*\nG03*\nX0Y0I-5000000J0D01*\nG01*\nG37*\nM02*\n";
+
+#[test]
+fn region_arcs_are_linearized() {
+    let paths = parse_solder_mask(HALF_DISC_MASK).expect("parse mask");
+    assert_eq!(paths.len(), 1);
+
+    let points = path_points(&paths[0]);
+    assert!(points.len() > 10, "arc should not collapse to a chord");
+
+    // The arc bulges to y = 5 mm, which is -5 mm in SVG space
+    let top = points
+        .iter()
+        .map(|(_, y)| y.parse::<f64>().expect("numeric y"))
+        .fold(f64::INFINITY, f64::min);
+    assert!((top + 5.0 / 0.254).abs() < 0.01);
+}
+
+#[test]
+fn arc_segment_count_is_configurable() {
+    let coarse = parse_solder_mask_with_arc_segments(HALF_DISC_MASK, 8).expect("parse mask");
+    let fine = parse_solder_mask_with_arc_segments(HALF_DISC_MASK, 128).expect("parse mask");
+
+    // Start, base end, then one point per segment of the half turn
+    assert_eq!(path_points(&coarse[0]).len(), 2 + 4);
+    assert_eq!(path_points(&fine[0]).len(), 2 + 64);
+}
+
+/// Width and height of a path from the extent of its points
+fn path_size(path: &str) -> (f64, f64) {
+    let points: Vec<(f64, f64)> = path_points(path)
+        .iter()
+        .map(|(x, y)| (x.parse().expect("numeric x"), y.parse().expect("numeric y")))
+        .collect();
+    let extent = |values: Vec<f64>| {
+        values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+            - values.iter().cloned().fold(f64::INFINITY, f64::min)
+    };
+    (
+        extent(points.iter().map(|p| p.0).collect()),
+        extent(points.iter().map(|p| p.1).collect()),
+    )
+}
+
+/// KiCad's rotated rectangle and four-corner outline macros
+const KICAD_MACROS: &str = "%AMRotRect*\n0 Rectangle, with rotation*\n0 $1 length*\n0 $2 width*\n\
+0 $3 Rotation angle, in degrees counterclockwise*\n21,1,$1,$2,0,0,$3*%\n\
+%AMOutline4P*\n0 Free polygon, 4 corners , with rotation*\n0 $1 to $8 corner X, Y*\n\
+0 $9 Rotation angle, in degrees counterclockwise*\n4,1,4,$1,$2,$3,$4,$5,$6,$7,$8,$1,$2,$9*%\n";
+
+#[test]
+fn rotated_macro_flashes_use_the_rotated_size() {
+    let content = format!(
+        "%FSLAX46Y46*%\n%MOMM*%\n{KICAD_MACROS}%ADD10RotRect,2.0X1.0X90.0*%\n\
+         %ADD11Outline4P,-1.0X-0.5X1.0X-0.5X1.0X0.5X-1.0X0.5X90.0*%\n\
+         D10*\nX5000000Y5000000D03*\nD11*\nX8000000Y5000000D03*\nM02*\n"
+    );
+    let paths = parse_solder_mask(&content).expect("parse mask");
+    assert_eq!(paths.len(), 2);
+
+    // A 2 x 1 mm rectangle turned 90° is 1 mm wide and 2 mm tall; the
+    // rotation angle is not mistaken for a size
+    for path in &paths {
+        let (width, height) = path_size(path);
+        assert!((width - 1.0 / 0.254).abs() < 1e-6, "width {width}");
+        assert!((height - 2.0 / 0.254).abs() < 1e-6, "height {height}");
+    }
+}
+
+#[test]
+fn macro_flash_without_known_size_is_skipped() {
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%AMShifted*\n21,1,$1,$2,$3,0,0*%\n\
+                   %ADD10Shifted,1.0X1.0*%\n%ADD11Undefined,5.0*%\n%ADD12C,0.5*%\n\
+                   D10*\nX5000000Y5000000D03*\nD11*\nX6000000Y5000000D03*\n\
+                   D12*\nX8000000Y5000000D03*\nM02*\n";
+    let paths = parse_solder_mask(content).expect("parse mask");
+
+    // Only the circle is drawn: $3 is not set for D10 and D11 has no definition
+    assert_eq!(paths.len(), 1);
+    assert_eq!(commands(&paths[0]), vec!["M", "A", "A", "Z"]);
+}